@@ -2,9 +2,14 @@ use iced::Task;
 
 use crate::app::message::Message;
 use crate::app::state::{
-    App, AuthorLoadingState, LoadingState, NotificationKind, ReviewsLoadingState,
+    App, AuthorLoadingState, LoadingState, NotificationKind, ReviewsLoadingState, ScriptOutputState,
 };
-use crate::domain::{AuthorProfile, InstalledModule, ModuleUuid, RegistryIndex, ReviewsResponse};
+use crate::domain::{
+    AuthorProfile, ExternalModule, InstalledModule, ModuleUuid, RegistryIndex, ReviewsResponse,
+};
+use crate::services::UninstallResult;
+use crate::services::waybar_config::BarInfo;
+use crate::tasks;
 
 pub fn handle_registry_loaded(
     app: &mut App,
@@ -58,14 +63,37 @@ pub fn handle_installed_loaded(
         Ok(modules) => {
             app.installed_uuids = modules.iter().map(|m| m.uuid.to_string()).collect();
             app.installed_modules = modules;
+            Task::batch([tasks::load_external_modules(), tasks::check_drift()])
         }
         Err(e) => {
             app.push_notification(
                 format!("Failed to load installed modules: {e}"),
                 NotificationKind::Error,
             );
+            Task::none()
         }
     }
+}
+
+pub fn handle_external_modules_loaded(
+    app: &mut App,
+    result: Result<Vec<ExternalModule>, String>,
+) -> Task<Message> {
+    match result {
+        Ok(modules) => app.installed.external = modules,
+        Err(e) => tracing::debug!("Could not list external modules: {e}"),
+    }
+    Task::none()
+}
+
+pub fn handle_waybar_bars_loaded(
+    app: &mut App,
+    result: Result<Vec<BarInfo>, String>,
+) -> Task<Message> {
+    match result {
+        Ok(bars) => app.installed.bars = bars,
+        Err(e) => tracing::debug!("Could not list waybar bars: {e}"),
+    }
     Task::none()
 }
 
@@ -115,14 +143,39 @@ pub fn handle_toggle_completed(
 
 pub fn handle_uninstall_completed(
     app: &mut App,
-    result: Result<String, (String, String)>,
+    result: Result<UninstallResult, (String, String)>,
 ) -> Task<Message> {
     match result {
-        Ok(uuid) => {
+        Ok(UninstallResult { uuid, script }) => {
             app.installed.uninstalling.remove(&uuid);
             app.installed_uuids.remove(&uuid);
             app.installed_modules.retain(|m| m.uuid.to_string() != uuid);
-            app.push_notification("Module uninstalled".to_string(), NotificationKind::Success);
+
+            match script {
+                Some(result) => {
+                    if result.success {
+                        app.push_notification(
+                            "Module uninstalled".to_string(),
+                            NotificationKind::Success,
+                        );
+                    } else {
+                        app.push_notification(
+                            "Module uninstalled, but its uninstall script failed".to_string(),
+                            NotificationKind::Warning,
+                        );
+                    }
+                    app.script_output = Some(ScriptOutputState {
+                        title: format!("Uninstalled {uuid}"),
+                        result,
+                    });
+                }
+                None => {
+                    app.push_notification(
+                        "Module uninstalled".to_string(),
+                        NotificationKind::Success,
+                    );
+                }
+            }
         }
         Err((uuid, e)) => {
             app.installed.uninstalling.remove(&uuid);
@@ -132,6 +185,10 @@ pub fn handle_uninstall_completed(
     Task::none()
 }
 
+pub fn handle_close_script_output(app: &mut App) {
+    app.script_output = None;
+}
+
 pub fn handle_author_loaded(app: &mut App, result: Result<AuthorProfile, String>) -> Task<Message> {
     match result {
         Ok(profile) => {
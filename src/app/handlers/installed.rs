@@ -1,9 +1,13 @@
 use iced::Task;
 
 use crate::app::message::Message;
-use crate::app::state::{App, NotificationKind};
-use crate::domain::InstalledModule;
-use crate::tasks;
+use crate::app::state::{App, ChangePreviewState, NotificationKind, ScriptReviewState};
+use crate::domain::{
+    BarSection, ExternalModule, InstalledModule, ModuleMove, ModulePosition, ModuleUuid,
+    ModuleVersion,
+};
+use crate::services::{ConfigPreview, Drift, ScriptApproval, ScriptReview};
+use crate::tasks::{self, PendingChange, UpdateRequest};
 
 pub fn handle_install_module(app: &mut App, uuid: crate::domain::ModuleUuid) -> Task<Message> {
     let uuid_str = uuid.to_string();
@@ -15,16 +19,20 @@ pub fn handle_install_module(app: &mut App, uuid: crate::domain::ModuleUuid) ->
         app.module_detail.installing = false;
         return Task::none();
     }
+    start_install(app, &uuid_str, ScriptApproval::IfClean)
+}
 
+fn start_install(app: &mut App, uuid_str: &str, scripts: ScriptApproval) -> Task<Message> {
     if let Some(registry) = &app.registry
-        && let Some(module) = registry.find_by_uuid(&uuid_str)
+        && let Some(module) = registry.find_by_uuid(uuid_str)
     {
         return tasks::install_module(
-            uuid_str,
+            uuid_str.to_string(),
             module.name.clone(),
             module.version.clone(),
             module.repo_url.clone(),
             module.checksum.clone(),
+            scripts,
         );
     }
 
@@ -43,14 +51,238 @@ pub fn handle_toggle_module(
 ) -> Task<Message> {
     let uuid_str = uuid.to_string();
     app.installed.toggling.insert(uuid_str.clone());
-    tasks::toggle_module(uuid_str, enabled)
+    tasks::preview_change(PendingChange::Toggle {
+        uuid: uuid_str,
+        enabled,
+    })
 }
 
 pub fn handle_set_module_position(
     uuid: crate::domain::ModuleUuid,
     section: crate::domain::BarSection,
+    bar: Option<String>,
+) -> Task<Message> {
+    tasks::preview_change(PendingChange::Position {
+        uuid: uuid.to_string(),
+        position: ModulePosition::new(section).on_bar(bar),
+    })
+}
+
+pub fn handle_change_previewed(
+    app: &mut App,
+    change: PendingChange,
+    result: Result<ConfigPreview, String>,
 ) -> Task<Message> {
-    tasks::change_module_position(uuid.to_string(), section)
+    match result {
+        Ok(preview) if preview.is_empty() => change.apply(ScriptApproval::IfClean),
+        Ok(preview) => {
+            app.change_preview = Some(ChangePreviewState::new(change, preview));
+            Task::none()
+        }
+        Err(e) => {
+            clear_pending(app, &change);
+            app.push_notification(
+                format!("{} failed: {e}", change.title()),
+                NotificationKind::Error,
+            );
+            Task::none()
+        }
+    }
+}
+
+pub fn handle_confirm_change(app: &mut App) -> Task<Message> {
+    match app.change_preview.take() {
+        Some(state) if state.can_apply() => {
+            let scripts = if state.scripts_approved {
+                ScriptApproval::Approved
+            } else {
+                ScriptApproval::IfClean
+            };
+            state.change.apply(scripts)
+        }
+        Some(state) => {
+            clear_pending(app, &state.change);
+            Task::none()
+        }
+        None => Task::none(),
+    }
+}
+
+pub fn handle_cancel_change(app: &mut App) {
+    if let Some(state) = app.change_preview.take() {
+        clear_pending(app, &state.change);
+    }
+}
+
+pub fn handle_scripts_need_review(
+    app: &mut App,
+    uuid: String,
+    review: ScriptReview,
+) -> Task<Message> {
+    match ModuleUuid::try_from(uuid.as_str()) {
+        Ok(uuid) => {
+            app.script_review = Some(ScriptReviewState {
+                uuid,
+                review,
+                approved: false,
+            });
+        }
+        Err(e) => {
+            app.module_detail.installing = false;
+            app.push_notification(format!("Installation failed: {e}"), NotificationKind::Error);
+        }
+    }
+    Task::none()
+}
+
+/// Ticks or clears the approval box in whichever review is open.
+pub fn handle_approve_scripts(app: &mut App, approved: bool) {
+    if let Some(state) = &mut app.change_preview {
+        state.scripts_approved = approved;
+    } else if let Some(state) = &mut app.script_review {
+        state.approved = approved;
+    }
+}
+
+pub fn handle_confirm_script_review(app: &mut App) -> Task<Message> {
+    match app.script_review.take() {
+        Some(state) if state.approved => {
+            start_install(app, &state.uuid.to_string(), ScriptApproval::Approved)
+        }
+        Some(state) => {
+            app.script_review = Some(state);
+            Task::none()
+        }
+        None => Task::none(),
+    }
+}
+
+pub fn handle_cancel_script_review(app: &mut App) {
+    if app.script_review.take().is_some() {
+        app.module_detail.installing = false;
+    }
+}
+
+fn clear_pending(app: &mut App, change: &PendingChange) {
+    app.installed.toggling.remove(change.uuid());
+    app.installed.updating.remove(change.uuid());
+}
+
+/// Reloads everything read from disk after the Waybar files, `installed.json`
+/// or a module directory changed outside Barforge. Loading the installed
+/// modules checks for drift again.
+pub fn handle_waybar_files_changed(app: &mut App) -> Task<Message> {
+    Task::batch([
+        tasks::load_installed(),
+        tasks::load_waybar_bars(),
+        tasks::load_bar_layout(app.layout.bar.clone()),
+    ])
+}
+
+pub fn handle_toggle_external(module: ExternalModule, enabled: bool) -> Task<Message> {
+    tasks::toggle_external(module, enabled)
+}
+
+pub fn handle_move_external(module: ExternalModule, section: BarSection) -> Task<Message> {
+    tasks::move_external(module, section)
+}
+
+pub fn handle_adopt_external(module: ExternalModule) -> Task<Message> {
+    tasks::adopt_external(module)
+}
+
+pub fn handle_external_changed(app: &mut App, result: Result<String, String>) -> Task<Message> {
+    match result {
+        Ok(message) => {
+            app.push_notification(message, NotificationKind::Success);
+            Task::batch([
+                tasks::load_installed(),
+                tasks::load_bar_layout(app.layout.bar.clone()),
+            ])
+        }
+        Err(e) => {
+            app.push_notification(
+                format!("Failed to update external module: {e}"),
+                NotificationKind::Error,
+            );
+            tasks::load_external_modules()
+        }
+    }
+}
+
+pub fn handle_drift_checked(app: &mut App, result: Result<Vec<Drift>, String>) -> Task<Message> {
+    match result {
+        Ok(drift) => app.installed.drift = drift,
+        Err(e) => tracing::debug!("Could not check for drift: {e}"),
+    }
+    Task::none()
+}
+
+pub fn handle_fix_drift(app: &mut App, drift: Drift) -> Task<Message> {
+    if app.installed.fixing_drift.is_some() {
+        return Task::none();
+    }
+    app.installed.fixing_drift = Some(drift.clone());
+    tasks::fix_drift(drift)
+}
+
+pub fn handle_drift_fixed(
+    app: &mut App,
+    drift: Drift,
+    result: Result<(), String>,
+) -> Task<Message> {
+    app.installed.fixing_drift = None;
+    match result {
+        Ok(()) => app.push_notification(format!("Fixed: {drift}"), NotificationKind::Success),
+        Err(e) => app.push_notification(
+            format!("Failed to fix {}: {e}", drift.uuid()),
+            NotificationKind::Error,
+        ),
+    }
+    tasks::load_installed()
+}
+
+pub fn handle_move_module(uuid: ModuleUuid, to: ModuleMove) -> Task<Message> {
+    tasks::preview_change(PendingChange::Move {
+        uuid: uuid.to_string(),
+        to,
+    })
+}
+
+pub fn handle_drag_module(app: &mut App, uuid: ModuleUuid) -> Task<Message> {
+    app.installed.drag_over = Some(uuid.clone());
+    app.installed.dragging = Some(uuid);
+    Task::none()
+}
+
+pub fn handle_drag_over(app: &mut App, target: ModuleUuid) -> Task<Message> {
+    app.installed.drag_over = Some(target);
+    Task::none()
+}
+
+pub fn handle_drag_leave(app: &mut App, target: ModuleUuid) -> Task<Message> {
+    if app.installed.drag_over.as_ref() == Some(&target) {
+        app.installed.drag_over = None;
+    }
+    Task::none()
+}
+
+pub fn handle_drag_released(app: &mut App) -> Task<Message> {
+    if let Some(from) = app.layout.dragging.take() {
+        return match app.layout.drag_over.take() {
+            Some(to) => super::handle_layout_move_entry(app, from, to),
+            None => Task::none(),
+        };
+    }
+
+    let dragging = app.installed.dragging.take();
+    let target = app.installed.drag_over.take();
+    match (dragging, target) {
+        (Some(uuid), Some(target)) if uuid != target => {
+            handle_move_module(uuid, ModuleMove::Onto(target.to_string()))
+        }
+        _ => Task::none(),
+    }
 }
 
 pub fn handle_position_changed(app: &mut App, result: Result<String, String>) -> Task<Message> {
@@ -97,11 +329,15 @@ pub fn handle_update_module(app: &mut App, uuid: crate::domain::ModuleUuid) -> T
         && let Some(new_version) = &registry_module.version
     {
         app.installed.updating.insert(uuid_str.clone());
-        return tasks::update_module(
-            uuid_str,
-            registry_module.repo_url.clone(),
-            new_version.clone(),
-        );
+        return tasks::preview_change(PendingChange::Update {
+            request: UpdateRequest {
+                uuid: uuid_str,
+                repo_url: registry_module.repo_url.clone(),
+                version: new_version.clone(),
+                checksum: registry_module.checksum.clone(),
+            },
+            allow_unsigned: app.allow_unsigned_updates,
+        });
     }
     app.push_notification(
         "Cannot update: module not found".to_string(),
@@ -128,7 +364,12 @@ pub fn handle_update_all_modules(app: &mut App) -> Task<Message> {
                     .and_then(|reg_mod| {
                         reg_mod.version.as_ref().and_then(|new_ver| {
                             if new_ver > &installed.version {
-                                Some((uuid, reg_mod.repo_url.clone(), new_ver.clone()))
+                                Some(UpdateRequest {
+                                    uuid,
+                                    repo_url: reg_mod.repo_url.clone(),
+                                    version: new_ver.clone(),
+                                    checksum: reg_mod.checksum.clone(),
+                                })
                             } else {
                                 None
                             }
@@ -147,7 +388,7 @@ pub fn handle_update_all_modules(app: &mut App) -> Task<Message> {
     }
 
     app.installed.updating_all = true;
-    tasks::update_all_modules(updates)
+    tasks::update_all_modules(updates, app.allow_unsigned_updates)
 }
 
 pub fn handle_update_completed(
@@ -166,6 +407,7 @@ pub fn handle_update_completed(
             {
                 existing.version = updated_module.version;
                 existing.registry_version = updated_module.registry_version;
+                existing.previous_versions = updated_module.previous_versions;
             }
 
             app.push_notification(
@@ -180,6 +422,47 @@ pub fn handle_update_completed(
     Task::none()
 }
 
+pub fn handle_rollback_module(
+    app: &mut App,
+    uuid: crate::domain::ModuleUuid,
+    version: ModuleVersion,
+) -> Task<Message> {
+    let uuid_str = uuid.to_string();
+    if !app.installed.rolling_back.insert(uuid_str.clone()) {
+        return Task::none();
+    }
+    tasks::rollback_module(uuid_str, Some(version))
+}
+
+pub fn handle_rollback_completed(
+    app: &mut App,
+    result: Result<InstalledModule, (String, String)>,
+) -> Task<Message> {
+    match result {
+        Ok(module) => {
+            let uuid = module.uuid.to_string();
+            app.installed.rolling_back.remove(&uuid);
+            let message = format!(
+                "Rolled back {} to {}",
+                module.waybar_module_name, module.version
+            );
+            if let Some(existing) = app
+                .installed_modules
+                .iter_mut()
+                .find(|m| m.uuid.to_string() == uuid)
+            {
+                *existing = module;
+            }
+            app.push_notification(message, NotificationKind::Success);
+        }
+        Err((uuid, e)) => {
+            app.installed.rolling_back.remove(&uuid);
+            app.push_notification(format!("Rollback failed: {e}"), NotificationKind::Error);
+        }
+    }
+    Task::none()
+}
+
 pub fn handle_update_all_completed(app: &mut App, result: Result<usize, String>) -> Task<Message> {
     app.installed.updating_all = false;
     match result {
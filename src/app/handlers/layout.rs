@@ -0,0 +1,80 @@
+use iced::Task;
+
+use crate::app::message::Message;
+use crate::app::state::{App, NotificationKind};
+use crate::domain::BarSection;
+use crate::services::waybar_config::{BarInfo, LayoutLane};
+use crate::tasks;
+
+pub fn handle_layout_loaded(
+    app: &mut App,
+    result: Result<Vec<LayoutLane>, String>,
+) -> Task<Message> {
+    app.layout.loading = false;
+    match result {
+        Ok(lanes) => {
+            app.layout.lanes = lanes;
+            app.layout.error = None;
+        }
+        Err(e) => {
+            tracing::warn!("Failed to load bar layout: {e}");
+            app.layout.lanes.clear();
+            app.layout.error = Some(e);
+        }
+    }
+    Task::none()
+}
+
+pub fn handle_layout_entry_moved(
+    app: &mut App,
+    result: Result<Vec<LayoutLane>, String>,
+) -> Task<Message> {
+    match result {
+        Ok(lanes) => {
+            app.layout.lanes = lanes;
+            tasks::load_installed()
+        }
+        Err(e) => {
+            app.push_notification(
+                format!("Failed to move entry: {e}"),
+                NotificationKind::Error,
+            );
+            tasks::load_bar_layout(app.layout.bar.clone())
+        }
+    }
+}
+
+pub fn handle_layout_select_bar(app: &mut App, bar: BarInfo) -> Task<Message> {
+    app.layout.bar = Some(bar.id());
+    app.layout.loading = true;
+    tasks::load_bar_layout(app.layout.bar.clone())
+}
+
+pub fn handle_layout_move_entry(
+    app: &mut App,
+    from: (BarSection, usize),
+    to: (BarSection, usize),
+) -> Task<Message> {
+    if from == to || !app.layout.move_entry(from, to) {
+        return Task::none();
+    }
+    tasks::move_layout_entry(app.layout.bar.clone(), from, to)
+}
+
+pub fn handle_layout_drag(app: &mut App, section: BarSection, index: usize) -> Task<Message> {
+    app.layout.dragging = Some((section, index));
+    app.layout.drag_over = Some((section, index));
+    Task::none()
+}
+
+pub fn handle_layout_drag_over(app: &mut App, section: BarSection, index: usize) -> Task<Message> {
+    app.layout.drag_over = Some((section, index));
+    Task::none()
+}
+
+pub fn handle_layout_drag_leave(app: &mut App, section: BarSection, index: usize) -> Task<Message> {
+    if app.layout.drag_over == Some((section, index)) {
+        app.layout.drag_over = None;
+    }
+    Task::none()
+}
@@ -1,6 +1,7 @@
 mod async_results;
 mod browse;
 mod installed;
+mod layout;
 mod navigation;
 mod security;
 mod settings;
@@ -9,6 +10,7 @@ mod system;
 pub use async_results::*;
 pub use browse::*;
 pub use installed::*;
+pub use layout::*;
 pub use navigation::*;
 pub use security::*;
 pub use settings::*;
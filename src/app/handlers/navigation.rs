@@ -38,6 +38,16 @@ pub fn handle_navigate(app: &mut App, screen: Screen) -> Task<Message> {
             Task::batch(tasks_to_run)
         };
     }
+    if screen == Screen::Settings {
+        app.screen = screen;
+        app.backups.loading = true;
+        return tasks::load_backups();
+    }
+    if screen == Screen::Layout {
+        app.screen = screen;
+        app.layout.loading = true;
+        return tasks::load_bar_layout(app.layout.bar.clone());
+    }
     app.screen = screen;
     Task::none()
 }
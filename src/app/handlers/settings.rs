@@ -2,6 +2,8 @@ use iced::Task;
 
 use crate::app::message::Message;
 use crate::app::state::{App, NotificationKind, Screen};
+use crate::services::{Backup, ConfigPreview};
+use crate::tasks;
 use crate::theme::ThemeMode;
 
 pub fn handle_system_theme_changed(app: &mut App, is_dark: bool) -> Task<Message> {
@@ -39,6 +41,116 @@ pub fn handle_toggle_tray(app: &mut App, enabled: bool) -> Task<Message> {
     Task::none()
 }
 
+pub fn handle_toggle_unsigned_updates(app: &mut App, enabled: bool) -> Task<Message> {
+    app.allow_unsigned_updates = enabled;
+    app.save_settings();
+    Task::none()
+}
+
+pub fn handle_toggle_sandbox_commands(app: &mut App, enabled: bool) -> Task<Message> {
+    app.sandbox_module_commands = enabled;
+    app.save_settings();
+    Task::none()
+}
+
+pub fn handle_toggle_seccomp_filter(app: &mut App, enabled: bool) -> Task<Message> {
+    app.seccomp_filter = enabled;
+    app.save_settings();
+    Task::none()
+}
+
+pub fn handle_set_backup_retention(app: &mut App, keep: usize) -> Task<Message> {
+    app.backup_retention = keep;
+    app.save_settings();
+    Task::none()
+}
+
+pub fn handle_backups_loaded(app: &mut App, result: Result<Vec<Backup>, String>) -> Task<Message> {
+    app.backups.loading = false;
+    match result {
+        Ok(backups) => {
+            if let Some(id) = &app.backups.selected
+                && !backups.iter().any(|b| &b.id == id)
+            {
+                app.backups.selected = None;
+                app.backups.diff = None;
+            }
+            app.backups.backups = backups;
+            app.backups.error = None;
+        }
+        Err(e) => app.backups.error = Some(e),
+    }
+    Task::none()
+}
+
+pub fn handle_select_backup(app: &mut App, id: String) -> Task<Message> {
+    app.backups.diff = None;
+    if app.backups.selected.as_ref() == Some(&id) {
+        app.backups.selected = None;
+        return Task::none();
+    }
+    app.backups.selected = Some(id.clone());
+    tasks::diff_backup(id)
+}
+
+pub fn handle_backup_diff_loaded(
+    app: &mut App,
+    id: String,
+    result: Result<ConfigPreview, String>,
+) -> Task<Message> {
+    if app.backups.selected.as_ref() == Some(&id) {
+        app.backups.diff = Some(result);
+    }
+    Task::none()
+}
+
+pub fn handle_backup_restored(
+    app: &mut App,
+    id: String,
+    result: Result<(), String>,
+) -> Task<Message> {
+    app.backups.restoring = None;
+    match result {
+        Ok(()) => {
+            app.backups.selected = None;
+            app.backups.diff = None;
+            app.push_notification(
+                format!("Restored Waybar files from backup {id}"),
+                NotificationKind::Success,
+            );
+            app.backups.loading = true;
+            Task::batch([tasks::load_backups(), tasks::load_waybar_bars()])
+        }
+        Err(e) => {
+            app.push_notification(e, NotificationKind::Error);
+            Task::none()
+        }
+    }
+}
+
+pub fn handle_prune_backups(app: &mut App) -> Task<Message> {
+    tasks::prune_backups(app.backup_retention)
+}
+
+pub fn handle_backups_pruned(app: &mut App, result: Result<Vec<String>, String>) -> Task<Message> {
+    match result {
+        Ok(removed) => {
+            let message = match removed.len() {
+                0 => "No backups to remove".to_string(),
+                1 => "Removed 1 backup".to_string(),
+                n => format!("Removed {n} backups"),
+            };
+            app.push_notification(message, NotificationKind::Info);
+            app.backups.loading = true;
+            tasks::load_backups()
+        }
+        Err(e) => {
+            app.push_notification(e, NotificationKind::Error);
+            Task::none()
+        }
+    }
+}
+
 pub fn handle_open_preferences(app: &mut App, uuid: crate::domain::ModuleUuid) -> Task<Message> {
     let uuid_str = uuid.to_string();
     if let Some(installed) = app.installed_modules.iter().find(|m| m.uuid == uuid) {
@@ -125,6 +237,8 @@ pub fn handle_escape_pressed(app: &mut App) -> Task<Message> {
         app.preferences.values.clear();
     } else if app.confirmation.pending_action.is_some() {
         app.confirmation.pending_action = None;
+    } else if app.script_output.is_some() {
+        app.script_output = None;
     } else if matches!(app.screen, Screen::ModuleDetail(_)) {
         app.screen = Screen::Browse;
         app.module_detail.screenshot = crate::app::state::ScreenshotState::NotLoaded;
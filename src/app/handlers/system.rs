@@ -36,7 +36,11 @@ pub fn handle_show_notification(app: &mut App, message: String, kind: Notificati
 }
 
 pub fn handle_dismiss_notification(app: &mut App) -> Task<Message> {
-    if app.confirmation.pending_action.is_some() {
+    if app.change_preview.is_some() {
+        super::handle_cancel_change(app);
+    } else if app.script_review.is_some() {
+        super::handle_cancel_script_review(app);
+    } else if app.confirmation.pending_action.is_some() {
         app.confirmation.pending_action = None;
     } else if matches!(app.screen, Screen::ModuleDetail(_)) {
         app.screen = Screen::Browse;
@@ -59,6 +63,13 @@ pub fn handle_confirm_action(app: &mut App) -> Task<Message> {
                 app.installed.uninstalling.insert(uuid.clone());
                 return tasks::uninstall_module(uuid);
             }
+            ConfirmationAction::RemoveExternal(module) => {
+                return tasks::remove_external(module);
+            }
+            ConfirmationAction::RestoreBackup(id) => {
+                app.backups.restoring = Some(id.clone());
+                return tasks::restore_backup(id);
+            }
         }
     }
     Task::none()
@@ -4,10 +4,16 @@ use crate::app::state::{
     CategoryFilter, ConfirmationAction, NotificationKind, Screen, SortField, ViewMode,
 };
 use crate::domain::{
-    AuthorProfile, BarSection, InstalledModule, ModuleUuid, RegistryIndex, ReviewsResponse,
+    AuthorProfile, BarSection, ExternalModule, InstalledModule, ModuleMove, ModuleUuid,
+    ModuleVersion, RegistryIndex, ReviewsResponse,
 };
 use crate::security::SandboxStatus;
-use crate::services::{DepReport, InstallStage, PreferenceValue};
+use crate::services::waybar_config::{BarInfo, LayoutLane};
+use crate::services::{
+    Backup, ConfigPreview, DepReport, Drift, InstallStage, PreferenceValue, ScriptReview,
+    UninstallResult,
+};
+use crate::tasks::PendingChange;
 use crate::theme::ThemeMode;
 
 #[derive(Debug, Clone)]
@@ -30,10 +36,47 @@ pub enum Message {
     SetModulePosition {
         uuid: ModuleUuid,
         section: BarSection,
+        bar: Option<String>,
     },
     PositionChanged(Result<String, String>),
+    MoveModule {
+        uuid: ModuleUuid,
+        to: ModuleMove,
+    },
+    DragModule(ModuleUuid),
+    DragOver(ModuleUuid),
+    DragLeave(ModuleUuid),
+    DragReleased,
+    ExternalModulesLoaded(Result<Vec<ExternalModule>, String>),
+    ToggleExternal {
+        module: ExternalModule,
+        enabled: bool,
+    },
+    MoveExternal {
+        module: ExternalModule,
+        section: BarSection,
+    },
+    AdoptExternal(ExternalModule),
+    ExternalChanged(Result<String, String>),
+    DriftChecked(Result<Vec<Drift>, String>),
+    FixDrift(Drift),
+    DriftFixed(Drift, Result<(), String>),
+    LayoutLoaded(Result<Vec<LayoutLane>, String>),
+    LayoutEntryMoved(Result<Vec<LayoutLane>, String>),
+    LayoutSelectBar(BarInfo),
+    LayoutMoveEntry {
+        from: (BarSection, usize),
+        to: (BarSection, usize),
+    },
+    LayoutDrag(BarSection, usize),
+    LayoutDragOver(BarSection, usize),
+    LayoutDragLeave(BarSection, usize),
     UninstallModule(ModuleUuid),
     UpdateModule(ModuleUuid),
+    RollbackModule {
+        uuid: ModuleUuid,
+        version: ModuleVersion,
+    },
     UpdateAllModules,
     OpenPreferences(ModuleUuid),
 
@@ -44,11 +87,13 @@ pub enum Message {
     RegistryLoaded(Result<RegistryIndex, String>),
     RegistryRefreshed(Result<RegistryIndex, String>),
     InstalledLoaded(Result<Vec<InstalledModule>, String>),
+    WaybarBarsLoaded(Result<Vec<BarInfo>, String>),
     InstallCompleted(Result<InstalledModule, String>),
     ToggleCompleted(Result<String, (String, String)>),
-    UninstallCompleted(Result<String, (String, String)>),
+    UninstallCompleted(Result<UninstallResult, (String, String)>),
     UpdateCompleted(Result<InstalledModule, String>),
     UpdateAllCompleted(Result<usize, String>),
+    RollbackCompleted(Result<InstalledModule, (String, String)>),
 
     ShowNotification(String, NotificationKind),
     DismissNotification,
@@ -57,6 +102,7 @@ pub enum Message {
     SystemThemeChanged(bool),
     SetThemeMode(ThemeMode),
     OmarchyThemeChanged,
+    WaybarFilesChanged,
 
     NavigateBack,
     ScreenshotLoaded(Result<image::Handle, String>),
@@ -66,12 +112,30 @@ pub enum Message {
     RequestConfirmation(ConfirmationAction),
     ConfirmAction,
     CancelConfirmation,
+    CloseScriptOutput,
+    ChangePreviewed(PendingChange, Result<ConfigPreview, String>),
+    ConfirmChange,
+    CancelChange,
+    ScriptsNeedReview(String, ScriptReview),
+    ApproveScripts(bool),
+    ConfirmScriptReview,
+    CancelScriptReview,
 
     ClearCache,
     CacheClearCompleted(Result<(), String>),
     ResetSettings,
     SettingsResetCompleted(Result<(), String>),
     ToggleTray(bool),
+    ToggleUnsignedUpdates(bool),
+    ToggleSandboxCommands(bool),
+    ToggleSeccompFilter(bool),
+    SetBackupRetention(usize),
+    BackupsLoaded(Result<Vec<Backup>, String>),
+    SelectBackup(String),
+    BackupDiffLoaded(String, Result<ConfigPreview, String>),
+    BackupRestored(String, Result<(), String>),
+    PruneBackups,
+    BackupsPruned(Result<Vec<String>, String>),
 
     FocusSearch,
     EscapePressed,
@@ -17,9 +17,10 @@ use crate::theme::{
     menu_style, pick_list_style,
 };
 use crate::widget::{
-    confirmation_dialog, empty_state, empty_state_dynamic, empty_state_with_action, module_card,
-    module_detail_screen, module_row, module_table, notification_toast, preferences_modal,
-    settings_screen, sidebar, skeleton_card,
+    change_preview_dialog, confirmation_dialog, drift_banner, empty_state, empty_state_dynamic,
+    empty_state_with_action, external_row, layout_screen, module_card, module_detail_screen,
+    module_row, module_table, notification_toast, preferences_modal, script_output_dialog,
+    script_review_dialog, settings_screen, sidebar, skeleton_card,
 };
 
 pub use message::Message;
@@ -63,8 +64,62 @@ impl App {
                 handlers::handle_toggle_module(self, uuid, enabled)
             }
 
-            Message::SetModulePosition { uuid, section } => {
-                handlers::handle_set_module_position(uuid, section)
+            Message::SetModulePosition { uuid, section, bar } => {
+                handlers::handle_set_module_position(uuid, section, bar)
+            }
+
+            Message::MoveModule { uuid, to } => handlers::handle_move_module(uuid, to),
+
+            Message::DragModule(uuid) => handlers::handle_drag_module(self, uuid),
+
+            Message::DragOver(target) => handlers::handle_drag_over(self, target),
+
+            Message::DragLeave(target) => handlers::handle_drag_leave(self, target),
+
+            Message::DragReleased => handlers::handle_drag_released(self),
+
+            Message::ExternalModulesLoaded(result) => {
+                handlers::handle_external_modules_loaded(self, result)
+            }
+
+            Message::ToggleExternal { module, enabled } => {
+                handlers::handle_toggle_external(module, enabled)
+            }
+
+            Message::MoveExternal { module, section } => {
+                handlers::handle_move_external(module, section)
+            }
+
+            Message::AdoptExternal(module) => handlers::handle_adopt_external(module),
+
+            Message::ExternalChanged(result) => handlers::handle_external_changed(self, result),
+
+            Message::DriftChecked(result) => handlers::handle_drift_checked(self, result),
+
+            Message::FixDrift(drift) => handlers::handle_fix_drift(self, drift),
+
+            Message::DriftFixed(drift, result) => handlers::handle_drift_fixed(self, drift, result),
+
+            Message::LayoutLoaded(result) => handlers::handle_layout_loaded(self, result),
+
+            Message::LayoutEntryMoved(result) => handlers::handle_layout_entry_moved(self, result),
+
+            Message::LayoutSelectBar(bar) => handlers::handle_layout_select_bar(self, bar),
+
+            Message::LayoutMoveEntry { from, to } => {
+                handlers::handle_layout_move_entry(self, from, to)
+            }
+
+            Message::LayoutDrag(section, index) => {
+                handlers::handle_layout_drag(self, section, index)
+            }
+
+            Message::LayoutDragOver(section, index) => {
+                handlers::handle_layout_drag_over(self, section, index)
+            }
+
+            Message::LayoutDragLeave(section, index) => {
+                handlers::handle_layout_drag_leave(self, section, index)
             }
 
             Message::PositionChanged(result) => handlers::handle_position_changed(self, result),
@@ -86,6 +141,7 @@ impl App {
             Message::RegistryRefreshed(result) => handlers::handle_registry_refreshed(self, result),
 
             Message::InstalledLoaded(result) => handlers::handle_installed_loaded(self, result),
+            Message::WaybarBarsLoaded(result) => handlers::handle_waybar_bars_loaded(self, result),
 
             Message::InstallCompleted(result) => handlers::handle_install_completed(self, result),
 
@@ -99,6 +155,12 @@ impl App {
 
             Message::UpdateAllModules => handlers::handle_update_all_modules(self),
 
+            Message::RollbackModule { uuid, version } => {
+                handlers::handle_rollback_module(self, uuid, version)
+            }
+
+            Message::RollbackCompleted(result) => handlers::handle_rollback_completed(self, result),
+
             Message::UpdateCompleted(result) => handlers::handle_update_completed(self, result),
 
             Message::UpdateAllCompleted(result) => {
@@ -121,6 +183,7 @@ impl App {
             Message::SetThemeMode(mode) => handlers::handle_set_theme_mode(self, mode),
 
             Message::OmarchyThemeChanged => handlers::handle_omarchy_theme_changed(self),
+            Message::WaybarFilesChanged => handlers::handle_waybar_files_changed(self),
 
             Message::ScreenshotLoaded(result) => handlers::handle_screenshot_loaded(self, result),
 
@@ -143,6 +206,38 @@ impl App {
                 Task::none()
             }
 
+            Message::CloseScriptOutput => {
+                handlers::handle_close_script_output(self);
+                Task::none()
+            }
+
+            Message::ChangePreviewed(change, result) => {
+                handlers::handle_change_previewed(self, change, result)
+            }
+
+            Message::ConfirmChange => handlers::handle_confirm_change(self),
+
+            Message::CancelChange => {
+                handlers::handle_cancel_change(self);
+                Task::none()
+            }
+
+            Message::ScriptsNeedReview(uuid, review) => {
+                handlers::handle_scripts_need_review(self, uuid, review)
+            }
+
+            Message::ApproveScripts(approved) => {
+                handlers::handle_approve_scripts(self, approved);
+                Task::none()
+            }
+
+            Message::ConfirmScriptReview => handlers::handle_confirm_script_review(self),
+
+            Message::CancelScriptReview => {
+                handlers::handle_cancel_script_review(self);
+                Task::none()
+            }
+
             Message::ClearCache => handlers::handle_clear_cache(),
 
             Message::CacheClearCompleted(result) => {
@@ -159,6 +254,36 @@ impl App {
 
             Message::ToggleTray(enabled) => handlers::handle_toggle_tray(self, enabled),
 
+            Message::ToggleUnsignedUpdates(enabled) => {
+                handlers::handle_toggle_unsigned_updates(self, enabled)
+            }
+
+            Message::ToggleSandboxCommands(enabled) => {
+                handlers::handle_toggle_sandbox_commands(self, enabled)
+            }
+
+            Message::ToggleSeccompFilter(enabled) => {
+                handlers::handle_toggle_seccomp_filter(self, enabled)
+            }
+
+            Message::SetBackupRetention(keep) => handlers::handle_set_backup_retention(self, keep),
+
+            Message::BackupsLoaded(result) => handlers::handle_backups_loaded(self, result),
+
+            Message::SelectBackup(id) => handlers::handle_select_backup(self, id),
+
+            Message::BackupDiffLoaded(id, result) => {
+                handlers::handle_backup_diff_loaded(self, id, result)
+            }
+
+            Message::BackupRestored(id, result) => {
+                handlers::handle_backup_restored(self, id, result)
+            }
+
+            Message::PruneBackups => handlers::handle_prune_backups(self),
+
+            Message::BackupsPruned(result) => handlers::handle_backups_pruned(self, result),
+
             Message::FocusSearch => handlers::handle_focus_search(self),
 
             Message::EscapePressed => handlers::handle_escape_pressed(self),
@@ -219,6 +344,7 @@ impl App {
             Screen::Installed => self.view_installed(),
             Screen::Updates => self.view_updates(),
             Screen::Settings => self.view_settings(),
+            Screen::Layout => self.view_layout(),
             Screen::ModuleDetail(uuid) => self.view_module_detail(uuid),
             Screen::AuthorProfile(username) => self.view_author_profile(username),
         };
@@ -274,17 +400,55 @@ impl App {
             Space::new().into()
         };
 
+        let script_output_overlay: Element<Message> = if let Some(output) = &self.script_output {
+            script_output_dialog(output, &self.theme)
+        } else {
+            Space::new().into()
+        };
+
+        let change_preview_overlay: Element<Message> = if let Some(state) = &self.change_preview {
+            change_preview_dialog(state, &self.theme)
+        } else {
+            Space::new().into()
+        };
+
+        let script_review_overlay: Element<Message> = if let Some(state) = &self.script_review {
+            script_review_dialog(state, &self.theme)
+        } else {
+            Space::new().into()
+        };
+
         stack![
             main_layout,
             notification_overlay,
             confirmation_overlay,
-            preferences_overlay
+            preferences_overlay,
+            script_output_overlay,
+            change_preview_overlay,
+            script_review_overlay
         ]
         .into()
     }
 
+    fn view_layout(&self) -> Element<'_, Message> {
+        layout_screen(
+            &self.layout,
+            &self.installed.bars,
+            &self.installed_modules,
+            &self.theme,
+        )
+    }
+
     fn view_settings(&self) -> Element<'_, Message> {
-        settings_screen(&self.theme, self.tray_enabled)
+        settings_screen(
+            &self.theme,
+            self.tray_enabled,
+            self.allow_unsigned_updates,
+            self.sandbox_module_commands,
+            self.seccomp_filter,
+            self.backup_retention,
+            &self.backups,
+        )
     }
 
     fn view_module_detail(&self, uuid: &str) -> Element<'_, Message> {
@@ -770,7 +934,7 @@ impl App {
         )
         .padding([SPACING_MD, SPACING_LG]);
 
-        let filtered_modules: Vec<_> = self
+        let mut filtered_modules: Vec<_> = self
             .installed_modules
             .iter()
             .filter(|m| {
@@ -783,40 +947,100 @@ impl App {
                 }
             })
             .collect();
+        filtered_modules.sort_by_key(|m| {
+            let position = m.position.clone().unwrap_or_default();
+            let bar = position
+                .bar
+                .as_deref()
+                .and_then(|id| self.installed.bars.iter().position(|b| b.matches(id)))
+                .unwrap_or(0);
+            let section = crate::domain::BarSection::all()
+                .iter()
+                .position(|s| *s == position.section)
+                .unwrap_or(0);
+            (bar, section, position.order.unwrap_or(u32::MAX))
+        });
 
-        let rows: Vec<Element<Message>> = filtered_modules
+        let mut rows: Vec<Element<Message>> = filtered_modules
             .iter()
             .map(|m| {
                 let uuid = m.uuid.to_string();
                 let is_toggling = self.installed.toggling.contains(&uuid);
                 let is_uninstalling = self.installed.uninstalling.contains(&uuid);
-                module_row(m, is_toggling, is_uninstalling, &self.theme)
+                let is_rolling_back = self.installed.rolling_back.contains(&uuid);
+                let is_drop_target = self.installed.dragging.as_ref().is_some_and(|dragged| {
+                    dragged != &m.uuid && self.installed.drag_over.as_ref() == Some(&m.uuid)
+                });
+                module_row(
+                    m,
+                    is_toggling,
+                    is_uninstalling,
+                    is_rolling_back,
+                    &self.installed.bars,
+                    is_drop_target,
+                    &self.theme,
+                )
             })
             .collect();
 
-        let content: Element<Message> = if self.installed_modules.is_empty() {
-            empty_state_with_action(
-                Icon::Installed,
-                "No modules installed",
-                "Browse modules to find and install new ones",
-                "Browse Modules",
-                Message::Navigate(Screen::Browse),
-                &self.theme,
-            )
-        } else if rows.is_empty() && has_search {
-            empty_state_dynamic(
-                Icon::Search,
-                "No matches found",
-                format!("No modules match \"{}\"", search_query),
-                &self.theme,
-            )
-        } else {
-            scrollable(column(rows).spacing(SPACING_SM).padding(SPACING_LG))
-                .height(Length::Fill)
-                .into()
-        };
+        let external_rows: Vec<Element<Message>> = self
+            .installed
+            .external
+            .iter()
+            .filter(|m| {
+                search_query.is_empty()
+                    || m.name.to_lowercase().contains(&search_query.to_lowercase())
+            })
+            .map(|m| external_row(m, &self.theme))
+            .collect();
+        if !external_rows.is_empty() {
+            rows.push(
+                container(
+                    text("External modules")
+                        .size(14.0)
+                        .color(self.theme.text_muted),
+                )
+                .padding([SPACING_SM, 0.0])
+                .into(),
+            );
+            rows.extend(external_rows);
+        }
 
-        column![header, content]
+        let content: Element<Message> =
+            if self.installed_modules.is_empty() && self.installed.external.is_empty() {
+                empty_state_with_action(
+                    Icon::Installed,
+                    "No modules installed",
+                    "Browse modules to find and install new ones",
+                    "Browse Modules",
+                    Message::Navigate(Screen::Browse),
+                    &self.theme,
+                )
+            } else if rows.is_empty() && has_search {
+                empty_state_dynamic(
+                    Icon::Search,
+                    "No matches found",
+                    format!("No modules match \"{}\"", search_query),
+                    &self.theme,
+                )
+            } else {
+                scrollable(column(rows).spacing(SPACING_SM).padding(SPACING_LG))
+                    .height(Length::Fill)
+                    .into()
+            };
+
+        let mut page = column![header];
+        if !self.installed.drift.is_empty() {
+            page = page.push(
+                container(drift_banner(
+                    &self.installed.drift,
+                    self.installed.fixing_drift.as_ref(),
+                    &self.theme,
+                ))
+                .padding([0.0, SPACING_LG]),
+            );
+        }
+        page.push(content)
             .width(Length::Fill)
             .height(Length::Fill)
             .into()
@@ -1061,6 +1285,7 @@ impl App {
                         "2" => Some(Message::Navigate(Screen::Installed)),
                         "3" => Some(Message::Navigate(Screen::Updates)),
                         "4" => Some(Message::Navigate(Screen::Settings)),
+                        "5" => Some(Message::Navigate(Screen::Layout)),
                         _ => None,
                     }
                 } else {
@@ -1076,9 +1301,22 @@ impl App {
             iced::Subscription::none()
         };
 
+        let drag_sub = if self.installed.dragging.is_some() || self.layout.dragging.is_some() {
+            event::listen_with(|event, _status, _id| match event {
+                Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left)) => {
+                    Some(Message::DragReleased)
+                }
+                _ => None,
+            })
+        } else {
+            iced::Subscription::none()
+        };
+
         iced::Subscription::batch([
             keyboard_sub,
+            drag_sub,
             omarchy_watcher,
+            tasks::watch_waybar_files(),
             time::every(Duration::from_millis(50)).map(|_| Message::Tick),
         ])
     }
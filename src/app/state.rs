@@ -5,13 +5,16 @@ use std::time::{Duration, Instant};
 use iced::widget::image;
 
 use crate::domain::{
-    AuthorProfile, InstalledModule, ModuleCategory, RegistryIndex, ReviewsResponse,
+    AuthorProfile, BarSection, ExternalModule, InstalledModule, ModuleCategory, ModuleUuid,
+    RegistryIndex, ReviewsResponse,
 };
-use crate::security::SandboxStatus;
+use crate::security::{SandboxStatus, ScriptResult};
 use crate::services::{
-    InstallStage, ModulePreferences, OmarchyPalette, PreferencesSchema, is_omarchy_available,
-    load_omarchy_palette, load_settings,
+    Backup, ConfigPreview, Drift, InstallStage, ModulePreferences, OmarchyPalette,
+    PreferencesSchema, ScriptReview, is_omarchy_available, load_omarchy_palette, load_settings,
+    waybar_config::{BarInfo, LayoutLane},
 };
+use crate::tasks::PendingChange;
 use crate::theme::{AppTheme, ThemeMode};
 
 use crate::tray::TrayEvent;
@@ -24,6 +27,7 @@ pub enum Screen {
     Installed,
     Updates,
     Settings,
+    Layout,
     ModuleDetail(String),
     AuthorProfile(String),
 }
@@ -35,13 +39,17 @@ impl Screen {
             Screen::Installed => "Installed",
             Screen::Updates => "Updates",
             Screen::Settings => "Settings",
+            Screen::Layout => "Layout",
             Screen::ModuleDetail(_) => "Module Detail",
             Screen::AuthorProfile(_) => "Author Profile",
         }
     }
 
     pub fn is_main_nav(&self) -> bool {
-        matches!(self, Screen::Browse | Screen::Installed | Screen::Updates)
+        matches!(
+            self,
+            Screen::Browse | Screen::Installed | Screen::Updates | Screen::Layout
+        )
     }
 }
 
@@ -147,11 +155,58 @@ pub struct InstalledState {
     pub uninstalling: HashSet<String>,
     pub updating: HashSet<String>,
     pub updating_all: bool,
+    pub rolling_back: HashSet<String>,
+    pub bars: Vec<BarInfo>,
+    pub external: Vec<ExternalModule>,
+    pub drift: Vec<Drift>,
+    pub fixing_drift: Option<Drift>,
+    pub dragging: Option<ModuleUuid>,
+    pub drag_over: Option<ModuleUuid>,
     pub search_query: String,
     pub pending_search: Option<String>,
     pub search_debounce_start: Option<std::time::Instant>,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct LayoutState {
+    pub bar: Option<String>,
+    pub lanes: Vec<LayoutLane>,
+    pub loading: bool,
+    pub error: Option<String>,
+    pub dragging: Option<(BarSection, usize)>,
+    pub drag_over: Option<(BarSection, usize)>,
+}
+
+impl LayoutState {
+    pub fn lane(&self, section: BarSection) -> Option<&LayoutLane> {
+        self.lanes.iter().find(|lane| lane.section == section)
+    }
+
+    pub fn move_entry(&mut self, from: (BarSection, usize), to: (BarSection, usize)) -> bool {
+        let Some(entry) = self
+            .lanes
+            .iter_mut()
+            .find(|lane| lane.section == from.0)
+            .filter(|lane| from.1 < lane.entries.len())
+            .map(|lane| lane.entries.remove(from.1))
+        else {
+            return false;
+        };
+
+        match self.lanes.iter_mut().find(|lane| lane.section == to.0) {
+            Some(lane) => {
+                let index = to.1.min(lane.entries.len());
+                lane.entries.insert(index, entry);
+            }
+            None => self.lanes.push(LayoutLane {
+                section: to.0,
+                entries: vec![entry],
+            }),
+        }
+        true
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Notification {
     pub message: String,
@@ -225,6 +280,8 @@ pub struct AuthorProfileState {
 #[derive(Debug, Clone)]
 pub enum ConfirmationAction {
     UninstallModule { uuid: String, name: String },
+    RemoveExternal(ExternalModule),
+    RestoreBackup(String),
 }
 
 #[derive(Debug, Clone, Default)]
@@ -232,6 +289,52 @@ pub struct ConfirmationState {
     pub pending_action: Option<ConfirmationAction>,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct BackupsState {
+    pub backups: Vec<Backup>,
+    pub loading: bool,
+    pub error: Option<String>,
+    pub selected: Option<String>,
+    pub diff: Option<Result<ConfigPreview, String>>,
+    pub restoring: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChangePreviewState {
+    pub change: PendingChange,
+    pub preview: ConfigPreview,
+    pub scripts_approved: bool,
+}
+
+impl ChangePreviewState {
+    pub fn new(change: PendingChange, preview: ConfigPreview) -> Self {
+        Self {
+            change,
+            preview,
+            scripts_approved: false,
+        }
+    }
+
+    pub fn can_apply(&self) -> bool {
+        self.preview.is_valid() && (self.preview.scripts.is_clean() || self.scripts_approved)
+    }
+}
+
+/// Risky findings in a module's scripts, held back from install until the
+/// user approves them.
+#[derive(Debug, Clone)]
+pub struct ScriptReviewState {
+    pub uuid: ModuleUuid,
+    pub review: ScriptReview,
+    pub approved: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScriptOutputState {
+    pub title: String,
+    pub result: ScriptResult,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct PreferencesState {
     pub open_for: Option<String>,
@@ -249,6 +352,8 @@ pub struct App {
 
     pub browse: BrowseState,
     pub installed: InstalledState,
+    pub layout: LayoutState,
+    pub backups: BackupsState,
 
     pub notifications: VecDeque<Notification>,
     pub loading: LoadingState,
@@ -262,6 +367,9 @@ pub struct App {
     pub author_profile: AuthorProfileState,
     pub confirmation: ConfirmationState,
     pub preferences: PreferencesState,
+    pub script_output: Option<ScriptOutputState>,
+    pub change_preview: Option<ChangePreviewState>,
+    pub script_review: Option<ScriptReviewState>,
 
     pub spinner_frame: usize,
     pub last_spinner_update: Instant,
@@ -270,6 +378,10 @@ pub struct App {
     pub tray_receiver: Option<Receiver<TrayEvent>>,
 
     pub sandbox_status: Option<SandboxStatus>,
+    pub allow_unsigned_updates: bool,
+    pub sandbox_module_commands: bool,
+    pub seccomp_filter: bool,
+    pub backup_retention: usize,
 }
 
 impl Default for App {
@@ -315,6 +427,8 @@ impl Default for App {
             installed_uuids: HashSet::new(),
             browse,
             installed: InstalledState::default(),
+            layout: LayoutState::default(),
+            backups: BackupsState::default(),
             notifications: VecDeque::new(),
             loading: LoadingState::default(),
             theme_mode,
@@ -325,11 +439,18 @@ impl Default for App {
             author_profile: AuthorProfileState::default(),
             confirmation: ConfirmationState::default(),
             preferences: PreferencesState::default(),
+            script_output: None,
+            change_preview: None,
+            script_review: None,
             spinner_frame: 0,
             last_spinner_update: Instant::now(),
             tray_enabled,
             tray_receiver,
             sandbox_status: None,
+            allow_unsigned_updates: settings.allow_unsigned_updates,
+            sandbox_module_commands: settings.sandbox_module_commands,
+            seccomp_filter: settings.seccomp_filter,
+            backup_retention: settings.backup_retention,
         }
     }
 }
@@ -502,6 +623,10 @@ impl App {
             sort_field: self.browse.sort_field,
             sort_order: self.browse.sort_order,
             tray_enabled: self.tray_enabled,
+            allow_unsigned_updates: self.allow_unsigned_updates,
+            backup_retention: self.backup_retention,
+            sandbox_module_commands: self.sandbox_module_commands,
+            seccomp_filter: self.seccomp_filter,
         };
         if let Err(e) = crate::services::save_settings(&settings) {
             tracing::warn!("Failed to save user settings: {e}");
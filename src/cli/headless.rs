@@ -0,0 +1,1291 @@
+use serde::Serialize;
+use thiserror::Error;
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::domain::{
+    BarSection, InstalledModule, ModulePosition, ModuleUuid, ModuleVersion, RegistryIndex,
+};
+use crate::security::ScriptResult;
+use crate::services::manifest::{Manifest, SyncAction, plan_sync};
+use crate::services::paths;
+use crate::services::preferences::load_preferences;
+use crate::services::waybar_config::{self, BarInfo};
+use crate::services::{
+    Backup, BackupError, BackupStore, ConfigPreview, Drift, ScriptApproval, ScriptReview,
+};
+use crate::tasks::{self, InstallFailure, PendingChange, UpdateRequest};
+
+use super::{BackupAction, Commands};
+
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NOT_FOUND: i32 = 3;
+
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("{0}")]
+    InvalidArgument(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Failed(String),
+}
+
+impl CommandError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CommandError::InvalidArgument(_) => EXIT_USAGE,
+            CommandError::NotFound(_) => EXIT_NOT_FOUND,
+            CommandError::Failed(_) => EXIT_FAILURE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleUpdate {
+    pub uuid: String,
+    pub from: ModuleVersion,
+    pub to: ModuleVersion,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncFailure {
+    #[serde(flatten)]
+    pub action: SyncAction,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DriftFailure {
+    #[serde(flatten)]
+    pub drift: Drift,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum CommandOutput {
+    Installed {
+        module: InstalledModule,
+    },
+    AlreadyInstalled {
+        uuid: String,
+    },
+    Removed {
+        uuid: String,
+        script: Option<ScriptResult>,
+    },
+    Enabled {
+        uuid: String,
+    },
+    Disabled {
+        uuid: String,
+    },
+    Updated {
+        updated: Vec<ModuleUpdate>,
+        failed: Vec<ModuleUpdate>,
+    },
+    RolledBack {
+        uuid: String,
+        from: ModuleVersion,
+        to: ModuleVersion,
+    },
+    List {
+        modules: Vec<InstalledModule>,
+    },
+    Bars {
+        bars: Vec<BarInfo>,
+    },
+    Moved {
+        uuid: String,
+        position: ModulePosition,
+    },
+    Synced {
+        dry_run: bool,
+        actions: Vec<SyncAction>,
+        failed: Vec<SyncFailure>,
+    },
+    Preview {
+        uuid: String,
+        preview: ConfigPreview,
+    },
+    Backups {
+        backups: Vec<Backup>,
+    },
+    BackupDiff {
+        id: String,
+        preview: ConfigPreview,
+    },
+    BackupRestored {
+        id: String,
+        previous: Option<String>,
+    },
+    BackupsPruned {
+        removed: Vec<String>,
+    },
+    Doctor {
+        fix: bool,
+        drift: Vec<Drift>,
+        failed: Vec<DriftFailure>,
+    },
+}
+
+impl CommandOutput {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CommandOutput::Updated { failed, .. } if !failed.is_empty() => EXIT_FAILURE,
+            CommandOutput::Synced { failed, .. } if !failed.is_empty() => EXIT_FAILURE,
+            CommandOutput::Preview { preview, .. } if !preview.is_valid() => EXIT_FAILURE,
+            CommandOutput::Doctor {
+                fix: false, drift, ..
+            } if !drift.is_empty() => EXIT_FAILURE,
+            CommandOutput::Doctor { failed, .. } if !failed.is_empty() => EXIT_FAILURE,
+            _ => EXIT_SUCCESS,
+        }
+    }
+
+    pub fn render_text(&self) -> String {
+        match self {
+            CommandOutput::Installed { module } => {
+                format!("Installed {} {}", module.uuid, module.version)
+            }
+            CommandOutput::AlreadyInstalled { uuid } => format!("{uuid} is already installed"),
+            CommandOutput::Removed { uuid, script } => match script {
+                Some(script) => format!("Removed {uuid}\n{}", render_script_result(script)),
+                None => format!("Removed {uuid}"),
+            },
+            CommandOutput::Enabled { uuid } => format!("Enabled {uuid}"),
+            CommandOutput::Disabled { uuid } => format!("Disabled {uuid}"),
+            CommandOutput::Updated { updated, failed } => {
+                if updated.is_empty() && failed.is_empty() {
+                    return "All modules are up to date".to_string();
+                }
+                let mut lines: Vec<String> = updated
+                    .iter()
+                    .map(|u| format!("Updated {} {} -> {}", u.uuid, u.from, u.to))
+                    .collect();
+                lines.extend(
+                    failed
+                        .iter()
+                        .map(|u| format!("Failed to update {} {} -> {}", u.uuid, u.from, u.to)),
+                );
+                lines.join("\n")
+            }
+            CommandOutput::RolledBack { uuid, from, to } => {
+                format!("Rolled back {uuid} {from} -> {to}")
+            }
+            CommandOutput::List { modules } => render_module_list(modules),
+            CommandOutput::Bars { bars } => render_bar_list(bars),
+            CommandOutput::Moved { uuid, position } => {
+                let target = render_position(position.section, position.bar.as_deref());
+                match position.order {
+                    Some(order) => format!("Moved {uuid} to {target} at index {order}"),
+                    None => format!("Moved {uuid} to {target}"),
+                }
+            }
+            CommandOutput::Synced {
+                dry_run,
+                actions,
+                failed,
+            } => {
+                if actions.is_empty() && failed.is_empty() {
+                    return "Already in sync".to_string();
+                }
+                let mut lines = Vec::with_capacity(actions.len() + failed.len() + 1);
+                if *dry_run {
+                    lines.push(format!(
+                        "Would apply {} change{}:",
+                        actions.len(),
+                        if actions.len() == 1 { "" } else { "s" }
+                    ));
+                }
+                lines.extend(actions.iter().map(|a| format!("  {a}")));
+                lines.extend(
+                    failed
+                        .iter()
+                        .map(|f| format!("  {} (failed: {})", f.action, f.error)),
+                );
+                lines.join("\n")
+            }
+            CommandOutput::Preview { uuid, preview } => {
+                if preview.is_empty() {
+                    return format!("No changes to the Waybar files for {uuid}");
+                }
+                preview.to_string().trim_end().to_string()
+            }
+            CommandOutput::Backups { backups } => render_backup_list(backups),
+            CommandOutput::BackupDiff { id, preview } => {
+                if preview.is_empty() {
+                    return format!("Backup {id} matches the current files");
+                }
+                preview.to_string().trim_end().to_string()
+            }
+            CommandOutput::BackupRestored { id, previous } => match previous {
+                Some(previous) => {
+                    format!("Restored backup {id}\nThe replaced files were backed up as {previous}")
+                }
+                None => format!("Restored backup {id}"),
+            },
+            CommandOutput::BackupsPruned { removed } => match removed.len() {
+                0 => "No backups to remove".to_string(),
+                1 => "Removed 1 backup".to_string(),
+                n => format!("Removed {n} backups"),
+            },
+            CommandOutput::Doctor { fix, drift, failed } => render_drift(*fix, drift, failed),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonSuccess<'a> {
+    ok: bool,
+    #[serde(flatten)]
+    output: &'a CommandOutput,
+}
+
+#[derive(Serialize)]
+struct JsonFailure<'a> {
+    ok: bool,
+    error: String,
+    exit_code: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uuid: Option<&'a str>,
+}
+
+pub async fn run(command: Commands, json: bool) -> i32 {
+    let uuid = command_uuid(&command).map(str::to_string);
+
+    match execute(command).await {
+        Ok(output) => {
+            if json {
+                let body = JsonSuccess {
+                    ok: true,
+                    output: &output,
+                };
+                match serde_json::to_string_pretty(&body) {
+                    Ok(s) => print_stdout(&s),
+                    Err(e) => {
+                        eprintln!("error: failed to serialize output: {e}");
+                        return EXIT_FAILURE;
+                    }
+                }
+            } else {
+                print_stdout(&output.render_text());
+            }
+            output.exit_code()
+        }
+        Err(e) => {
+            let code = e.exit_code();
+            if json {
+                let body = JsonFailure {
+                    ok: false,
+                    error: e.to_string(),
+                    exit_code: code,
+                    uuid: uuid.as_deref(),
+                };
+                if let Ok(s) = serde_json::to_string_pretty(&body) {
+                    print_stdout(&s);
+                }
+            } else {
+                eprintln!("error: {e}");
+            }
+            code
+        }
+    }
+}
+
+fn print_stdout(text: &str) {
+    use std::io::Write;
+
+    // Piping into `head` and friends closes stdout early; that is not an error.
+    let _ = writeln!(std::io::stdout(), "{text}");
+}
+
+fn command_uuid(command: &Commands) -> Option<&str> {
+    match command {
+        Commands::Install { uuid, .. }
+        | Commands::Remove { uuid }
+        | Commands::Enable { uuid, .. }
+        | Commands::Disable { uuid, .. } => Some(uuid),
+        Commands::Update { uuid, .. } => uuid.as_deref(),
+        Commands::Rollback { uuid, .. } | Commands::Move { uuid, .. } => Some(uuid),
+        _ => None,
+    }
+}
+
+async fn execute(command: Commands) -> Result<CommandOutput, CommandError> {
+    match command {
+        Commands::Install {
+            uuid,
+            approve_scripts,
+            update_lock,
+        } => install(uuid, approval(approve_scripts), update_lock).await,
+        Commands::Remove { uuid } => remove(uuid).await,
+        Commands::Enable {
+            uuid,
+            dry_run,
+            approve_scripts,
+        } => toggle(uuid, true, dry_run, approval(approve_scripts)).await,
+        Commands::Disable { uuid, dry_run } => {
+            toggle(uuid, false, dry_run, ScriptApproval::IfClean).await
+        }
+        Commands::Update {
+            uuid,
+            allow_unsigned,
+            dry_run,
+            approve_scripts,
+            ..
+        } => update(uuid, allow_unsigned, dry_run, approval(approve_scripts)).await,
+        Commands::Rollback { uuid, version } => rollback(uuid, version).await,
+        Commands::List => list().await,
+        Commands::Bars => bars().await,
+        Commands::Move {
+            uuid,
+            section,
+            bar,
+            index,
+            dry_run,
+        } => move_module(uuid, section, bar, index, dry_run).await,
+        Commands::Sync { dry_run, manifest } => sync(manifest, dry_run).await,
+        Commands::Backups { action } => backups(action).await,
+        Commands::Doctor { fix } => doctor(fix).await,
+        Commands::Gui | Commands::InternalSandboxExec { .. } | Commands::RunSandboxed { .. } => {
+            Err(CommandError::InvalidArgument(
+                "Not a headless command".to_string(),
+            ))
+        }
+    }
+}
+
+fn parse_uuid(uuid: &str) -> Result<ModuleUuid, CommandError> {
+    ModuleUuid::try_from(uuid)
+        .map_err(|e| CommandError::InvalidArgument(format!("Invalid UUID '{uuid}': {e}")))
+}
+
+async fn load_installed() -> Result<Vec<InstalledModule>, CommandError> {
+    tasks::load_installed_async()
+        .await
+        .map_err(CommandError::Failed)
+}
+
+fn find_installed<'a>(
+    modules: &'a [InstalledModule],
+    uuid: &ModuleUuid,
+) -> Result<&'a InstalledModule, CommandError> {
+    modules
+        .iter()
+        .find(|m| &m.uuid == uuid)
+        .ok_or_else(|| CommandError::NotFound(format!("Module not installed: {uuid}")))
+}
+
+fn approval(approve_scripts: bool) -> ScriptApproval {
+    if approve_scripts {
+        ScriptApproval::Approved
+    } else {
+        ScriptApproval::IfClean
+    }
+}
+
+async fn install(
+    uuid: String,
+    scripts: ScriptApproval,
+    update_lock: bool,
+) -> Result<CommandOutput, CommandError> {
+    let uuid = parse_uuid(&uuid)?;
+    let installed = load_installed().await?;
+    if installed.iter().any(|m| m.uuid == uuid) {
+        return Ok(CommandOutput::AlreadyInstalled {
+            uuid: uuid.to_string(),
+        });
+    }
+
+    let registry = tasks::fetch_registry_async()
+        .await
+        .map_err(CommandError::Failed)?;
+    let uuid_str = uuid.to_string();
+    let module = registry
+        .find_by_uuid(&uuid_str)
+        .ok_or_else(|| CommandError::NotFound(format!("Module not found in registry: {uuid}")))?;
+
+    let module = tasks::install_module_async(
+        uuid_str,
+        module.name.clone(),
+        module.version.clone(),
+        module.repo_url.clone(),
+        module.checksum.clone(),
+        scripts,
+        update_lock,
+    )
+    .await
+    .map_err(install_failure)?;
+
+    Ok(CommandOutput::Installed { module })
+}
+
+async fn remove(uuid: String) -> Result<CommandOutput, CommandError> {
+    let uuid = parse_uuid(&uuid)?;
+    find_installed(&load_installed().await?, &uuid)?;
+
+    let result = tasks::uninstall_module_async(uuid.to_string())
+        .await
+        .map_err(|(_, e)| CommandError::Failed(e))?;
+
+    Ok(CommandOutput::Removed {
+        uuid: result.uuid,
+        script: result.script,
+    })
+}
+
+async fn preview(change: PendingChange) -> Result<CommandOutput, CommandError> {
+    let (change, preview) = tasks::preview_change_async(change).await;
+    Ok(CommandOutput::Preview {
+        uuid: change.uuid().to_string(),
+        preview: preview.map_err(CommandError::Failed)?,
+    })
+}
+
+fn install_failure(failure: InstallFailure) -> CommandError {
+    match failure {
+        InstallFailure::Unapproved(review) => unapproved(&review),
+        InstallFailure::Failed(e) => CommandError::Failed(e),
+    }
+}
+
+fn unapproved(review: &ScriptReview) -> CommandError {
+    CommandError::Failed(format!(
+        "{} risky patterns in the module's scripts:\n{review}\
+         Review them and rerun with --approve-scripts to go ahead",
+        review.finding_count()
+    ))
+}
+
+async fn toggle(
+    uuid: String,
+    enabled: bool,
+    dry_run: bool,
+    scripts: ScriptApproval,
+) -> Result<CommandOutput, CommandError> {
+    let uuid = parse_uuid(&uuid)?;
+    find_installed(&load_installed().await?, &uuid)?;
+    if dry_run {
+        return preview(PendingChange::Toggle {
+            uuid: uuid.to_string(),
+            enabled,
+        })
+        .await;
+    }
+    let uuid = tasks::toggle_module_async(uuid.to_string(), enabled, scripts)
+        .await
+        .map_err(|(_, e)| install_failure(e))?;
+
+    Ok(if enabled {
+        CommandOutput::Enabled { uuid }
+    } else {
+        CommandOutput::Disabled { uuid }
+    })
+}
+
+async fn update(
+    uuid: Option<String>,
+    allow_unsigned: bool,
+    dry_run: bool,
+    scripts: ScriptApproval,
+) -> Result<CommandOutput, CommandError> {
+    let installed = load_installed().await?;
+    let targets: Vec<InstalledModule> = match &uuid {
+        Some(uuid) => vec![find_installed(&installed, &parse_uuid(uuid)?)?.clone()],
+        None => installed,
+    };
+
+    let registry = tasks::refresh_registry_async()
+        .await
+        .map_err(CommandError::Failed)?;
+    let pending = pending_updates(&targets, &registry);
+
+    if pending.is_empty() {
+        return Ok(CommandOutput::Updated {
+            updated: Vec::new(),
+            failed: Vec::new(),
+        });
+    }
+
+    if uuid.is_some()
+        && let Some((update, request)) = pending.first()
+    {
+        if dry_run {
+            return preview(PendingChange::Update {
+                request: request.clone(),
+                allow_unsigned,
+            })
+            .await;
+        }
+        tasks::update_module_async(request.clone(), allow_unsigned, scripts)
+            .await
+            .map_err(CommandError::Failed)?;
+        return Ok(CommandOutput::Updated {
+            updated: vec![update.clone()],
+            failed: Vec::new(),
+        });
+    }
+
+    let requests = pending.iter().map(|(_, request)| request.clone()).collect();
+    let count = tasks::update_all_modules_async(requests, allow_unsigned, scripts)
+        .await
+        .map_err(CommandError::Failed)?;
+    tracing::debug!("Updated {} of {} modules", count, pending.len());
+
+    let after = load_installed().await?;
+    let (updated, failed) = pending.into_iter().map(|(u, _)| u).partition(|u| {
+        after
+            .iter()
+            .any(|m| m.uuid.to_string() == u.uuid && m.version == u.to)
+    });
+
+    Ok(CommandOutput::Updated { updated, failed })
+}
+
+async fn rollback(uuid: String, version: Option<String>) -> Result<CommandOutput, CommandError> {
+    let uuid = parse_uuid(&uuid)?;
+    let installed = load_installed().await?;
+    let module = find_installed(&installed, &uuid)?;
+
+    let version = version
+        .map(|v| {
+            ModuleVersion::try_from(v.as_str())
+                .map_err(|e| CommandError::InvalidArgument(format!("Invalid version '{v}': {e}")))
+        })
+        .transpose()?;
+    if let Some(version) = &version
+        && !module.previous_versions.contains(version)
+    {
+        return Err(CommandError::NotFound(format!(
+            "{uuid} {version} is not kept, available: {}",
+            render_versions(&module.previous_versions)
+        )));
+    }
+    if module.previous_versions.is_empty() {
+        return Err(CommandError::NotFound(format!(
+            "No previous version of {uuid} is kept"
+        )));
+    }
+
+    let from = module.version.clone();
+    let rolled_back = tasks::rollback_module_async(uuid.to_string(), version)
+        .await
+        .map_err(CommandError::Failed)?;
+
+    Ok(CommandOutput::RolledBack {
+        uuid: uuid.to_string(),
+        from,
+        to: rolled_back.version,
+    })
+}
+
+fn render_versions(versions: &[ModuleVersion]) -> String {
+    if versions.is_empty() {
+        return "none".to_string();
+    }
+    versions
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+async fn list() -> Result<CommandOutput, CommandError> {
+    Ok(CommandOutput::List {
+        modules: load_installed().await?,
+    })
+}
+
+async fn bars() -> Result<CommandOutput, CommandError> {
+    let content = waybar_config::load_config()
+        .await
+        .map_err(CommandError::NotFound)?;
+    let bars = waybar_config::list_bars(&content).map_err(CommandError::Failed)?;
+    Ok(CommandOutput::Bars { bars })
+}
+
+async fn move_module(
+    uuid: String,
+    section: String,
+    bar: Option<String>,
+    index: Option<u32>,
+    dry_run: bool,
+) -> Result<CommandOutput, CommandError> {
+    let parsed = parse_uuid(&uuid)?;
+    let section = parse_section(&section)?;
+
+    let modules = load_installed().await?;
+    find_installed(&modules, &parsed)?;
+
+    if let Some(id) = &bar {
+        let content = waybar_config::load_config()
+            .await
+            .map_err(CommandError::NotFound)?;
+        let bars = waybar_config::list_bars(&content).map_err(CommandError::Failed)?;
+        if !bars.iter().any(|b| b.matches(id)) {
+            return Err(CommandError::NotFound(format!(
+                "No bar named {id} in waybar config"
+            )));
+        }
+    }
+
+    let requested = ModulePosition::new(section).on_bar(bar).at(index);
+    if dry_run {
+        return preview(PendingChange::Position {
+            uuid,
+            position: requested,
+        })
+        .await;
+    }
+    tasks::change_module_position_async(uuid.clone(), requested.clone())
+        .await
+        .map_err(CommandError::Failed)?;
+
+    let position = load_installed()
+        .await?
+        .into_iter()
+        .find(|m| m.uuid == parsed)
+        .and_then(|m| m.position)
+        .unwrap_or(requested);
+
+    Ok(CommandOutput::Moved { uuid, position })
+}
+
+fn parse_section(section: &str) -> Result<BarSection, CommandError> {
+    BarSection::all()
+        .iter()
+        .copied()
+        .find(|s| s.to_string().eq_ignore_ascii_case(section))
+        .ok_or_else(|| {
+            CommandError::InvalidArgument(format!(
+                "Invalid section '{section}': expected left, center or right"
+            ))
+        })
+}
+
+async fn sync(manifest: Option<PathBuf>, dry_run: bool) -> Result<CommandOutput, CommandError> {
+    let manifest_path = manifest.unwrap_or_else(paths::manifest_path);
+    if !manifest_path.exists() {
+        return Err(CommandError::NotFound(format!(
+            "Manifest not found: {}",
+            manifest_path.display()
+        )));
+    }
+    let manifest = Manifest::from_file(&manifest_path)
+        .map_err(|e| CommandError::InvalidArgument(e.to_string()))?;
+
+    let installed = load_installed().await?;
+    let actions = plan_sync(&manifest, &installed, load_preferences);
+
+    if dry_run {
+        return Ok(CommandOutput::Synced {
+            dry_run,
+            actions,
+            failed: Vec::new(),
+        });
+    }
+
+    let registry = if actions.iter().any(SyncAction::needs_registry) {
+        Some(
+            tasks::fetch_registry_async()
+                .await
+                .map_err(CommandError::Failed)?,
+        )
+    } else {
+        None
+    };
+
+    let mut applied = Vec::with_capacity(actions.len());
+    let mut failed = Vec::new();
+    let mut failed_uuids = HashSet::new();
+
+    for action in actions {
+        if failed_uuids.contains(action.uuid()) {
+            failed.push(SyncFailure {
+                action,
+                error: "skipped after an earlier failure".to_string(),
+            });
+            continue;
+        }
+
+        match tasks::apply_sync_action(&action, registry.as_ref()).await {
+            Ok(()) => applied.push(action),
+            Err(error) => {
+                failed_uuids.insert(action.uuid().to_string());
+                failed.push(SyncFailure { action, error });
+            }
+        }
+    }
+
+    Ok(CommandOutput::Synced {
+        dry_run,
+        actions: applied,
+        failed,
+    })
+}
+
+async fn backups(action: BackupAction) -> Result<CommandOutput, CommandError> {
+    match action {
+        BackupAction::List => Ok(CommandOutput::Backups {
+            backups: tasks::load_backups_async()
+                .await
+                .map_err(CommandError::Failed)?,
+        }),
+        BackupAction::Diff { id } => {
+            find_backup(&id)?;
+            Ok(CommandOutput::BackupDiff {
+                preview: tasks::diff_backup_async(id.clone())
+                    .await
+                    .map_err(CommandError::Failed)?,
+                id,
+            })
+        }
+        BackupAction::Restore { id } => {
+            find_backup(&id)?;
+            let previous = tasks::restore_backup_async(id.clone())
+                .await
+                .map_err(CommandError::Failed)?;
+            Ok(CommandOutput::BackupRestored {
+                id,
+                previous: previous.map(|backup| backup.id),
+            })
+        }
+        BackupAction::Prune { keep } => {
+            let keep = keep.unwrap_or_else(|| BackupStore::default().keep());
+            Ok(CommandOutput::BackupsPruned {
+                removed: tasks::prune_backups_async(keep)
+                    .await
+                    .map_err(CommandError::Failed)?,
+            })
+        }
+    }
+}
+
+async fn doctor(fix: bool) -> Result<CommandOutput, CommandError> {
+    let drift = tasks::check_drift_async()
+        .await
+        .map_err(CommandError::Failed)?;
+
+    let mut failed = Vec::new();
+    if fix {
+        for item in &drift {
+            if let Err(error) = tasks::fix_drift_async(item.clone()).await {
+                failed.push(DriftFailure {
+                    drift: item.clone(),
+                    error,
+                });
+            }
+        }
+    }
+    Ok(CommandOutput::Doctor { fix, drift, failed })
+}
+
+fn find_backup(id: &str) -> Result<Backup, CommandError> {
+    BackupStore::default().get(id).map_err(|e| match e {
+        BackupError::NotFound(_) => CommandError::NotFound(e.to_string()),
+        e => CommandError::Failed(e.to_string()),
+    })
+}
+
+fn pending_updates(
+    installed: &[InstalledModule],
+    registry: &RegistryIndex,
+) -> Vec<(ModuleUpdate, UpdateRequest)> {
+    installed
+        .iter()
+        .filter_map(|module| {
+            let uuid = module.uuid.to_string();
+            let registry_module = registry.find_by_uuid(&uuid)?;
+            let new_version = registry_module.version.as_ref()?;
+            (new_version > &module.version).then(|| {
+                (
+                    ModuleUpdate {
+                        uuid: uuid.clone(),
+                        from: module.version.clone(),
+                        to: new_version.clone(),
+                    },
+                    UpdateRequest {
+                        uuid,
+                        repo_url: registry_module.repo_url.clone(),
+                        version: new_version.clone(),
+                        checksum: registry_module.checksum.clone(),
+                    },
+                )
+            })
+        })
+        .collect()
+}
+
+fn render_script_result(script: &ScriptResult) -> String {
+    let status = match (script.success, script.exit_code) {
+        (true, _) => "Uninstall script finished successfully".to_string(),
+        (false, Some(code)) => format!("Uninstall script exited with code {code}"),
+        (false, None) => "Uninstall script was terminated".to_string(),
+    };
+
+    let mut lines = vec![status];
+    for (label, content) in [("stdout", &script.stdout), ("stderr", &script.stderr)] {
+        if content.trim().is_empty() {
+            continue;
+        }
+        lines.push(format!("{label}:"));
+        lines.extend(content.trim_end().lines().map(|line| format!("  {line}")));
+    }
+    lines.join("\n")
+}
+
+fn render_position(section: BarSection, bar: Option<&str>) -> String {
+    let section = section.to_string().to_lowercase();
+    match bar {
+        Some(bar) => format!("{section} on {bar}"),
+        None => section,
+    }
+}
+
+fn render_bar_list(bars: &[BarInfo]) -> String {
+    if bars.is_empty() {
+        return "No bars defined".to_string();
+    }
+
+    bars.iter()
+        .map(|bar| format!("{:<12}  {bar}", bar.id()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_drift(fix: bool, drift: &[Drift], failed: &[DriftFailure]) -> String {
+    if drift.is_empty() {
+        return "No problems found".to_string();
+    }
+
+    if fix {
+        return drift
+            .iter()
+            .map(|item| match failed.iter().find(|f| &f.drift == item) {
+                Some(failure) => format!("Failed to fix: {item} ({})", failure.error),
+                None => format!("Fixed: {item}"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    let mut lines = vec![format!(
+        "Found {} problem{}:",
+        drift.len(),
+        if drift.len() == 1 { "" } else { "s" }
+    )];
+    for item in drift {
+        lines.push(format!("  {item}"));
+        lines.push(format!("    fix: {}", item.fix_label()));
+    }
+    lines.push("Run `barforge doctor --fix` to repair them".to_string());
+    lines.join("\n")
+}
+
+fn render_backup_list(backups: &[Backup]) -> String {
+    if backups.is_empty() {
+        return "No backups".to_string();
+    }
+
+    backups
+        .iter()
+        .map(|backup| {
+            let files: Vec<String> = backup
+                .files
+                .iter()
+                .filter(|f| f.stored.is_some())
+                .filter_map(|f| f.path.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .collect();
+            format!(
+                "{}  {}  {}",
+                backup.id,
+                backup
+                    .created_at
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M:%S"),
+                files.join(", ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_module_list(modules: &[InstalledModule]) -> String {
+    if modules.is_empty() {
+        return "No modules installed".to_string();
+    }
+
+    let uuid_width = modules
+        .iter()
+        .map(|m| m.uuid.to_string().len())
+        .max()
+        .unwrap_or(0);
+    let version_width = modules
+        .iter()
+        .map(|m| m.version.to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    modules
+        .iter()
+        .map(|m| {
+            let state = if m.enabled { "enabled" } else { "disabled" };
+            let position = m.position.clone().unwrap_or_default();
+            let mut line = format!(
+                "{:<uuid_width$}  {:<version_width$}  {:<8}  {}",
+                m.uuid.to_string(),
+                m.version.to_string(),
+                state,
+                render_position(position.section, position.bar.as_deref()),
+            );
+            if m.has_update()
+                && let Some(v) = &m.registry_version
+            {
+                line.push_str(&format!("  (update available: {v})"));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{InstalledModuleBuilder, RegistryModuleBuilder, test_registry};
+
+    #[test]
+    fn command_error_exit_codes() {
+        assert_eq!(
+            CommandError::InvalidArgument(String::new()).exit_code(),
+            EXIT_USAGE
+        );
+        assert_eq!(
+            CommandError::NotFound(String::new()).exit_code(),
+            EXIT_NOT_FOUND
+        );
+        assert_eq!(
+            CommandError::Failed(String::new()).exit_code(),
+            EXIT_FAILURE
+        );
+    }
+
+    #[test]
+    fn parse_uuid_rejects_invalid() {
+        let err = parse_uuid("no-at-sign").unwrap_err();
+        assert!(matches!(err, CommandError::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn find_installed_reports_not_found() {
+        let modules = vec![InstalledModuleBuilder::new("clock").build()];
+        let uuid = ModuleUuid::try_from("weather@test").unwrap();
+        let err = find_installed(&modules, &uuid).unwrap_err();
+        assert_eq!(err.exit_code(), EXIT_NOT_FOUND);
+    }
+
+    #[test]
+    fn pending_updates_only_includes_newer_versions() {
+        let installed = vec![
+            InstalledModuleBuilder::new("weather")
+                .version("1.0.0")
+                .build(),
+            InstalledModuleBuilder::new("clock")
+                .version("2.0.0")
+                .build(),
+            InstalledModuleBuilder::new("local").build(),
+        ];
+        let registry = test_registry(vec![
+            RegistryModuleBuilder::new("weather")
+                .author("test")
+                .version("1.2.0")
+                .checksum("abc123")
+                .build(),
+            RegistryModuleBuilder::new("clock")
+                .author("test")
+                .version("2.0.0")
+                .build(),
+        ]);
+
+        let pending = pending_updates(&installed, &registry);
+
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].0.uuid, "weather@test");
+        assert_eq!(pending[0].0.to.to_string(), "1.2.0");
+        assert_eq!(pending[0].1.repo_url, "https://github.com/test/weather");
+        assert_eq!(pending[0].1.version.to_string(), "1.2.0");
+        assert_eq!(pending[0].1.checksum.as_deref(), Some("abc123"));
+    }
+
+    #[test]
+    fn preview_output_renders_diff_and_fails_on_issues() {
+        use crate::services::FileChange;
+        use crate::services::waybar_config::ConfigIssue;
+
+        let mut output = CommandOutput::Preview {
+            uuid: "weather@test".to_string(),
+            preview: ConfigPreview {
+                changes: FileChange::between(PathBuf::from("style.css"), "a\n", "b\n")
+                    .into_iter()
+                    .collect(),
+                issues: Vec::new(),
+                ..ConfigPreview::default()
+            },
+        };
+        assert_eq!(output.exit_code(), EXIT_SUCCESS);
+        assert!(output.render_text().contains("-a\n+b"));
+
+        if let CommandOutput::Preview { preview, .. } = &mut output {
+            preview.issues.push(ConfigIssue::DuplicateEntry {
+                bar: "Bar 1".to_string(),
+                name: "clock".to_string(),
+            });
+        }
+        assert_eq!(output.exit_code(), EXIT_FAILURE);
+        assert!(output.render_text().contains("clock is listed twice"));
+    }
+
+    #[test]
+    fn updated_output_with_failures_exits_nonzero() {
+        let entry = ModuleUpdate {
+            uuid: "weather@test".to_string(),
+            from: ModuleVersion::try_from("1.0.0").unwrap(),
+            to: ModuleVersion::try_from("1.1.0").unwrap(),
+        };
+        let ok = CommandOutput::Updated {
+            updated: vec![entry.clone()],
+            failed: Vec::new(),
+        };
+        let partial = CommandOutput::Updated {
+            updated: Vec::new(),
+            failed: vec![entry],
+        };
+        assert_eq!(ok.exit_code(), EXIT_SUCCESS);
+        assert_eq!(partial.exit_code(), EXIT_FAILURE);
+    }
+
+    #[test]
+    fn json_output_is_tagged_with_result() {
+        let output = CommandOutput::Enabled {
+            uuid: "weather@test".to_string(),
+        };
+        let body = JsonSuccess {
+            ok: true,
+            output: &output,
+        };
+        let value = serde_json::to_value(&body).unwrap();
+        assert_eq!(value["ok"], true);
+        assert_eq!(value["result"], "enabled");
+        assert_eq!(value["uuid"], "weather@test");
+    }
+
+    #[test]
+    fn render_module_list_shows_state_and_updates() {
+        let modules = vec![
+            InstalledModuleBuilder::new("weather")
+                .registry_version("1.1.0")
+                .build(),
+            InstalledModuleBuilder::new("clock").enabled(false).build(),
+        ];
+        let text = render_module_list(&modules);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("weather@test"));
+        assert!(lines[0].contains("enabled"));
+        assert!(lines[0].contains("update available: 1.1.0"));
+        assert!(lines[1].contains("disabled"));
+    }
+
+    #[test]
+    fn render_bar_list_shows_ids_and_outputs() {
+        let bars = vec![
+            BarInfo {
+                index: 0,
+                name: Some("main".to_string()),
+                outputs: vec!["DP-1".to_string()],
+            },
+            BarInfo {
+                index: 1,
+                name: None,
+                outputs: Vec::new(),
+            },
+        ];
+        let text = render_bar_list(&bars);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("main"));
+        assert!(lines[0].contains("main (DP-1)"));
+        assert!(lines[1].starts_with("#2"));
+    }
+
+    #[test]
+    fn backup_list_names_stored_files() {
+        let backup = Backup {
+            id: "20260101-120000-000".to_string(),
+            created_at: chrono::Utc::now(),
+            files: vec![
+                crate::services::BackupFile {
+                    path: PathBuf::from("/waybar/config.jsonc"),
+                    stored: Some("0-config.jsonc".to_string()),
+                },
+                crate::services::BackupFile {
+                    path: PathBuf::from("/waybar/barforge-modules.jsonc"),
+                    stored: None,
+                },
+                crate::services::BackupFile {
+                    path: PathBuf::from("/waybar/style.css"),
+                    stored: Some("2-style.css".to_string()),
+                },
+            ],
+        };
+
+        let text = render_backup_list(&[backup]);
+        assert!(text.starts_with("20260101-120000-000  "));
+        assert!(text.ends_with("config.jsonc, style.css"));
+        assert_eq!(render_backup_list(&[]), "No backups");
+    }
+
+    #[test]
+    fn doctor_output_fails_until_drift_is_fixed() {
+        let drift = vec![Drift::NotPlaced {
+            uuid: "weather@test".to_string(),
+            module_name: "custom/weather".to_string(),
+        }];
+
+        let found = CommandOutput::Doctor {
+            fix: false,
+            drift: drift.clone(),
+            failed: Vec::new(),
+        };
+        assert_eq!(found.exit_code(), EXIT_FAILURE);
+        assert!(
+            found
+                .render_text()
+                .contains("weather@test is enabled but custom/weather is not on any bar")
+        );
+
+        let fixed = CommandOutput::Doctor {
+            fix: true,
+            drift: drift.clone(),
+            failed: Vec::new(),
+        };
+        assert_eq!(fixed.exit_code(), EXIT_SUCCESS);
+        assert!(fixed.render_text().starts_with("Fixed: weather@test"));
+
+        let failed = CommandOutput::Doctor {
+            fix: true,
+            drift: drift.clone(),
+            failed: vec![DriftFailure {
+                drift: drift[0].clone(),
+                error: "no config".to_string(),
+            }],
+        };
+        assert_eq!(failed.exit_code(), EXIT_FAILURE);
+        assert!(failed.render_text().ends_with("(no config)"));
+    }
+
+    #[test]
+    fn moved_output_names_target_bar() {
+        let output = CommandOutput::Moved {
+            uuid: "weather@test".to_string(),
+            position: ModulePosition::new(BarSection::Left).on_bar(Some("DP-1".to_string())),
+        };
+        assert_eq!(output.render_text(), "Moved weather@test to left on DP-1");
+
+        let output = CommandOutput::Moved {
+            uuid: "weather@test".to_string(),
+            position: ModulePosition::new(BarSection::Right).at(Some(2)),
+        };
+        assert_eq!(
+            output.render_text(),
+            "Moved weather@test to right at index 2"
+        );
+    }
+
+    #[test]
+    fn parse_section_is_case_insensitive() {
+        assert_eq!(parse_section("Right").unwrap(), BarSection::Right);
+        assert!(matches!(
+            parse_section("top"),
+            Err(CommandError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn synced_output_renders_dry_run_plan() {
+        let output = CommandOutput::Synced {
+            dry_run: true,
+            actions: vec![SyncAction::Remove {
+                uuid: "clock@test".to_string(),
+            }],
+            failed: Vec::new(),
+        };
+        assert_eq!(
+            output.render_text(),
+            "Would apply 1 change:\n  - remove clock@test"
+        );
+        assert_eq!(output.exit_code(), EXIT_SUCCESS);
+    }
+
+    #[test]
+    fn synced_output_with_failures_exits_nonzero() {
+        let output = CommandOutput::Synced {
+            dry_run: false,
+            actions: Vec::new(),
+            failed: vec![SyncFailure {
+                action: SyncAction::Enable {
+                    uuid: "clock@test".to_string(),
+                },
+                error: "boom".to_string(),
+            }],
+        };
+        assert_eq!(output.exit_code(), EXIT_FAILURE);
+        let value = serde_json::to_value(&output).unwrap();
+        assert_eq!(value["failed"][0]["action"], "enable");
+        assert_eq!(value["failed"][0]["error"], "boom");
+    }
+
+    #[test]
+    fn removed_output_includes_script_result() {
+        let output = CommandOutput::Removed {
+            uuid: "weather@test".to_string(),
+            script: Some(ScriptResult {
+                success: false,
+                exit_code: Some(2),
+                stdout: "cleaning up\n".to_string(),
+                stderr: "missing cache\n".to_string(),
+            }),
+        };
+        assert_eq!(
+            output.render_text(),
+            "Removed weather@test\nUninstall script exited with code 2\nstdout:\n  cleaning up\nstderr:\n  missing cache"
+        );
+        let value = serde_json::to_value(&output).unwrap();
+        assert_eq!(value["script"]["exit_code"], 2);
+    }
+
+    #[test]
+    fn rolled_back_output_renders_versions() {
+        let output = CommandOutput::RolledBack {
+            uuid: "weather@test".to_string(),
+            from: ModuleVersion::try_from("1.2.0").unwrap(),
+            to: ModuleVersion::try_from("1.1.0").unwrap(),
+        };
+        assert_eq!(
+            output.render_text(),
+            "Rolled back weather@test 1.2.0 -> 1.1.0"
+        );
+        assert_eq!(render_versions(&[]), "none");
+    }
+
+    #[test]
+    fn render_empty_list() {
+        assert_eq!(render_module_list(&[]), "No modules installed");
+    }
+}
@@ -0,0 +1,550 @@
+mod headless;
+
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+pub use headless::{CommandError, CommandOutput, EXIT_FAILURE, EXIT_NOT_FOUND, EXIT_SUCCESS};
+
+#[derive(Parser)]
+#[command(name = "barforge")]
+#[command(about = "Barforge module manager for Waybar")]
+#[command(version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    #[arg(long, global = true, help = "Print machine-readable JSON output")]
+    pub json: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    #[command(about = "Launch the graphical interface (default)")]
+    Gui,
+
+    #[command(about = "Install a module from the registry")]
+    Install {
+        uuid: String,
+        #[arg(
+            long,
+            help = "Run module scripts even when they contain risky patterns"
+        )]
+        approve_scripts: bool,
+        #[arg(
+            long,
+            help = "Install the registry's version even when barforge.lock pins another"
+        )]
+        update_lock: bool,
+    },
+
+    #[command(about = "Uninstall a module", visible_alias = "uninstall")]
+    Remove { uuid: String },
+
+    #[command(about = "Enable an installed module in the Waybar config")]
+    Enable {
+        uuid: String,
+        #[arg(
+            long,
+            help = "Print the changes to the Waybar files without applying them"
+        )]
+        dry_run: bool,
+        #[arg(
+            long,
+            help = "Run module scripts even when they contain risky patterns"
+        )]
+        approve_scripts: bool,
+    },
+
+    #[command(about = "Disable an installed module in the Waybar config")]
+    Disable {
+        uuid: String,
+        #[arg(
+            long,
+            help = "Print the changes to the Waybar files without applying them"
+        )]
+        dry_run: bool,
+    },
+
+    #[command(about = "Update one module, or every module with --all")]
+    Update {
+        #[arg(required_unless_present = "all")]
+        uuid: Option<String>,
+        #[arg(long, conflicts_with = "uuid")]
+        all: bool,
+        #[arg(
+            long,
+            help = "Fall back to unsigned GitHub downloads when no signed package exists"
+        )]
+        allow_unsigned: bool,
+        #[arg(
+            long,
+            conflicts_with = "all",
+            help = "Print the changes to the Waybar files without applying them"
+        )]
+        dry_run: bool,
+        #[arg(
+            long,
+            help = "Run module scripts even when they contain risky patterns"
+        )]
+        approve_scripts: bool,
+    },
+
+    #[command(about = "Roll a module back to a previously installed version")]
+    Rollback {
+        uuid: String,
+        #[arg(help = "Version to restore (defaults to the most recent one)")]
+        version: Option<String>,
+    },
+
+    #[command(about = "List installed modules")]
+    List,
+
+    #[command(about = "List the bars defined in the Waybar config")]
+    Bars,
+
+    #[command(about = "Move a module to another section or bar")]
+    Move {
+        uuid: String,
+        #[arg(help = "Bar section: left, center or right")]
+        section: String,
+        #[arg(long, help = "Bar name, output or #index (defaults to the first bar)")]
+        bar: Option<String>,
+        #[arg(
+            long,
+            help = "Position within the section, starting at 0 (defaults to the end)"
+        )]
+        index: Option<u32>,
+        #[arg(
+            long,
+            help = "Print the changes to the Waybar files without applying them"
+        )]
+        dry_run: bool,
+    },
+
+    #[command(about = "Converge installed modules to match barforge.toml")]
+    Sync {
+        #[arg(long, help = "Show what would change without applying it")]
+        dry_run: bool,
+        #[arg(
+            long,
+            help = "Path to the manifest (defaults to ~/.config/barforge/barforge.toml)"
+        )]
+        manifest: Option<PathBuf>,
+    },
+
+    #[command(about = "Check that installed.json matches the Waybar config and module files")]
+    Doctor {
+        #[arg(long, help = "Repair every problem that is found")]
+        fix: bool,
+    },
+
+    #[command(about = "List, diff, restore or prune backups of the Waybar files")]
+    Backups {
+        #[command(subcommand)]
+        action: BackupAction,
+    },
+
+    #[command(about = "Run a command under a module's declared sandbox permissions")]
+    RunSandboxed {
+        uuid: String,
+        #[arg(last = true, required = true, help = "Command to run, after --")]
+        command: Vec<String>,
+    },
+
+    #[command(hide = true)]
+    InternalSandboxExec {
+        #[arg(long)]
+        script: PathBuf,
+        #[arg(long)]
+        module_dir: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BackupAction {
+    #[command(about = "List backups, newest first")]
+    List,
+
+    #[command(about = "Show what restoring a backup would change")]
+    Diff { id: String },
+
+    #[command(about = "Restore the Waybar config and stylesheet from a backup")]
+    Restore { id: String },
+
+    #[command(about = "Remove all but the newest backups")]
+    Prune {
+        #[arg(
+            long,
+            help = "Number of backups to keep (defaults to the retention setting)"
+        )]
+        keep: Option<usize>,
+    },
+}
+
+impl Cli {
+    pub fn run_headless(command: Commands, json: bool) -> ! {
+        let runtime = match tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                eprintln!("error: failed to start async runtime: {e}");
+                std::process::exit(EXIT_FAILURE);
+            }
+        };
+
+        let code = runtime.block_on(headless::run(command, json));
+        std::process::exit(code);
+    }
+
+    pub fn run_sandbox_exec(script: PathBuf, module_dir: PathBuf) -> ! {
+        let config_json = std::env::var("BARFORGE_SANDBOX_CONFIG")
+            .expect("Missing BARFORGE_SANDBOX_CONFIG environment variable");
+
+        let config: crate::security::sandbox::SandboxConfig =
+            serde_json::from_str(&config_json).expect("Invalid sandbox configuration JSON");
+
+        enforce_sandbox(&config);
+
+        let status = std::process::Command::new("bash")
+            .arg(&script)
+            .current_dir(&module_dir)
+            .env("MODULE_DIR", &module_dir)
+            .status()
+            .expect("Failed to run script");
+
+        std::process::exit(status.code().unwrap_or(1));
+    }
+
+    /// Replaces this process with `command`, run in the module's directory
+    /// under the permissions its `Package.toml` declares. Waybar runs module
+    /// commands through this when sandboxed module commands are turned on.
+    pub fn run_sandboxed(uuid: String, command: Vec<String>) -> ! {
+        use std::os::unix::process::CommandExt;
+
+        let installed = match crate::services::StateStore::default().load() {
+            Ok(installed) => installed,
+            Err(e) => {
+                eprintln!("error: {e}");
+                std::process::exit(EXIT_FAILURE);
+            }
+        };
+        let Some(module) = installed.into_iter().find(|m| m.uuid.to_string() == uuid) else {
+            eprintln!("error: Module not installed: {uuid}");
+            std::process::exit(EXIT_NOT_FOUND);
+        };
+        let module_dir = module.install_path;
+        let cache_dir = crate::services::paths::module_cache_path(&uuid);
+
+        let config = match crate::services::runtime_sandbox_config(
+            &module_dir,
+            &cache_dir,
+            crate::services::load_settings().seccomp_filter,
+        ) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("error: {e}");
+                std::process::exit(EXIT_FAILURE);
+            }
+        };
+        enforce_sandbox(&config);
+
+        let Some((program, args)) = command.split_first() else {
+            std::process::exit(EXIT_FAILURE);
+        };
+        let error = std::process::Command::new(program)
+            .args(args)
+            .current_dir(&module_dir)
+            .env("MODULE_DIR", &module_dir)
+            .env("MODULE_CACHE_DIR", &cache_dir)
+            .env("TMPDIR", &cache_dir)
+            .exec();
+        eprintln!("error: Failed to run {program}: {error}");
+        std::process::exit(127);
+    }
+}
+
+/// Restricts this process, exiting if the restrictions cannot be applied.
+/// Kernels without Landlock only get a warning.
+fn enforce_sandbox(config: &crate::security::sandbox::SandboxConfig) {
+    use crate::security::sandbox::SandboxLayer;
+
+    let result = crate::security::sandbox::apply(config);
+
+    if config.seccomp
+        && result.status.allows_execution()
+        && !result.layers.contains(&SandboxLayer::Seccomp)
+    {
+        eprintln!(
+            "WARNING: seccomp filter not supported on {}",
+            std::env::consts::ARCH
+        );
+    }
+    let layers: Vec<String> = result.layers.iter().map(ToString::to_string).collect();
+    tracing::debug!("Sandbox layers: {}", layers.join(", "));
+
+    match result.status {
+        crate::security::sandbox::SandboxStatus::FullyEnforced
+        | crate::security::sandbox::SandboxStatus::PartiallyEnforced => {}
+        crate::security::sandbox::SandboxStatus::NotSupported => {
+            eprintln!("WARNING: Landlock sandbox not supported on this kernel");
+        }
+        crate::security::sandbox::SandboxStatus::Failed => {
+            eprintln!("ERROR: Failed to apply sandbox restrictions");
+            std::process::exit(127);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_parses_no_args_as_none() {
+        let cli = Cli::parse_from(["barforge"]);
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
+    fn cli_parses_gui_command() {
+        let cli = Cli::parse_from(["barforge", "gui"]);
+        assert!(matches!(cli.command, Some(Commands::Gui)));
+    }
+
+    #[test]
+    fn cli_parses_internal_sandbox_exec() {
+        let cli = Cli::parse_from([
+            "barforge",
+            "internal-sandbox-exec",
+            "--script",
+            "/path/to/script.sh",
+            "--module-dir",
+            "/path/to/module",
+        ]);
+        match cli.command {
+            Some(Commands::InternalSandboxExec { script, module_dir }) => {
+                assert_eq!(script, PathBuf::from("/path/to/script.sh"));
+                assert_eq!(module_dir, PathBuf::from("/path/to/module"));
+            }
+            _ => panic!("Expected InternalSandboxExec command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_run_sandboxed() {
+        let cli = Cli::parse_from([
+            "barforge",
+            "run-sandboxed",
+            "weather@test",
+            "--",
+            "sh",
+            "-c",
+            "curl wttr.in",
+        ]);
+        match cli.command {
+            Some(Commands::RunSandboxed { uuid, command }) => {
+                assert_eq!(uuid, "weather@test");
+                assert_eq!(command, vec!["sh", "-c", "curl wttr.in"]);
+            }
+            _ => panic!("Expected RunSandboxed command"),
+        }
+        assert!(Cli::try_parse_from(["barforge", "run-sandboxed", "weather@test"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_install_command() {
+        let cli = Cli::parse_from(["barforge", "install", "weather@test"]);
+        match cli.command {
+            Some(Commands::Install {
+                uuid,
+                approve_scripts,
+                update_lock,
+            }) => {
+                assert_eq!(uuid, "weather@test");
+                assert!(!approve_scripts);
+                assert!(!update_lock);
+            }
+            _ => panic!("Expected Install command"),
+        }
+        assert!(!cli.json);
+    }
+
+    #[test]
+    fn cli_parses_uninstall_alias_as_remove() {
+        let cli = Cli::parse_from(["barforge", "uninstall", "weather@test"]);
+        assert!(matches!(cli.command, Some(Commands::Remove { .. })));
+    }
+
+    #[test]
+    fn cli_parses_global_json_flag_after_subcommand() {
+        let cli = Cli::parse_from(["barforge", "list", "--json"]);
+        assert!(matches!(cli.command, Some(Commands::List)));
+        assert!(cli.json);
+    }
+
+    #[test]
+    fn cli_parses_update_all() {
+        let cli = Cli::parse_from(["barforge", "update", "--all"]);
+        match cli.command {
+            Some(Commands::Update {
+                uuid,
+                all,
+                allow_unsigned,
+                dry_run,
+                approve_scripts,
+            }) => {
+                assert!(uuid.is_none());
+                assert!(all);
+                assert!(!allow_unsigned);
+                assert!(!dry_run);
+                assert!(!approve_scripts);
+            }
+            _ => panic!("Expected Update command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_update_allow_unsigned() {
+        let cli = Cli::parse_from([
+            "barforge",
+            "update",
+            "weather-wttr@barforge",
+            "--allow-unsigned",
+        ]);
+        match cli.command {
+            Some(Commands::Update {
+                uuid,
+                allow_unsigned,
+                ..
+            }) => {
+                assert_eq!(uuid.as_deref(), Some("weather-wttr@barforge"));
+                assert!(allow_unsigned);
+            }
+            _ => panic!("Expected Update command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_rollback_with_optional_version() {
+        let cli = Cli::parse_from(["barforge", "rollback", "weather@test", "1.1.0"]);
+        match cli.command {
+            Some(Commands::Rollback { uuid, version }) => {
+                assert_eq!(uuid, "weather@test");
+                assert_eq!(version.as_deref(), Some("1.1.0"));
+            }
+            _ => panic!("Expected Rollback command"),
+        }
+
+        let cli = Cli::parse_from(["barforge", "rollback", "weather@test"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Rollback { version: None, .. })
+        ));
+    }
+
+    #[test]
+    fn cli_parses_enable_dry_run() {
+        let cli = Cli::parse_from(["barforge", "enable", "weather@test", "--dry-run"]);
+        match cli.command {
+            Some(Commands::Enable { uuid, dry_run, .. }) => {
+                assert_eq!(uuid, "weather@test");
+                assert!(dry_run);
+            }
+            _ => panic!("Expected Enable command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_enable_approve_scripts() {
+        let cli = Cli::parse_from(["barforge", "enable", "weather@test", "--approve-scripts"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Enable {
+                approve_scripts: true,
+                dry_run: false,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn cli_rejects_update_all_dry_run() {
+        assert!(Cli::try_parse_from(["barforge", "update", "--all", "--dry-run"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_move_with_bar() {
+        let cli = Cli::parse_from(["barforge", "move", "weather@test", "left", "--bar", "DP-1"]);
+        match cli.command {
+            Some(Commands::Move {
+                uuid,
+                section,
+                bar,
+                index,
+                dry_run,
+            }) => {
+                assert_eq!(uuid, "weather@test");
+                assert_eq!(section, "left");
+                assert_eq!(bar.as_deref(), Some("DP-1"));
+                assert!(index.is_none());
+                assert!(!dry_run);
+            }
+            _ => panic!("Expected Move command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_sync_dry_run_with_manifest() {
+        let cli = Cli::parse_from([
+            "barforge",
+            "sync",
+            "--dry-run",
+            "--manifest",
+            "/dotfiles/barforge.toml",
+        ]);
+        match cli.command {
+            Some(Commands::Sync { dry_run, manifest }) => {
+                assert!(dry_run);
+                assert_eq!(manifest, Some(PathBuf::from("/dotfiles/barforge.toml")));
+            }
+            _ => panic!("Expected Sync command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_backup_actions() {
+        let cli = Cli::parse_from(["barforge", "backups", "restore", "20260101-120000-000"]);
+        match cli.command {
+            Some(Commands::Backups {
+                action: BackupAction::Restore { id },
+            }) => assert_eq!(id, "20260101-120000-000"),
+            _ => panic!("Expected Backups restore command"),
+        }
+
+        let cli = Cli::parse_from(["barforge", "backups", "prune", "--keep", "3"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Backups {
+                action: BackupAction::Prune { keep: Some(3) }
+            })
+        ));
+    }
+
+    #[test]
+    fn cli_parses_doctor_fix() {
+        let cli = Cli::parse_from(["barforge", "doctor", "--fix"]);
+        assert!(matches!(cli.command, Some(Commands::Doctor { fix: true })));
+    }
+
+    #[test]
+    fn cli_rejects_update_without_target() {
+        assert!(Cli::try_parse_from(["barforge", "update"]).is_err());
+    }
+
+    #[test]
+    fn cli_rejects_update_with_uuid_and_all() {
+        assert!(Cli::try_parse_from(["barforge", "update", "weather@test", "--all"]).is_err());
+    }
+}
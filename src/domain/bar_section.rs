@@ -33,11 +33,13 @@ impl std::fmt::Display for BarSection {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub struct ModulePosition {
     pub section: BarSection,
     #[serde(default)]
     pub order: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bar: Option<String>,
 }
 
 impl ModulePosition {
@@ -45,8 +47,26 @@ impl ModulePosition {
         Self {
             section,
             order: None,
+            bar: None,
         }
     }
+
+    pub fn on_bar(mut self, bar: Option<String>) -> Self {
+        self.bar = bar;
+        self
+    }
+
+    pub fn at(mut self, order: Option<u32>) -> Self {
+        self.order = order;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleMove {
+    Up,
+    Down,
+    Onto(String),
 }
 
 #[cfg(test)]
@@ -77,6 +97,26 @@ mod tests {
         let pos = ModulePosition::new(BarSection::Right);
         assert_eq!(pos.section, BarSection::Right);
         assert!(pos.order.is_none());
+        assert!(pos.bar.is_none());
+    }
+
+    #[test]
+    fn test_module_position_bar_round_trip() {
+        let pos = ModulePosition::new(BarSection::Left).on_bar(Some("DP-1".to_string()));
+        let json = serde_json::to_string(&pos).unwrap();
+        let parsed: ModulePosition = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.bar.as_deref(), Some("DP-1"));
+
+        let legacy: ModulePosition = serde_json::from_str(r#"{"section": "left"}"#).unwrap();
+        assert!(legacy.bar.is_none());
+    }
+
+    #[test]
+    fn test_module_position_order_round_trip() {
+        let pos = ModulePosition::new(BarSection::Right).at(Some(2));
+        let json = serde_json::to_string(&pos).unwrap();
+        let parsed: ModulePosition = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.order, Some(2));
     }
 
     #[test]
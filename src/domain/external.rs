@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+use super::ModulePosition;
+
+const ADOPTED_NAMESPACE: &str = "local";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExternalModule {
+    pub name: String,
+    pub enabled: bool,
+    pub position: ModulePosition,
+    #[serde(default)]
+    pub has_definition: bool,
+}
+
+impl ExternalModule {
+    pub fn is_custom_module(&self) -> bool {
+        self.name.starts_with("custom/")
+    }
+
+    pub fn can_adopt(&self) -> bool {
+        self.is_custom_module() && self.has_definition
+    }
+
+    pub fn same_entry(&self, other: &ExternalModule) -> bool {
+        self.name == other.name && self.position.bar == other.position.bar
+    }
+
+    pub fn adopted_uuid(&self) -> String {
+        let name: String = self
+            .name
+            .trim_start_matches("custom/")
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        format!("{name}@{ADOPTED_NAMESPACE}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::BarSection;
+
+    fn external(name: &str) -> ExternalModule {
+        ExternalModule {
+            name: name.to_string(),
+            enabled: true,
+            position: ModulePosition::new(BarSection::Right),
+            has_definition: true,
+        }
+    }
+
+    #[test]
+    fn only_custom_modules_with_definitions_can_be_adopted() {
+        assert!(external("custom/foo").can_adopt());
+        assert!(!external("clock").can_adopt());
+
+        let mut undefined = external("custom/bar");
+        undefined.has_definition = false;
+        assert!(!undefined.can_adopt());
+    }
+
+    #[test]
+    fn adopted_uuid_is_sanitized() {
+        assert_eq!(external("custom/foo").adopted_uuid(), "foo@local");
+        assert_eq!(external("custom/My Mod#2").adopted_uuid(), "my-mod-2@local");
+    }
+
+    #[test]
+    fn same_entry_compares_name_and_bar() {
+        let a = external("clock");
+        let mut b = external("clock");
+        b.position.section = BarSection::Left;
+        assert!(a.same_entry(&b));
+
+        b.position.bar = Some("DP-1".to_string());
+        assert!(!a.same_entry(&b));
+    }
+}
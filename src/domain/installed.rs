@@ -19,6 +19,8 @@ pub struct InstalledModule {
     pub registry_version: Option<ModuleVersion>,
     #[serde(default)]
     pub position: Option<ModulePosition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previous_versions: Vec<ModuleVersion>,
 }
 
 fn default_installed_at() -> chrono::DateTime<chrono::Utc> {
@@ -61,6 +63,7 @@ mod tests {
             installed_at: chrono::Utc::now(),
             registry_version: None,
             position: None,
+            previous_versions: Vec::new(),
         };
         assert!(module.is_custom_module());
     }
@@ -77,6 +80,7 @@ mod tests {
             installed_at: chrono::Utc::now(),
             registry_version: None,
             position: None,
+            previous_versions: Vec::new(),
         };
         assert!(!module.is_custom_module());
     }
@@ -93,6 +97,7 @@ mod tests {
             installed_at: chrono::Utc::now(),
             registry_version: Some(ModuleVersion::try_from("2.0.0").unwrap()),
             position: None,
+            previous_versions: Vec::new(),
         };
         assert!(module.has_update());
     }
@@ -109,6 +114,7 @@ mod tests {
             installed_at: chrono::Utc::now(),
             registry_version: Some(ModuleVersion::try_from("1.0.0").unwrap()),
             position: None,
+            previous_versions: Vec::new(),
         };
         assert!(!module.has_update());
     }
@@ -125,6 +131,7 @@ mod tests {
             installed_at: chrono::Utc::now(),
             registry_version: None,
             position: None,
+            previous_versions: Vec::new(),
         };
         assert!(!module.has_update());
     }
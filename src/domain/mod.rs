@@ -1,9 +1,11 @@
 mod bar_section;
+mod external;
 mod installed;
 
-pub use bar_section::{BarSection, ModulePosition};
+pub use bar_section::{BarSection, ModuleMove, ModulePosition};
 pub use barforge_registry_types::{
     Author, AuthorProfile, CategoryInfo, ModuleCategory, ModuleUuid, ModuleUuidError,
     ModuleVersion, RegistryIndex, RegistryModule, Review, ReviewUser, ReviewsResponse,
 };
+pub use external::ExternalModule;
 pub use installed::InstalledModule;
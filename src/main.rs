@@ -3,10 +3,12 @@ use clap::Parser;
 use std::io::IsTerminal;
 use tracing_subscriber::{EnvFilter, fmt, prelude::*};
 
-fn setup_tracing() {
+fn setup_tracing(headless: bool) {
     let is_terminal = std::io::stderr().is_terminal();
 
-    let default_filter = if is_terminal {
+    let default_filter = if headless {
+        "barforge=warn"
+    } else if is_terminal {
         "barforge=debug"
     } else {
         "barforge=info"
@@ -57,12 +59,17 @@ fn main() -> iced::Result {
         Some(Commands::InternalSandboxExec { script, module_dir }) => {
             Cli::run_sandbox_exec(script, module_dir);
         }
+        Some(Commands::RunSandboxed { uuid, command }) => Cli::run_sandboxed(uuid, command),
         Some(Commands::Gui) | None => run_gui(),
+        Some(command) => {
+            setup_tracing(true);
+            Cli::run_headless(command, cli.json);
+        }
     }
 }
 
 fn run_gui() -> iced::Result {
-    setup_tracing();
+    setup_tracing(false);
     setup_panic_handler();
 
     tracing::info!(
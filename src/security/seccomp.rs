@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+
+use seccompiler::{
+    BpfProgram, SeccompAction, SeccompCmpArgLen, SeccompCmpOp, SeccompCondition, SeccompFilter,
+    SeccompRule, TargetArch,
+};
+use thiserror::Error;
+
+use super::sandbox::SandboxConfig;
+
+/// Syscalls no module script needs: debugging other processes, kernel
+/// modules and keyrings, mounts and namespaces, and `io_uring`, which can
+/// do file and socket work the other rules would not see.
+const DENIED_SYSCALLS: &[i64] = &[
+    libc::SYS_ptrace,
+    libc::SYS_process_vm_readv,
+    libc::SYS_process_vm_writev,
+    libc::SYS_io_uring_setup,
+    libc::SYS_io_uring_enter,
+    libc::SYS_io_uring_register,
+    libc::SYS_bpf,
+    libc::SYS_perf_event_open,
+    libc::SYS_userfaultfd,
+    libc::SYS_kexec_load,
+    libc::SYS_init_module,
+    libc::SYS_finit_module,
+    libc::SYS_delete_module,
+    libc::SYS_keyctl,
+    libc::SYS_add_key,
+    libc::SYS_request_key,
+    libc::SYS_mount,
+    libc::SYS_umount2,
+    libc::SYS_pivot_root,
+    libc::SYS_swapon,
+    libc::SYS_swapoff,
+    libc::SYS_reboot,
+    libc::SYS_setns,
+    libc::SYS_unshare,
+];
+
+/// The low bits of `socket`'s type argument; the rest are flags.
+const SOCK_TYPE_MASK: u64 = 0xf;
+
+#[derive(Debug, Error)]
+pub enum SeccompError {
+    #[error("Failed to build seccomp filter: {0}")]
+    Build(#[from] seccompiler::BackendError),
+
+    #[error("Failed to install seccomp filter: {0}")]
+    Install(#[from] seccompiler::Error),
+}
+
+/// Installs a filter built from `config` on the calling thread, which
+/// children inherit. Denied calls fail with `EPERM`. Returns `false` on
+/// architectures seccompiler cannot target.
+pub fn apply(config: &SandboxConfig) -> Result<bool, SeccompError> {
+    let Ok(arch) = TargetArch::try_from(std::env::consts::ARCH) else {
+        return Ok(false);
+    };
+    let program = build(config, arch)?;
+    seccompiler::apply_filter(&program)?;
+    Ok(true)
+}
+
+fn build(config: &SandboxConfig, arch: TargetArch) -> Result<BpfProgram, SeccompError> {
+    let filter = SeccompFilter::new(
+        rules(config)?,
+        SeccompAction::Allow,
+        SeccompAction::Errno(libc::EPERM as u32),
+        arch,
+    )?;
+    Ok(filter.try_into()?)
+}
+
+/// The denied calls. An empty rule list denies the syscall outright; a
+/// `socket` rule denies it when any one rule's conditions all hold.
+fn rules(config: &SandboxConfig) -> Result<BTreeMap<i64, Vec<SeccompRule>>, SeccompError> {
+    let mut rules: BTreeMap<i64, Vec<SeccompRule>> = DENIED_SYSCALLS
+        .iter()
+        .map(|&syscall| (syscall, Vec::new()))
+        .collect();
+
+    let mut domains = vec![libc::AF_PACKET];
+    if !config.allow_network {
+        domains.extend([libc::AF_INET, libc::AF_INET6]);
+    }
+    if config.deny_unix_sockets {
+        domains.push(libc::AF_UNIX);
+    }
+
+    let mut socket_rules = domains
+        .into_iter()
+        .map(|domain| socket_rule(0, SeccompCmpOp::Eq, domain as u64))
+        .collect::<Result<Vec<_>, _>>()?;
+    socket_rules.push(socket_rule(
+        1,
+        SeccompCmpOp::MaskedEq(SOCK_TYPE_MASK),
+        libc::SOCK_RAW as u64,
+    )?);
+    rules.insert(libc::SYS_socket, socket_rules);
+
+    Ok(rules)
+}
+
+fn socket_rule(arg: u8, op: SeccompCmpOp, value: u64) -> Result<SeccompRule, SeccompError> {
+    let condition = SeccompCondition::new(arg, SeccompCmpArgLen::Dword, op, value)?;
+    Ok(SeccompRule::new(vec![condition])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn denied_domains(config: &SandboxConfig) -> usize {
+        rules(config).unwrap()[&libc::SYS_socket].len() - 1
+    }
+
+    #[test]
+    fn socket_rules_follow_permissions() {
+        assert_eq!(denied_domains(&SandboxConfig::default()), 3);
+
+        let closed = SandboxConfig {
+            deny_unix_sockets: true,
+            ..SandboxConfig::default()
+        };
+        assert_eq!(denied_domains(&closed), 4);
+
+        let open = SandboxConfig {
+            allow_network: true,
+            ..SandboxConfig::default()
+        };
+        assert_eq!(denied_domains(&open), 1);
+    }
+
+    #[test]
+    fn always_denies_tracing_and_io_uring() {
+        let rules = rules(&SandboxConfig::default()).unwrap();
+        for syscall in [libc::SYS_ptrace, libc::SYS_io_uring_setup] {
+            assert!(rules[&syscall].is_empty());
+        }
+    }
+
+    const PROBE_CONFIG: &str = "BARFORGE_SECCOMP_PROBE";
+
+    /// Runs `socket_probe` in a new test process with the filter for
+    /// `config` installed, and returns what opening each socket did there.
+    /// `None` when seccomp is unavailable.
+    fn probe(config: &SandboxConfig) -> Option<String> {
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "security::seccomp::tests::socket_probe"])
+            .args(["--nocapture", "--test-threads=1"])
+            .env(PROBE_CONFIG, serde_json::to_string(config).unwrap())
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let result = stdout
+            .lines()
+            .find_map(|line| line.split_once("probe: "))
+            .map(|(_, result)| result)
+            .unwrap_or_else(|| panic!("socket_probe did not run:\n{stdout}"));
+        (result != "unsupported").then(|| result.to_string())
+    }
+
+    /// Does nothing unless started by `probe`.
+    #[test]
+    fn socket_probe() {
+        let Ok(config) = std::env::var(PROBE_CONFIG) else {
+            return;
+        };
+        let config: SandboxConfig = serde_json::from_str(&config).unwrap();
+        if !apply(&config).unwrap_or(false) {
+            println!("probe: unsupported");
+            return;
+        }
+        let result = |opened: std::io::Result<()>| match opened {
+            Ok(()) => "ok".to_string(),
+            Err(e) if e.raw_os_error() == Some(libc::EPERM) => "EPERM".to_string(),
+            Err(e) => e.to_string(),
+        };
+        let inet = result(std::net::UdpSocket::bind("127.0.0.1:0").map(drop));
+        let unix = result(std::os::unix::net::UnixDatagram::unbound().map(drop));
+        println!("probe: inet={inet} unix={unix}");
+    }
+
+    #[test]
+    fn filter_denies_sockets_the_permissions_do_not_grant() {
+        let Some(closed) = probe(&SandboxConfig::default()) else {
+            return;
+        };
+        assert_eq!(closed, "inet=EPERM unix=ok");
+
+        let open = SandboxConfig {
+            allow_network: true,
+            ..SandboxConfig::default()
+        };
+        assert_eq!(probe(&open).unwrap(), "inet=ok unix=ok");
+
+        let no_unix = SandboxConfig {
+            deny_unix_sockets: true,
+            ..SandboxConfig::default()
+        };
+        assert_eq!(probe(&no_unix).unwrap(), "inet=EPERM unix=EPERM");
+    }
+
+    #[test]
+    fn filter_compiles_for_this_architecture() {
+        if let Ok(arch) = TargetArch::try_from(std::env::consts::ARCH) {
+            assert!(!build(&SandboxConfig::default(), arch).unwrap().is_empty());
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::services::config_preview::{ConfigPreview, FileChange};
+use crate::services::own_writes::record_own_write;
+use crate::services::paths;
+
+pub const DEFAULT_BACKUP_RETENTION: usize = 20;
+
+const BACKUP_MANIFEST: &str = "backup.json";
+
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("Failed to access backups: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Failed to parse backup manifest: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("Backup not found: {0}")]
+    NotFound(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub path: PathBuf,
+    /// Name of the copy inside the backup, or `None` if the file did not exist.
+    pub stored: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backup {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<BackupFile>,
+}
+
+pub fn backups_dir() -> PathBuf {
+    paths::data_dir().join("backups")
+}
+
+/// Snapshots of the Waybar config and stylesheet, taken together before
+/// Barforge overwrites them.
+#[derive(Debug, Clone)]
+pub struct BackupStore {
+    dir: PathBuf,
+    keep: usize,
+}
+
+impl Default for BackupStore {
+    fn default() -> Self {
+        Self::new(
+            backups_dir(),
+            crate::services::load_settings().backup_retention,
+        )
+    }
+}
+
+impl BackupStore {
+    pub fn new(dir: impl Into<PathBuf>, keep: usize) -> Self {
+        Self {
+            dir: dir.into(),
+            keep,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn keep(&self) -> usize {
+        self.keep
+    }
+
+    /// Copies `paths` into a new backup and prunes old ones down to the
+    /// retention count. Does nothing when retention is zero.
+    pub fn snapshot(&self, paths: &[PathBuf]) -> Result<Option<Backup>, BackupError> {
+        if self.keep == 0 {
+            return Ok(None);
+        }
+        let backup = self.create(paths)?;
+        self.prune(self.keep)?;
+        tracing::info!(
+            "Backed up waybar files to {}",
+            self.backup_dir(&backup.id).display()
+        );
+        Ok(Some(backup))
+    }
+
+    pub fn create(&self, paths: &[PathBuf]) -> Result<Backup, BackupError> {
+        fs::create_dir_all(&self.dir)?;
+        let created_at = Utc::now();
+        let id = self.reserve_id(created_at)?;
+        let dir = self.backup_dir(&id);
+
+        let copied = (|| -> Result<Backup, BackupError> {
+            let mut files = Vec::new();
+            for (index, path) in paths.iter().enumerate() {
+                let stored = match fs::read(path) {
+                    Ok(content) => {
+                        let name = path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        let name = format!("{index}-{name}");
+                        fs::write(dir.join(&name), content)?;
+                        Some(name)
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                    Err(e) => return Err(e.into()),
+                };
+                files.push(BackupFile {
+                    path: path.clone(),
+                    stored,
+                });
+            }
+            let backup = Backup {
+                id: id.clone(),
+                created_at,
+                files,
+            };
+            fs::write(
+                dir.join(BACKUP_MANIFEST),
+                serde_json::to_string_pretty(&backup)?,
+            )?;
+            Ok(backup)
+        })();
+
+        if copied.is_err() {
+            let _ = fs::remove_dir_all(&dir);
+        }
+        copied
+    }
+
+    /// Lists backups, newest first.
+    pub fn list(&self) -> Result<Vec<Backup>, BackupError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut backups: Vec<Backup> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let id = entry.file_name().to_string_lossy().into_owned();
+                match self.get(&id) {
+                    Ok(backup) => Some(backup),
+                    Err(e) => {
+                        tracing::debug!("Skipping backup {id}: {e}");
+                        None
+                    }
+                }
+            })
+            .collect();
+        backups.sort_by(|a, b| {
+            b.created_at
+                .cmp(&a.created_at)
+                .then_with(|| b.id.cmp(&a.id))
+        });
+        Ok(backups)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Backup, BackupError> {
+        if !is_backup_id(id) {
+            return Err(BackupError::NotFound(id.to_string()));
+        }
+        let content = match fs::read_to_string(self.backup_dir(id).join(BACKUP_MANIFEST)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(BackupError::NotFound(id.to_string()));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// What restoring `id` would change in the current files.
+    pub fn diff(&self, id: &str) -> Result<ConfigPreview, BackupError> {
+        let backup = self.get(id)?;
+        let mut preview = ConfigPreview::default();
+        for file in &backup.files {
+            let current = read_if_exists(&file.path)?.unwrap_or_default();
+            let saved = self.saved_content(&backup, file)?.unwrap_or_default();
+            preview
+                .changes
+                .extend(FileChange::between(file.path.clone(), &current, &saved));
+        }
+        Ok(preview)
+    }
+
+    /// Writes the files in `id` back, removing any that did not exist when it
+    /// was taken. The current files are backed up first so the restore can be
+    /// undone.
+    pub fn restore(&self, id: &str) -> Result<Option<Backup>, BackupError> {
+        let backup = self.get(id)?;
+        let contents = self.contents(&backup)?;
+
+        let paths: Vec<PathBuf> = backup.files.iter().map(|f| f.path.clone()).collect();
+        let previous = self.snapshot(&paths)?;
+
+        self.write_back(&backup, contents)?;
+        tracing::info!("Restored waybar files from backup {id}");
+        Ok(previous)
+    }
+
+    /// Writes the files in `id` back without backing up the current ones.
+    pub fn restore_without_backup(&self, id: &str) -> Result<(), BackupError> {
+        let backup = self.get(id)?;
+        let contents = self.contents(&backup)?;
+        self.write_back(&backup, contents)
+    }
+
+    fn contents(&self, backup: &Backup) -> Result<Vec<Option<String>>, BackupError> {
+        backup
+            .files
+            .iter()
+            .map(|file| self.saved_content(backup, file))
+            .collect()
+    }
+
+    fn write_back(
+        &self,
+        backup: &Backup,
+        contents: Vec<Option<String>>,
+    ) -> Result<(), BackupError> {
+        for (file, content) in backup.files.iter().zip(contents) {
+            record_own_write(&file.path);
+            match content {
+                Some(content) => {
+                    if let Some(parent) = file.path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(&file.path, content)?;
+                }
+                None => match fs::remove_file(&file.path) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes all but the newest `keep` backups and returns their ids.
+    pub fn prune(&self, keep: usize) -> Result<Vec<String>, BackupError> {
+        let mut removed = Vec::new();
+        for backup in self.list()?.into_iter().skip(keep) {
+            fs::remove_dir_all(self.backup_dir(&backup.id))?;
+            removed.push(backup.id);
+        }
+        Ok(removed)
+    }
+
+    fn backup_dir(&self, id: &str) -> PathBuf {
+        self.dir.join(id)
+    }
+
+    fn saved_content(
+        &self,
+        backup: &Backup,
+        file: &BackupFile,
+    ) -> Result<Option<String>, BackupError> {
+        match &file.stored {
+            Some(name) if is_backup_id(name) => Ok(Some(fs::read_to_string(
+                self.backup_dir(&backup.id).join(name),
+            )?)),
+            Some(name) => Err(BackupError::NotFound(format!("{}/{name}", backup.id))),
+            None => Ok(None),
+        }
+    }
+
+    fn reserve_id(&self, created_at: DateTime<Utc>) -> Result<String, BackupError> {
+        let base = created_at.format("%Y%m%d-%H%M%S-%3f").to_string();
+        for attempt in 0.. {
+            let id = match attempt {
+                0 => base.clone(),
+                n => format!("{base}-{n}"),
+            };
+            match fs::create_dir(self.backup_dir(&id)) {
+                Ok(()) => return Ok(id),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        unreachable!("backup ids are unbounded")
+    }
+}
+
+fn is_backup_id(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<String>, BackupError> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    struct Fixture {
+        _dir: tempfile::TempDir,
+        store: BackupStore,
+        config: PathBuf,
+        style: PathBuf,
+    }
+
+    fn fixture(keep: usize) -> Fixture {
+        let dir = tempdir().unwrap();
+        let config = dir.path().join("waybar/config.jsonc");
+        let style = dir.path().join("waybar/style.css");
+        fs::create_dir_all(config.parent().unwrap()).unwrap();
+        fs::write(&config, "{\"modules-left\": []}\n").unwrap();
+        fs::write(&style, "#clock {}\n").unwrap();
+        Fixture {
+            store: BackupStore::new(dir.path().join("backups"), keep),
+            _dir: dir,
+            config,
+            style,
+        }
+    }
+
+    impl Fixture {
+        fn paths(&self) -> Vec<PathBuf> {
+            vec![self.config.clone(), self.style.clone()]
+        }
+    }
+
+    #[test]
+    fn snapshot_copies_config_and_style_together() {
+        let f = fixture(5);
+        let backup = f.store.snapshot(&f.paths()).unwrap().unwrap();
+
+        assert_eq!(backup.files.len(), 2);
+        assert!(backup.files.iter().all(|file| file.stored.is_some()));
+        assert_eq!(f.store.list().unwrap(), vec![backup]);
+    }
+
+    #[test]
+    fn zero_retention_disables_snapshots() {
+        let f = fixture(0);
+        assert!(f.store.snapshot(&f.paths()).unwrap().is_none());
+        assert!(f.store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn snapshot_prunes_to_retention() {
+        let f = fixture(2);
+        for _ in 0..4 {
+            f.store.snapshot(&f.paths()).unwrap();
+        }
+        assert_eq!(f.store.list().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn list_is_newest_first() {
+        let f = fixture(5);
+        let first = f.store.create(&f.paths()).unwrap();
+        let second = f.store.create(&f.paths()).unwrap();
+
+        let ids: Vec<String> = f.store.list().unwrap().into_iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec![second.id, first.id]);
+    }
+
+    #[test]
+    fn diff_shows_what_restore_changes() {
+        let f = fixture(5);
+        let backup = f.store.create(&f.paths()).unwrap();
+        fs::write(&f.style, "#clock { color: red; }\n").unwrap();
+
+        let preview = f.store.diff(&backup.id).unwrap();
+        assert_eq!(preview.changes.len(), 1);
+        assert_eq!(preview.changes[0].path, f.style);
+        assert!(
+            preview.changes[0]
+                .hunks
+                .contains("-#clock { color: red; }\n+#clock {}\n")
+        );
+    }
+
+    #[test]
+    fn restore_writes_files_back_and_backs_up_current_ones() {
+        let f = fixture(5);
+        let managed = f.config.with_file_name("barforge-modules.jsonc");
+        let mut paths = f.paths();
+        paths.push(managed.clone());
+        let backup = f.store.create(&paths).unwrap();
+
+        fs::write(&f.config, "{}\n").unwrap();
+        fs::write(&managed, "{}\n").unwrap();
+
+        let previous = f.store.restore(&backup.id).unwrap().unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&f.config).unwrap(),
+            "{\"modules-left\": []}\n"
+        );
+        assert!(!managed.exists());
+        assert_eq!(f.store.diff(&previous.id).unwrap().changes.len(), 2);
+    }
+
+    #[test]
+    fn unknown_or_unsafe_ids_are_not_found() {
+        let f = fixture(5);
+        assert!(matches!(
+            f.store.get("missing"),
+            Err(BackupError::NotFound(_))
+        ));
+        assert!(matches!(
+            f.store.restore("../waybar"),
+            Err(BackupError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn prune_returns_removed_ids() {
+        let f = fixture(5);
+        let old = f.store.create(&f.paths()).unwrap();
+        f.store.create(&f.paths()).unwrap();
+
+        assert_eq!(f.store.prune(1).unwrap(), vec![old.id]);
+        assert_eq!(f.store.list().unwrap().len(), 1);
+    }
+}
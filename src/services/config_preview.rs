@@ -0,0 +1,227 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::services::module_css::StyleIssue;
+use crate::services::script_review::ScriptReview;
+use crate::services::waybar_config::ConfigIssue;
+
+const DIFF_CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub hunks: String,
+}
+
+impl FileChange {
+    pub fn between(path: PathBuf, old: &str, new: &str) -> Option<Self> {
+        (old != new).then(|| Self {
+            path,
+            hunks: unified_hunks(old, new, DIFF_CONTEXT),
+        })
+    }
+}
+
+impl fmt::Display for FileChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.display();
+        write!(f, "--- {path}\n+++ {path}\n{}", self.hunks)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConfigPreview {
+    pub changes: Vec<FileChange>,
+    pub issues: Vec<ConfigIssue>,
+    /// Module CSS problems. Unlike `issues`, these don't block the change.
+    pub style_issues: Vec<StyleIssue>,
+    /// Risky patterns in the module's scripts, which need approval before
+    /// the change is applied.
+    pub scripts: ScriptReview,
+}
+
+impl ConfigPreview {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+            && self.issues.is_empty()
+            && self.style_issues.is_empty()
+            && self.scripts.is_clean()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for ConfigPreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            write!(f, "{change}")?;
+        }
+        for issue in &self.issues {
+            writeln!(f, "error: {issue}")?;
+        }
+        for issue in &self.style_issues {
+            writeln!(f, "warning: {issue}")?;
+        }
+        for line in self.scripts.to_string().lines() {
+            writeln!(f, "review: {line}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+fn line_edits<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Edit<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let width = new_mid.len() + 1;
+    let mut common = vec![0u32; (old_mid.len() + 1) * width];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            common[i * width + j] = if old_mid[i] == new_mid[j] {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut edits: Vec<Edit> = old[..prefix].iter().map(|l| Edit::Same(l)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < old_mid.len() || j < new_mid.len() {
+        if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+            edits.push(Edit::Same(old_mid[i]));
+            i += 1;
+            j += 1;
+        } else if i < old_mid.len()
+            && (j == new_mid.len() || common[(i + 1) * width + j] >= common[i * width + j + 1])
+        {
+            edits.push(Edit::Removed(old_mid[i]));
+            i += 1;
+        } else {
+            edits.push(Edit::Added(new_mid[j]));
+            j += 1;
+        }
+    }
+    edits.extend(old[old.len() - suffix..].iter().map(|l| Edit::Same(l)));
+    edits
+}
+
+pub fn unified_hunks(old: &str, new: &str, context: usize) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let edits = line_edits(&old_lines, &new_lines);
+
+    let changed: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !matches!(e, Edit::Same(_)))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(edits.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    let mut out = String::new();
+    for (start, end) in ranges {
+        let in_old = |e: &&Edit| !matches!(e, Edit::Added(_));
+        let in_new = |e: &&Edit| !matches!(e, Edit::Removed(_));
+        let old_start = edits[..start].iter().filter(in_old).count();
+        let new_start = edits[..start].iter().filter(in_new).count();
+        let old_len = edits[start..end].iter().filter(in_old).count();
+        let new_len = edits[start..end].iter().filter(in_new).count();
+
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start + usize::from(old_len > 0),
+            old_len,
+            new_start + usize::from(new_len > 0),
+            new_len
+        ));
+        for edit in &edits[start..end] {
+            let (marker, line) = match edit {
+                Edit::Same(line) => (' ', line),
+                Edit::Removed(line) => ('-', line),
+                Edit::Added(line) => ('+', line),
+            };
+            out.push(marker);
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_files_have_no_change() {
+        assert!(FileChange::between(PathBuf::from("config"), "a\nb\n", "a\nb\n").is_none());
+    }
+
+    #[test]
+    fn hunks_show_context_and_changes() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n";
+
+        assert_eq!(
+            unified_hunks(old, new, 2),
+            "@@ -3,5 +3,5 @@\n 3\n 4\n-5\n+five\n 6\n 7\n"
+        );
+    }
+
+    #[test]
+    fn distant_changes_get_separate_hunks() {
+        let old: String = (1..=20).map(|i| format!("line {i}\n")).collect();
+        let new = old
+            .replace("line 2\n", "line two\n")
+            .replace("line 19\n", "line nineteen\n");
+
+        let hunks = unified_hunks(&old, &new, 1);
+        assert_eq!(hunks.matches("@@ -").count(), 2);
+        assert!(hunks.contains("-line 19\n+line nineteen\n"));
+    }
+
+    #[test]
+    fn added_file_counts_from_line_one() {
+        let hunks = unified_hunks("", "a\nb\n", 3);
+        assert_eq!(hunks, "@@ -0,0 +1,2 @@\n+a\n+b\n");
+    }
+
+    #[test]
+    fn display_includes_file_headers_and_issues() {
+        let preview = ConfigPreview {
+            changes: vec![FileChange::between(PathBuf::from("style.css"), "a\n", "b\n").unwrap()],
+            issues: Vec::new(),
+            ..ConfigPreview::default()
+        };
+        let text = preview.to_string();
+        assert!(text.starts_with("--- style.css\n+++ style.css\n@@ -1,1 +1,1 @@\n-a\n+b\n"));
+    }
+}
@@ -0,0 +1,273 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::domain::InstalledModule;
+use crate::services::waybar_config::WaybarFiles;
+
+/// A place where `installed.json` and the files on disk disagree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Drift {
+    MissingDirectory { uuid: String },
+    UntrackedDirectory { uuid: String, path: PathBuf },
+    NotPlaced { uuid: String, module_name: String },
+    StillPlaced { uuid: String, module_name: String },
+    MissingDefinition { uuid: String, module_name: String },
+    MissingStyle { uuid: String },
+    LeftoverStyle { uuid: String },
+}
+
+impl Drift {
+    pub fn uuid(&self) -> &str {
+        match self {
+            Drift::MissingDirectory { uuid }
+            | Drift::UntrackedDirectory { uuid, .. }
+            | Drift::NotPlaced { uuid, .. }
+            | Drift::StillPlaced { uuid, .. }
+            | Drift::MissingDefinition { uuid, .. }
+            | Drift::MissingStyle { uuid }
+            | Drift::LeftoverStyle { uuid } => uuid,
+        }
+    }
+
+    /// What fixing this drift does, phrased to follow "Fix:".
+    pub fn fix_label(&self) -> &'static str {
+        match self {
+            Drift::MissingDirectory { .. } => "remove it from the installed modules",
+            Drift::UntrackedDirectory { .. } => "delete the directory",
+            Drift::NotPlaced { .. } => "add it back to the bar",
+            Drift::StillPlaced { .. } => "remove it from the bar",
+            Drift::MissingDefinition { .. } => "restore its definition",
+            Drift::MissingStyle { .. } => "restore its CSS",
+            Drift::LeftoverStyle { .. } => "remove its CSS",
+        }
+    }
+}
+
+impl std::fmt::Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Drift::MissingDirectory { uuid } => {
+                write!(f, "{uuid} is installed but its directory is missing")
+            }
+            Drift::UntrackedDirectory { path, .. } => {
+                write!(f, "{} is not an installed module", path.display())
+            }
+            Drift::NotPlaced { uuid, module_name } => {
+                write!(f, "{uuid} is enabled but {module_name} is not on any bar")
+            }
+            Drift::StillPlaced { uuid, module_name } => {
+                write!(f, "{uuid} is disabled but {module_name} is still on a bar")
+            }
+            Drift::MissingDefinition { uuid, module_name } => {
+                write!(f, "{uuid} is enabled but {module_name} is not defined")
+            }
+            Drift::MissingStyle { uuid } => {
+                write!(f, "{uuid} is enabled but its CSS is missing")
+            }
+            Drift::LeftoverStyle { uuid } => {
+                write!(f, "{uuid} is not enabled but its CSS is still loaded")
+            }
+        }
+    }
+}
+
+/// The directories in `modules_dir` that could hold a module, skipping the
+/// staging, preview and version directories.
+pub fn module_directories(modules_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(modules_dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Compares the installed modules against their directories, the Waybar
+/// config in `files` and the uuids that have a CSS block in `styled`.
+pub fn find_drift(
+    installed: &[InstalledModule],
+    module_dirs: &[PathBuf],
+    files: Option<&WaybarFiles>,
+    styled: &[String],
+) -> Vec<Drift> {
+    let mut drift = Vec::new();
+
+    for path in module_dirs {
+        let uuid = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if !installed.iter().any(|m| m.uuid.to_string() == uuid) {
+            drift.push(Drift::UntrackedDirectory {
+                uuid,
+                path: path.clone(),
+            });
+        }
+    }
+
+    for module in installed {
+        let uuid = module.uuid.to_string();
+        let module_name = module.waybar_module_name.clone();
+        if !module.install_path.is_dir() {
+            drift.push(Drift::MissingDirectory { uuid });
+            continue;
+        }
+
+        if let Some(files) = files {
+            let placed = files.is_placed(&module_name).unwrap_or(module.enabled);
+            if module.enabled && !placed {
+                drift.push(Drift::NotPlaced {
+                    uuid: uuid.clone(),
+                    module_name: module_name.clone(),
+                });
+            } else if !module.enabled && placed {
+                drift.push(Drift::StillPlaced {
+                    uuid: uuid.clone(),
+                    module_name: module_name.clone(),
+                });
+            }
+            if module.enabled
+                && module.install_path.join("config.jsonc").exists()
+                && files.definition(&module_name).is_none()
+            {
+                drift.push(Drift::MissingDefinition {
+                    uuid: uuid.clone(),
+                    module_name,
+                });
+            }
+        }
+
+        let has_style = styled.contains(&uuid);
+        if module.enabled && !has_style && module.install_path.join("style.css").exists() {
+            drift.push(Drift::MissingStyle { uuid });
+        } else if !module.enabled && has_style {
+            drift.push(Drift::LeftoverStyle { uuid });
+        }
+    }
+
+    for uuid in styled {
+        if !installed.iter().any(|m| &m.uuid.to_string() == uuid) {
+            drift.push(Drift::LeftoverStyle { uuid: uuid.clone() });
+        }
+    }
+
+    drift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ModuleUuid, ModuleVersion};
+    use tempfile::TempDir;
+
+    fn module(dir: &TempDir, uuid: &str, name: &str, enabled: bool) -> InstalledModule {
+        let install_path = dir.path().join("modules").join(uuid);
+        std::fs::create_dir_all(&install_path).unwrap();
+        InstalledModule {
+            uuid: ModuleUuid::try_from(uuid).unwrap(),
+            version: ModuleVersion::try_from("1.0.0").unwrap(),
+            install_path,
+            enabled,
+            waybar_module_name: name.to_string(),
+            has_preferences: false,
+            installed_at: chrono::Utc::now(),
+            registry_version: None,
+            position: None,
+            previous_versions: Vec::new(),
+        }
+    }
+
+    fn waybar(dir: &TempDir, config: &str) -> WaybarFiles {
+        let path = dir.path().join("config");
+        std::fs::write(&path, config).unwrap();
+        WaybarFiles::open(&path).unwrap()
+    }
+
+    #[test]
+    fn consistent_modules_have_no_drift() {
+        let dir = tempfile::tempdir().unwrap();
+        let weather = module(&dir, "weather@test", "custom/weather", true);
+        std::fs::write(weather.install_path.join("config.jsonc"), "{}").unwrap();
+        std::fs::write(weather.install_path.join("style.css"), "").unwrap();
+        let clock = module(&dir, "clock@test", "custom/clock", false);
+        let files = waybar(
+            &dir,
+            r#"{"modules-right": ["custom/weather"], "custom/weather": {}}"#,
+        );
+        let dirs = module_directories(&dir.path().join("modules"));
+
+        let drift = find_drift(
+            &[weather, clock],
+            &dirs,
+            Some(&files),
+            &["weather@test".to_string()],
+        );
+        assert!(drift.is_empty(), "{drift:?}");
+    }
+
+    #[test]
+    fn hand_edits_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let weather = module(&dir, "weather@test", "custom/weather", true);
+        std::fs::write(weather.install_path.join("config.jsonc"), "{}").unwrap();
+        std::fs::write(weather.install_path.join("style.css"), "").unwrap();
+        let clock = module(&dir, "clock@test", "custom/clock", false);
+        let files = waybar(&dir, r#"{"modules-right": ["custom/clock"]}"#);
+
+        let drift = find_drift(&[weather, clock], &[], Some(&files), &[]);
+        assert_eq!(
+            drift,
+            vec![
+                Drift::NotPlaced {
+                    uuid: "weather@test".to_string(),
+                    module_name: "custom/weather".to_string(),
+                },
+                Drift::MissingDefinition {
+                    uuid: "weather@test".to_string(),
+                    module_name: "custom/weather".to_string(),
+                },
+                Drift::MissingStyle {
+                    uuid: "weather@test".to_string(),
+                },
+                Drift::StillPlaced {
+                    uuid: "clock@test".to_string(),
+                    module_name: "custom/clock".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn directories_and_css_without_a_record_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let weather = module(&dir, "weather@test", "custom/weather", false);
+        std::fs::remove_dir_all(&weather.install_path).unwrap();
+        std::fs::create_dir_all(dir.path().join("modules/stray@test")).unwrap();
+        std::fs::create_dir_all(dir.path().join("modules/.staging")).unwrap();
+        let dirs = module_directories(&dir.path().join("modules"));
+
+        let drift = find_drift(&[weather], &dirs, None, &["gone@test".to_string()]);
+        assert_eq!(
+            drift,
+            vec![
+                Drift::UntrackedDirectory {
+                    uuid: "stray@test".to_string(),
+                    path: dir.path().join("modules/stray@test"),
+                },
+                Drift::MissingDirectory {
+                    uuid: "weather@test".to_string(),
+                },
+                Drift::LeftoverStyle {
+                    uuid: "gone@test".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::domain::ExternalModule;
+use crate::services::paths;
+
+const DISABLED_EXTERNAL_FILE: &str = "external.json";
+
+pub fn disabled_external_path() -> PathBuf {
+    paths::data_dir().join(DISABLED_EXTERNAL_FILE)
+}
+
+pub fn load_disabled_external(path: &Path) -> Vec<ExternalModule> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_disabled_external(path: &Path, modules: &[ExternalModule]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(modules)?;
+    std::fs::write(path, json)
+}
+
+pub fn merge_external(
+    placed: Vec<ExternalModule>,
+    disabled: Vec<ExternalModule>,
+) -> Vec<ExternalModule> {
+    let hidden: Vec<ExternalModule> = disabled
+        .into_iter()
+        .filter(|d| !placed.iter().any(|p| p.same_entry(d)))
+        .map(|d| ExternalModule {
+            enabled: false,
+            ..d
+        })
+        .collect();
+    placed.into_iter().chain(hidden).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{BarSection, ModulePosition};
+    use tempfile::tempdir;
+
+    fn external(name: &str, enabled: bool) -> ExternalModule {
+        ExternalModule {
+            name: name.to_string(),
+            enabled,
+            position: ModulePosition::new(BarSection::Left),
+            has_definition: false,
+        }
+    }
+
+    #[test]
+    fn disabled_external_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("external.json");
+        assert!(load_disabled_external(&path).is_empty());
+
+        let modules = vec![external("clock", false)];
+        save_disabled_external(&path, &modules).unwrap();
+        assert_eq!(load_disabled_external(&path), modules);
+    }
+
+    #[test]
+    fn merge_external_drops_disabled_entries_that_are_placed_again() {
+        let merged = merge_external(
+            vec![external("clock", true)],
+            vec![external("clock", false), external("battery", false)],
+        );
+
+        assert_eq!(merged.len(), 2);
+        assert!(merged[0].enabled);
+        assert_eq!(merged[1].name, "battery");
+        assert!(!merged[1].enabled);
+    }
+}
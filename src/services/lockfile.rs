@@ -0,0 +1,267 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::domain::{ModuleUuid, ModuleVersion};
+
+const LOCKFILE_VERSION: u32 = 1;
+const LOCKFILE_HEADER: &str = "# This file is generated by Barforge. Do not edit it by hand.\n";
+
+#[derive(Debug, Error)]
+pub enum LockfileError {
+    #[error("Failed to read barforge.lock: {0}")]
+    ReadError(#[from] std::io::Error),
+
+    #[error("Failed to parse barforge.lock: {0}")]
+    ParseError(#[from] toml::de::Error),
+
+    #[error("Failed to serialize barforge.lock: {0}")]
+    SerializeError(#[from] toml::ser::Error),
+
+    #[error("Unsupported barforge.lock version: {0}")]
+    UnsupportedVersion(u32),
+
+    #[error(
+        "Checksum of {uuid} {version} differs from barforge.lock: locked {locked}, got {actual}"
+    )]
+    ChecksumMismatch {
+        uuid: String,
+        version: String,
+        locked: String,
+        actual: String,
+    },
+
+    #[error("{0} is locked to a verified package and cannot be installed unsigned")]
+    UnverifiedSource(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    #[serde(default, rename = "module")]
+    pub modules: Vec<LockedModule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedModule {
+    pub uuid: ModuleUuid,
+    pub version: ModuleVersion,
+    pub sha256: String,
+    pub signature: String,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: LOCKFILE_VERSION,
+            modules: Vec::new(),
+        }
+    }
+}
+
+impl std::str::FromStr for Lockfile {
+    type Err = LockfileError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lockfile: Lockfile = toml::from_str(s)?;
+        if lockfile.version > LOCKFILE_VERSION {
+            return Err(LockfileError::UnsupportedVersion(lockfile.version));
+        }
+        Ok(lockfile)
+    }
+}
+
+impl Lockfile {
+    pub fn load(path: &Path) -> Result<Self, LockfileError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), LockfileError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_toml()?)?;
+        Ok(())
+    }
+
+    pub fn to_toml(&self) -> Result<String, LockfileError> {
+        Ok(format!("{LOCKFILE_HEADER}{}", toml::to_string(self)?))
+    }
+
+    pub fn find(&self, uuid: &str) -> Option<&LockedModule> {
+        self.modules.iter().find(|m| m.uuid.to_string() == uuid)
+    }
+
+    pub fn upsert(&mut self, module: LockedModule) {
+        self.modules.retain(|m| m.uuid != module.uuid);
+        self.modules.push(module);
+        self.modules.sort_by_key(|m| m.uuid.to_string());
+    }
+
+    pub fn remove(&mut self, uuid: &str) -> bool {
+        let before = self.modules.len();
+        self.modules.retain(|m| m.uuid.to_string() != uuid);
+        self.modules.len() != before
+    }
+
+    pub fn verify(
+        &self,
+        uuid: &str,
+        version: &ModuleVersion,
+        sha256: &str,
+    ) -> Result<(), LockfileError> {
+        match self.find(uuid) {
+            Some(locked)
+                if &locked.version == version && !locked.sha256.eq_ignore_ascii_case(sha256) =>
+            {
+                Err(LockfileError::ChecksumMismatch {
+                    uuid: uuid.to_string(),
+                    version: version.to_string(),
+                    locked: locked.sha256.clone(),
+                    actual: sha256.to_string(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The version and checksum to install for `uuid`: the locked ones when
+    /// the lock has an entry, the requested ones otherwise.
+    pub fn pin(
+        &self,
+        uuid: &str,
+        version: ModuleVersion,
+        checksum: Option<String>,
+    ) -> (ModuleVersion, Option<String>) {
+        match self.find(uuid) {
+            Some(locked) => (locked.version.clone(), Some(locked.sha256.clone())),
+            None => (version, checksum),
+        }
+    }
+
+    pub fn verify_unsigned(&self, uuid: &str) -> Result<(), LockfileError> {
+        if self.find(uuid).is_some() {
+            return Err(LockfileError::UnverifiedSource(uuid.to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn locked(name: &str, version: &str, sha256: &str) -> LockedModule {
+        LockedModule {
+            uuid: ModuleUuid::try_from(format!("{name}@test").as_str()).unwrap(),
+            version: ModuleVersion::try_from(version).unwrap(),
+            sha256: sha256.to_string(),
+            signature: "untrusted comment: signature\nRWQ=\n".to_string(),
+        }
+    }
+
+    #[test]
+    fn missing_lockfile_loads_empty() {
+        let dir = tempdir().unwrap();
+        let lockfile = Lockfile::load(&dir.path().join("barforge.lock")).unwrap();
+        assert_eq!(lockfile.version, LOCKFILE_VERSION);
+        assert!(lockfile.modules.is_empty());
+    }
+
+    #[test]
+    fn roundtrips_through_disk() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("barforge.lock");
+
+        let mut lockfile = Lockfile::default();
+        lockfile.upsert(locked("weather", "1.2.0", "abc123"));
+        lockfile.save(&path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with(LOCKFILE_HEADER));
+
+        let loaded = Lockfile::load(&path).unwrap();
+        assert_eq!(loaded.modules, lockfile.modules);
+    }
+
+    #[test]
+    fn upsert_replaces_and_sorts() {
+        let mut lockfile = Lockfile::default();
+        lockfile.upsert(locked("weather", "1.0.0", "aaa"));
+        lockfile.upsert(locked("clock", "1.0.0", "bbb"));
+        lockfile.upsert(locked("weather", "1.1.0", "ccc"));
+
+        assert_eq!(lockfile.modules.len(), 2);
+        assert_eq!(lockfile.modules[0].uuid.to_string(), "clock@test");
+        assert_eq!(lockfile.modules[1].sha256, "ccc");
+    }
+
+    #[test]
+    fn remove_reports_whether_entry_existed() {
+        let mut lockfile = Lockfile::default();
+        lockfile.upsert(locked("weather", "1.0.0", "aaa"));
+        assert!(lockfile.remove("weather@test"));
+        assert!(!lockfile.remove("weather@test"));
+    }
+
+    #[test]
+    fn verify_rejects_different_hash_for_locked_version() {
+        let mut lockfile = Lockfile::default();
+        lockfile.upsert(locked("weather", "1.0.0", "aaa"));
+        let version = ModuleVersion::try_from("1.0.0").unwrap();
+
+        assert!(lockfile.verify("weather@test", &version, "AAA").is_ok());
+        assert!(matches!(
+            lockfile.verify("weather@test", &version, "bbb"),
+            Err(LockfileError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn verify_allows_new_versions_and_unlocked_modules() {
+        let mut lockfile = Lockfile::default();
+        lockfile.upsert(locked("weather", "1.0.0", "aaa"));
+        let newer = ModuleVersion::try_from("1.1.0").unwrap();
+
+        assert!(lockfile.verify("weather@test", &newer, "bbb").is_ok());
+        assert!(lockfile.verify("clock@test", &newer, "bbb").is_ok());
+    }
+
+    #[test]
+    fn pin_prefers_the_locked_version_and_checksum() {
+        let mut lockfile = Lockfile::default();
+        lockfile.upsert(locked("weather", "1.0.0", "aaa"));
+        let newer = ModuleVersion::try_from("1.1.0").unwrap();
+
+        let (version, checksum) = lockfile.pin("weather@test", newer.clone(), None);
+        assert_eq!(version.to_string(), "1.0.0");
+        assert_eq!(checksum.as_deref(), Some("aaa"));
+
+        let (version, checksum) = lockfile.pin("clock@test", newer.clone(), Some("bbb".into()));
+        assert_eq!(version, newer);
+        assert_eq!(checksum.as_deref(), Some("bbb"));
+    }
+
+    #[test]
+    fn verify_unsigned_rejects_locked_modules() {
+        let mut lockfile = Lockfile::default();
+        lockfile.upsert(locked("weather", "1.0.0", "aaa"));
+
+        assert!(matches!(
+            lockfile.verify_unsigned("weather@test"),
+            Err(LockfileError::UnverifiedSource(_))
+        ));
+        assert!(lockfile.verify_unsigned("clock@test").is_ok());
+    }
+
+    #[test]
+    fn rejects_newer_lockfile_format() {
+        let result: Result<Lockfile, _> = "version = 99\n".parse();
+        assert!(matches!(result, Err(LockfileError::UnsupportedVersion(99))));
+    }
+}
@@ -0,0 +1,418 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::domain::{BarSection, InstalledModule, ModuleUuid, ModuleVersion};
+use crate::services::preferences::{ModulePreferences, PreferenceValue};
+
+#[derive(Debug, Error)]
+pub enum ManifestError {
+    #[error("Failed to read barforge.toml: {0}")]
+    ReadError(#[from] std::io::Error),
+
+    #[error("Failed to parse barforge.toml: {0}")]
+    ParseError(#[from] toml::de::Error),
+
+    #[error("Module listed more than once: {0}")]
+    DuplicateModule(String),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default, rename = "module")]
+    pub modules: Vec<ManifestModule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestModule {
+    pub uuid: ModuleUuid,
+    #[serde(default)]
+    pub version: Option<ModuleVersion>,
+    #[serde(default)]
+    pub section: BarSection,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub preferences: BTreeMap<String, PreferenceValue>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl std::str::FromStr for Manifest {
+    type Err = ManifestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let manifest: Manifest = toml::from_str(s)?;
+
+        let mut seen = HashSet::new();
+        for module in &manifest.modules {
+            if !seen.insert(&module.uuid) {
+                return Err(ManifestError::DuplicateModule(module.uuid.to_string()));
+            }
+        }
+
+        Ok(manifest)
+    }
+}
+
+impl Manifest {
+    pub fn from_file(path: &Path) -> Result<Self, ManifestError> {
+        let content = std::fs::read_to_string(path)?;
+        content.parse()
+    }
+
+    pub fn find(&self, uuid: &str) -> Option<&ManifestModule> {
+        self.modules.iter().find(|m| m.uuid.to_string() == uuid)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SyncAction {
+    Install {
+        uuid: String,
+        version: Option<ModuleVersion>,
+    },
+    Remove {
+        uuid: String,
+    },
+    ChangeVersion {
+        uuid: String,
+        from: ModuleVersion,
+        to: ModuleVersion,
+    },
+    Reconfigure {
+        uuid: String,
+        changes: BTreeMap<String, PreferenceValue>,
+        reapply: bool,
+    },
+    Move {
+        uuid: String,
+        from: BarSection,
+        to: BarSection,
+    },
+    Enable {
+        uuid: String,
+    },
+    Disable {
+        uuid: String,
+    },
+}
+
+impl SyncAction {
+    pub fn uuid(&self) -> &str {
+        match self {
+            SyncAction::Install { uuid, .. }
+            | SyncAction::Remove { uuid }
+            | SyncAction::ChangeVersion { uuid, .. }
+            | SyncAction::Reconfigure { uuid, .. }
+            | SyncAction::Move { uuid, .. }
+            | SyncAction::Enable { uuid }
+            | SyncAction::Disable { uuid } => uuid,
+        }
+    }
+
+    pub fn needs_registry(&self) -> bool {
+        matches!(
+            self,
+            SyncAction::Install { .. } | SyncAction::ChangeVersion { .. }
+        )
+    }
+}
+
+impl std::fmt::Display for SyncAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncAction::Install {
+                uuid,
+                version: Some(v),
+            } => write!(f, "+ install {uuid} {v}"),
+            SyncAction::Install {
+                uuid,
+                version: None,
+            } => write!(f, "+ install {uuid}"),
+            SyncAction::Remove { uuid } => write!(f, "- remove {uuid}"),
+            SyncAction::ChangeVersion { uuid, from, to } => {
+                write!(f, "~ version {uuid} {from} -> {to}")
+            }
+            SyncAction::Reconfigure { uuid, changes, .. } => {
+                let keys: Vec<&str> = changes.keys().map(String::as_str).collect();
+                write!(f, "~ reconfigure {uuid} ({})", keys.join(", "))
+            }
+            SyncAction::Move { uuid, from, to } => write!(
+                f,
+                "~ move {uuid} {} -> {}",
+                from.to_string().to_lowercase(),
+                to.to_string().to_lowercase()
+            ),
+            SyncAction::Enable { uuid } => write!(f, "~ enable {uuid}"),
+            SyncAction::Disable { uuid } => write!(f, "~ disable {uuid}"),
+        }
+    }
+}
+
+pub fn plan_sync(
+    manifest: &Manifest,
+    installed: &[InstalledModule],
+    current_preferences: impl Fn(&str) -> ModulePreferences,
+) -> Vec<SyncAction> {
+    let mut actions = Vec::new();
+
+    for module in installed {
+        let uuid = module.uuid.to_string();
+        if manifest.find(&uuid).is_none() {
+            actions.push(SyncAction::Remove { uuid });
+        }
+    }
+
+    for wanted in &manifest.modules {
+        let uuid = wanted.uuid.to_string();
+        let current = installed.iter().find(|m| m.uuid == wanted.uuid);
+
+        let (enabled, section, prefs) = match current {
+            Some(module) => {
+                if let Some(version) = &wanted.version
+                    && version != &module.version
+                {
+                    actions.push(SyncAction::ChangeVersion {
+                        uuid: uuid.clone(),
+                        from: module.version.clone(),
+                        to: version.clone(),
+                    });
+                }
+                let section = module
+                    .position
+                    .as_ref()
+                    .map(|p| p.section)
+                    .unwrap_or_default();
+                (module.enabled, section, current_preferences(&uuid))
+            }
+            None => {
+                actions.push(SyncAction::Install {
+                    uuid: uuid.clone(),
+                    version: wanted.version.clone(),
+                });
+                (false, BarSection::default(), ModulePreferences::new())
+            }
+        };
+
+        let changes: BTreeMap<String, PreferenceValue> = wanted
+            .preferences
+            .iter()
+            .filter(|(key, value)| prefs.get(*key) != Some(*value))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        if !changes.is_empty() {
+            actions.push(SyncAction::Reconfigure {
+                uuid: uuid.clone(),
+                changes,
+                reapply: enabled && wanted.enabled,
+            });
+        }
+
+        if section != wanted.section {
+            actions.push(SyncAction::Move {
+                uuid: uuid.clone(),
+                from: section,
+                to: wanted.section,
+            });
+        }
+
+        match (enabled, wanted.enabled) {
+            (false, true) => actions.push(SyncAction::Enable { uuid }),
+            (true, false) => actions.push(SyncAction::Disable { uuid }),
+            _ => {}
+        }
+    }
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ModulePosition;
+    use crate::testing::InstalledModuleBuilder;
+
+    const SAMPLE_MANIFEST: &str = r#"
+[[module]]
+uuid = "weather@test"
+version = "1.2.0"
+section = "right"
+enabled = true
+
+[module.preferences]
+location = "Berlin"
+interval = 600
+show_humidity = false
+
+[[module]]
+uuid = "clock@test"
+"#;
+
+    #[test]
+    fn parses_manifest() {
+        let manifest: Manifest = SAMPLE_MANIFEST.parse().unwrap();
+        assert_eq!(manifest.modules.len(), 2);
+
+        let weather = &manifest.modules[0];
+        assert_eq!(weather.uuid.to_string(), "weather@test");
+        assert_eq!(weather.version.as_ref().unwrap().to_string(), "1.2.0");
+        assert_eq!(weather.section, BarSection::Right);
+        assert_eq!(
+            weather.preferences.get("interval"),
+            Some(&PreferenceValue::Number(600.0))
+        );
+
+        let clock = &manifest.modules[1];
+        assert!(clock.version.is_none());
+        assert_eq!(clock.section, BarSection::Center);
+        assert!(clock.enabled);
+    }
+
+    #[test]
+    fn rejects_duplicate_modules() {
+        let content = r#"
+[[module]]
+uuid = "clock@test"
+
+[[module]]
+uuid = "clock@test"
+"#;
+        let result: Result<Manifest, _> = content.parse();
+        assert!(matches!(result, Err(ManifestError::DuplicateModule(_))));
+    }
+
+    #[test]
+    fn rejects_invalid_uuid() {
+        let result: Result<Manifest, _> = "[[module]]\nuuid = \"no-namespace\"\n".parse();
+        assert!(matches!(result, Err(ManifestError::ParseError(_))));
+    }
+
+    #[test]
+    fn empty_manifest_removes_everything() {
+        let installed = vec![InstalledModuleBuilder::new("clock").build()];
+        let actions = plan_sync(&Manifest::default(), &installed, |_| {
+            ModulePreferences::new()
+        });
+        assert_eq!(
+            actions,
+            vec![SyncAction::Remove {
+                uuid: "clock@test".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn new_module_is_installed_configured_moved_and_enabled() {
+        let manifest: Manifest = SAMPLE_MANIFEST.parse().unwrap();
+        let installed = vec![InstalledModuleBuilder::new("clock").build()];
+
+        let actions = plan_sync(&manifest, &installed, |_| ModulePreferences::new());
+        let weather: Vec<&SyncAction> = actions
+            .iter()
+            .filter(|a| a.uuid() == "weather@test")
+            .collect();
+
+        assert_eq!(weather.len(), 4);
+        assert!(matches!(
+            weather[0],
+            SyncAction::Install {
+                version: Some(_),
+                ..
+            }
+        ));
+        assert!(matches!(
+            weather[1],
+            SyncAction::Reconfigure { reapply: false, .. }
+        ));
+        assert!(matches!(
+            weather[2],
+            SyncAction::Move {
+                to: BarSection::Right,
+                ..
+            }
+        ));
+        assert!(matches!(weather[3], SyncAction::Enable { .. }));
+    }
+
+    #[test]
+    fn matching_state_produces_no_actions() {
+        let manifest: Manifest = "[[module]]\nuuid = \"clock@test\"\n".parse().unwrap();
+        let installed = vec![InstalledModuleBuilder::new("clock").build()];
+        let actions = plan_sync(&manifest, &installed, |_| ModulePreferences::new());
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn detects_version_section_and_state_changes() {
+        let manifest: Manifest =
+            "[[module]]\nuuid = \"clock@test\"\nversion = \"2.0.0\"\nenabled = false\n"
+                .parse()
+                .unwrap();
+        let mut module = InstalledModuleBuilder::new("clock")
+            .version("1.0.0")
+            .build();
+        module.position = Some(ModulePosition::new(BarSection::Left));
+
+        let actions = plan_sync(&manifest, &[module], |_| ModulePreferences::new());
+
+        assert_eq!(actions.len(), 3);
+        assert!(matches!(actions[0], SyncAction::ChangeVersion { .. }));
+        assert!(matches!(
+            actions[1],
+            SyncAction::Move {
+                from: BarSection::Left,
+                to: BarSection::Center,
+                ..
+            }
+        ));
+        assert!(matches!(actions[2], SyncAction::Disable { .. }));
+    }
+
+    #[test]
+    fn only_changed_preferences_are_reported() {
+        let manifest: Manifest = SAMPLE_MANIFEST.parse().unwrap();
+        let mut module = InstalledModuleBuilder::new("weather")
+            .version("1.2.0")
+            .build();
+        module.position = Some(ModulePosition::new(BarSection::Right));
+
+        let actions = plan_sync(&manifest, &[module], |_| {
+            ModulePreferences::from([
+                (
+                    "location".to_string(),
+                    PreferenceValue::from("Berlin".to_string()),
+                ),
+                ("interval".to_string(), PreferenceValue::Number(300.0)),
+                ("show_humidity".to_string(), PreferenceValue::Bool(false)),
+            ])
+        });
+
+        let reconfigure = actions
+            .iter()
+            .find_map(|a| match a {
+                SyncAction::Reconfigure {
+                    changes, reapply, ..
+                } => Some((changes, *reapply)),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(reconfigure.0.keys().collect::<Vec<_>>(), vec!["interval"]);
+        assert!(reconfigure.1);
+    }
+
+    #[test]
+    fn display_formats_actions() {
+        let action = SyncAction::Move {
+            uuid: "clock@test".to_string(),
+            from: BarSection::Left,
+            to: BarSection::Right,
+        };
+        assert_eq!(action.to_string(), "~ move clock@test left -> right");
+    }
+}
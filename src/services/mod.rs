@@ -1,23 +1,57 @@
+pub mod backups;
+pub mod config_preview;
 pub mod dependency_checker;
+pub mod doctor;
+pub mod external_modules;
+pub mod lockfile;
+pub mod manifest;
+pub mod module_css;
 pub mod module_installer;
+pub mod module_update;
 pub mod omarchy_theme;
+pub mod own_writes;
 pub mod package_config;
 pub mod paths;
 pub mod preferences;
+pub mod script_review;
 pub mod settings;
+pub mod state_store;
 pub mod waybar_config;
+pub mod waybar_process;
 
+pub use backups::{
+    Backup, BackupError, BackupFile, BackupStore, DEFAULT_BACKUP_RETENTION, backups_dir,
+};
+pub use config_preview::{ConfigPreview, FileChange};
 pub use dependency_checker::{
     DepCheckError, DepReport, DepResult, DepSpec, DepType, check_binary, check_dependencies,
     check_python_module, extract_version, is_valid_binary_name, is_valid_python_module_name,
 };
+pub use doctor::{Drift, find_drift, module_directories};
+pub use external_modules::{
+    disabled_external_path, load_disabled_external, merge_external, save_disabled_external,
+};
+pub use lockfile::{LockedModule, Lockfile, LockfileError};
+pub use manifest::{Manifest, ManifestError, ManifestModule, SyncAction, plan_sync};
+pub use module_css::{ModuleStyle, StyleIssue, StyleUpdate, update_module_style};
 pub use module_installer::{
-    InstallError, InstallParams, InstallResult, InstallStage, SecureInstaller,
+    InstallError, InstallParams, InstallResult, InstallStage, ScriptApproval, SecureInstaller,
+    UninstallResult, run_uninstall_script,
+};
+pub use module_update::{
+    ModuleSwap, PREVIOUS_VERSIONS_KEPT, WaybarSnapshot, discard_staging, prune_versions,
+    record_previous_version, stored_version_path,
 };
 pub use omarchy_theme::{OmarchyPalette, is_omarchy_available, load_omarchy_palette};
-pub use package_config::{PackageConfigError, PackageInfo, PackageToml, Permissions};
+pub use own_writes::{OwnWriteGuard, is_own_write, own_write_guard, record_own_write};
+pub use package_config::{
+    PackageConfigError, PackageInfo, PackageToml, Permissions, runtime_sandbox_config,
+};
 pub use preferences::{
     ModulePreferences, PreferenceField, PreferenceValue, PreferencesSchema, SelectOption,
     load_preferences, load_schema, save_preferences,
 };
+pub use script_review::{ReviewedScript, ScriptReview, review_scripts};
 pub use settings::{UserSettings, load_settings, save_settings};
+pub use state_store::{STATE_SCHEMA_VERSION, StateError, StateStore, installed_state_path};
+pub use waybar_process::{ReloadError, ReloadOutcome, WaybarInstance, WaybarReloader};
@@ -0,0 +1,614 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::services::paths::WAYBAR_MANAGED_STYLE;
+
+const MANAGED_STYLE_HEADER: &str =
+    "/* Managed by Barforge. Module styles are added and removed here automatically. */";
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CssError {
+    #[error("unexpected `}}` on line {0}")]
+    UnexpectedBrace(usize),
+
+    #[error("block opened on line {0} is never closed")]
+    Unclosed(usize),
+
+    #[error("comment opened on line {0} is never closed")]
+    UnclosedComment(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StyleIssue {
+    Unparsable {
+        uuid: String,
+        error: String,
+    },
+    OutOfScope {
+        uuid: String,
+        selector: String,
+        line: usize,
+        scope: String,
+    },
+    Conflict {
+        selector: String,
+        uuids: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for StyleIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unparsable { uuid, error } => write!(f, "{uuid}: style.css: {error}"),
+            Self::OutOfScope {
+                uuid,
+                selector,
+                line,
+                scope,
+            } => write!(
+                f,
+                "{uuid}: `{selector}` on line {line} styles more than #{scope}"
+            ),
+            Self::Conflict { selector, uuids } => {
+                write!(f, "`{selector}` is styled by {}", uuids.join(" and "))
+            }
+        }
+    }
+}
+
+/// A selector, or an at-rule such as `@define-color name`, and the line it
+/// starts on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CssRule {
+    pub selector: String,
+    pub line: usize,
+}
+
+/// Lists the selectors and at-rules in `css`. Declarations are skipped.
+pub fn parse_rules(css: &str) -> Result<Vec<CssRule>, CssError> {
+    let mut rules = Vec::new();
+    let mut prelude = String::new();
+    let mut prelude_line = 1;
+    let mut line = 1;
+    let mut chars = css.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let start = line;
+                let mut closed = false;
+                while let Some(c) = chars.next() {
+                    if c == '\n' {
+                        line += 1;
+                    } else if c == '*' && chars.peek() == Some(&'/') {
+                        chars.next();
+                        closed = true;
+                        break;
+                    }
+                }
+                if !closed {
+                    return Err(CssError::UnclosedComment(start));
+                }
+                prelude.push(' ');
+            }
+            '{' => {
+                let start = line;
+                let mut depth = 1;
+                while depth > 0 {
+                    match chars.next() {
+                        Some('{') => depth += 1,
+                        Some('}') => depth -= 1,
+                        Some('\n') => line += 1,
+                        Some(_) => {}
+                        None => return Err(CssError::Unclosed(start)),
+                    }
+                }
+                push_rules(&mut rules, &prelude, prelude_line);
+                prelude.clear();
+            }
+            ';' => {
+                push_rules(&mut rules, &prelude, prelude_line);
+                prelude.clear();
+            }
+            '}' => return Err(CssError::UnexpectedBrace(line)),
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+                if prelude.trim().is_empty() {
+                    prelude_line = line;
+                }
+                prelude.push(c);
+            }
+        }
+    }
+    Ok(rules)
+}
+
+fn push_rules(rules: &mut Vec<CssRule>, prelude: &str, line: usize) {
+    let prelude = prelude.trim();
+    if prelude.is_empty() {
+        return;
+    }
+    if prelude.starts_with('@') {
+        // Only the rule and its name matter, e.g. `@define-color accent`.
+        let name: Vec<&str> = prelude.split_whitespace().take(2).collect();
+        rules.push(CssRule {
+            selector: name.join(" "),
+            line,
+        });
+        return;
+    }
+    rules.extend(
+        split_selectors(prelude)
+            .into_iter()
+            .map(|selector| CssRule {
+                selector: selector.split_whitespace().collect::<Vec<_>>().join(" "),
+                line,
+            }),
+    );
+}
+
+/// Splits a selector list on commas that are not inside `:not(...)` and
+/// similar.
+fn split_selectors(prelude: &str) -> Vec<&str> {
+    let mut selectors = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in prelude.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                selectors.push(prelude[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    selectors.push(prelude[start..].trim());
+    selectors.retain(|selector| !selector.is_empty());
+    selectors
+}
+
+/// The widget id Waybar gives a module, e.g. `custom-weather` for
+/// `custom/weather#2`.
+pub fn module_scope(waybar_module_name: &str) -> String {
+    let name = waybar_module_name
+        .split_once('#')
+        .map_or(waybar_module_name, |(name, _)| name);
+    name.replace('/', "-")
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+fn targets_scope(selector: &str, scope: &str) -> bool {
+    let id = format!("#{scope}");
+    selector.match_indices(&id).any(|(i, _)| {
+        !selector[i + id.len()..]
+            .chars()
+            .next()
+            .is_some_and(is_ident_char)
+    })
+}
+
+/// CSS that Barforge manages for one module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleStyle {
+    pub uuid: String,
+    pub css: String,
+}
+
+impl ModuleStyle {
+    /// Reports CSS that cannot be parsed and selectors that do not target
+    /// the module's own widget. `@import` is reported too, since it can pull
+    /// in anything.
+    pub fn scope_issues(&self, waybar_module_name: &str) -> Vec<StyleIssue> {
+        let scope = module_scope(waybar_module_name);
+        let rules = match parse_rules(&self.css) {
+            Ok(rules) => rules,
+            Err(e) => {
+                return vec![StyleIssue::Unparsable {
+                    uuid: self.uuid.clone(),
+                    error: e.to_string(),
+                }];
+            }
+        };
+        rules
+            .into_iter()
+            .filter(|rule| match rule.selector.strip_prefix('@') {
+                Some(at_rule) => at_rule.starts_with("import"),
+                None => !targets_scope(&rule.selector, &scope),
+            })
+            .map(|rule| StyleIssue::OutOfScope {
+                uuid: self.uuid.clone(),
+                selector: rule.selector,
+                line: rule.line,
+                scope: scope.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Reports selectors, colors and animations defined by more than one module.
+pub fn find_conflicts(styles: &[ModuleStyle]) -> Vec<StyleIssue> {
+    let mut owners: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for style in styles {
+        let Ok(rules) = parse_rules(&style.css) else {
+            continue;
+        };
+        for rule in rules {
+            if rule.selector.starts_with("@import") {
+                continue;
+            }
+            let uuids = owners.entry(rule.selector).or_default();
+            if !uuids.contains(&style.uuid) {
+                uuids.push(style.uuid.clone());
+            }
+        }
+    }
+    owners
+        .into_iter()
+        .filter(|(_, uuids)| uuids.len() > 1)
+        .map(|(selector, uuids)| StyleIssue::Conflict { selector, uuids })
+        .collect()
+}
+
+fn begin_marker(uuid: &str) -> String {
+    format!("/* BEGIN barforge:{uuid} */")
+}
+
+fn end_marker(uuid: &str) -> String {
+    format!("/* END barforge:{uuid} */")
+}
+
+pub fn inject_module_css(existing_css: &str, uuid: &str, module_css: &str) -> String {
+    format!(
+        "{}\n{}\n{}\n{}",
+        existing_css.trim_end(),
+        begin_marker(uuid),
+        module_css.trim(),
+        end_marker(uuid)
+    )
+}
+
+pub fn remove_module_css(css_content: &str, uuid: &str) -> String {
+    let begin_marker = begin_marker(uuid);
+    let end_marker = end_marker(uuid);
+
+    let Some(begin_pos) = css_content.find(&begin_marker) else {
+        return css_content.to_string();
+    };
+
+    let Some(end_pos) = css_content.find(&end_marker) else {
+        return css_content.to_string();
+    };
+
+    let before = css_content[..begin_pos].trim_end();
+    let after = css_content[end_pos + end_marker.len()..].trim_start();
+
+    if after.is_empty() {
+        before.to_string()
+    } else {
+        format!("{}\n{}", before, after)
+    }
+}
+
+/// Splits the marked module blocks out of `css`, returning the rest of the
+/// stylesheet and the blocks in order.
+pub fn take_module_blocks(css: &str) -> (String, Vec<ModuleStyle>) {
+    const BEGIN_PREFIX: &str = "/* BEGIN barforge:";
+
+    let mut rest = css.to_string();
+    let mut blocks = Vec::new();
+    let mut from = 0;
+    while let Some(offset) = rest[from..].find(BEGIN_PREFIX) {
+        let begin = from + offset;
+        let uuid_start = begin + BEGIN_PREFIX.len();
+        let Some(uuid_len) = rest[uuid_start..].find(" */") else {
+            break;
+        };
+        let uuid = rest[uuid_start..uuid_start + uuid_len].to_string();
+        let body_start = uuid_start + uuid_len + " */".len();
+        let Some(body_len) = rest[body_start..].find(&end_marker(&uuid)) else {
+            // Leave a block without an end marker where it is.
+            from = body_start;
+            continue;
+        };
+        let css = rest[body_start..body_start + body_len].trim().to_string();
+        // Removing the block trims the whitespace before it, so the next
+        // block can start earlier than this one did.
+        from = rest[..begin].trim_end().len();
+        rest = remove_module_css(&rest, &uuid);
+        blocks.push(ModuleStyle { uuid, css });
+    }
+    (rest, blocks)
+}
+
+fn imports_managed_style(style: &str) -> bool {
+    style.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with("@import") && line.contains(WAYBAR_MANAGED_STYLE)
+    })
+}
+
+/// The result of adding or removing a module's CSS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleUpdate {
+    /// The user's `style.css`.
+    pub style: String,
+    /// `barforge.css`.
+    pub managed: String,
+    pub issues: Vec<StyleIssue>,
+}
+
+/// Puts `module_css` for `uuid` into the managed stylesheet, or takes it out
+/// when `None`. Blocks that older releases wrote into `style.css` are moved
+/// over, and `style.css` is made to import the managed stylesheet so its own
+/// rules still win.
+pub fn update_module_style(
+    style: &str,
+    managed: &str,
+    uuid: &str,
+    module: Option<(&str, &str)>,
+) -> StyleUpdate {
+    let (style_rest, legacy) = take_module_blocks(style);
+    let (managed_rest, mut blocks) = take_module_blocks(managed);
+    for block in legacy {
+        if !blocks.iter().any(|b| b.uuid == block.uuid) {
+            blocks.push(block);
+        }
+    }
+    blocks.retain(|block| block.uuid != uuid);
+
+    let mut issues = Vec::new();
+    if let Some((waybar_module_name, css)) = module {
+        let block = ModuleStyle {
+            uuid: uuid.to_string(),
+            css: css.trim().to_string(),
+        };
+        issues = block.scope_issues(waybar_module_name);
+        blocks.push(block);
+        issues.extend(
+            find_conflicts(&blocks)
+                .into_iter()
+                .filter(|issue| matches!(issue, StyleIssue::Conflict { uuids, .. } if uuids.iter().any(|u| u == uuid))),
+        );
+    }
+
+    let mut managed = match managed_rest.trim() {
+        "" => MANAGED_STYLE_HEADER.to_string(),
+        rest => rest.to_string(),
+    };
+    for block in &blocks {
+        managed = inject_module_css(&managed, &block.uuid, &block.css);
+    }
+    managed.push('\n');
+
+    let style = if blocks.is_empty() || imports_managed_style(&style_rest) {
+        style_rest
+    } else {
+        let import = format!("@import \"{WAYBAR_MANAGED_STYLE}\";\n");
+        match style_rest.trim() {
+            "" => import,
+            _ => format!("{import}\n{style_rest}"),
+        }
+    };
+
+    StyleUpdate {
+        style,
+        managed,
+        issues,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selectors(css: &str) -> Vec<(String, usize)> {
+        parse_rules(css)
+            .unwrap()
+            .into_iter()
+            .map(|rule| (rule.selector, rule.line))
+            .collect()
+    }
+
+    #[test]
+    fn parse_rules_lists_selectors_with_lines() {
+        let css = "/* { } */\n#custom-weather,\n#custom-weather:hover { color: red; }\n\n@define-color accent #fff;\nwindow#waybar  .modules-left {\n  padding: 0;\n}\n:not(#a, #b) {}\n";
+
+        assert_eq!(
+            selectors(css),
+            vec![
+                ("#custom-weather".to_string(), 2),
+                ("#custom-weather:hover".to_string(), 2),
+                ("@define-color accent".to_string(), 5),
+                ("window#waybar .modules-left".to_string(), 6),
+                (":not(#a, #b)".to_string(), 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_rules_reports_unbalanced_braces() {
+        assert_eq!(
+            parse_rules("#a { color: red;\n"),
+            Err(CssError::Unclosed(1))
+        );
+        assert_eq!(parse_rules("#a {}\n}"), Err(CssError::UnexpectedBrace(2)));
+        assert_eq!(parse_rules("/* open"), Err(CssError::UnclosedComment(1)));
+    }
+
+    #[test]
+    fn module_scope_uses_waybar_widget_id() {
+        assert_eq!(module_scope("custom/weather"), "custom-weather");
+        assert_eq!(module_scope("custom/weather#2"), "custom-weather");
+    }
+
+    #[test]
+    fn scope_issues_flag_selectors_outside_the_module() {
+        let style = ModuleStyle {
+            uuid: "weather@test".to_string(),
+            css: "#custom-weather, #custom-weather label {}\n\
+                  window#waybar #custom-weather.hot {}\n\
+                  #custom-weather-extra {}\n\
+                  window#waybar { background: red; }\n\
+                  @import \"other.css\";\n\
+                  @define-color weather-bg #000;"
+                .to_string(),
+        };
+
+        let flagged: Vec<String> = style
+            .scope_issues("custom/weather")
+            .into_iter()
+            .map(|issue| match issue {
+                StyleIssue::OutOfScope { selector, .. } => selector,
+                other => panic!("unexpected issue {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            flagged,
+            vec![
+                "#custom-weather-extra",
+                "window#waybar",
+                "@import \"other.css\""
+            ]
+        );
+    }
+
+    #[test]
+    fn find_conflicts_names_every_module_styling_a_selector() {
+        let styles = [
+            ModuleStyle {
+                uuid: "a@test".to_string(),
+                css: "window#waybar {}\n#custom-a {}".to_string(),
+            },
+            ModuleStyle {
+                uuid: "b@test".to_string(),
+                css: "window#waybar { color: red; }\n#custom-b {}".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            find_conflicts(&styles),
+            vec![StyleIssue::Conflict {
+                selector: "window#waybar".to_string(),
+                uuids: vec!["a@test".to_string(), "b@test".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn inject_module_css_appends_with_markers() {
+        let existing = "* { font-family: monospace; }";
+        let module_css = ".custom-weather { color: #fff; }";
+
+        let result = inject_module_css(existing, "weather@test", module_css);
+
+        assert!(result.contains("/* BEGIN barforge:weather@test */"));
+        assert!(result.contains(".custom-weather { color: #fff; }"));
+        assert!(result.contains("/* END barforge:weather@test */"));
+        assert!(result.starts_with("* { font-family: monospace; }"));
+    }
+
+    #[test]
+    fn remove_module_css_strips_marked_section() {
+        let css = r#"* { font-family: monospace; }
+/* BEGIN barforge:weather@test */
+.custom-weather { color: #fff; }
+/* END barforge:weather@test */
+.clock { color: blue; }"#;
+
+        let result = remove_module_css(css, "weather@test");
+
+        assert!(!result.contains("barforge:weather@test"));
+        assert!(!result.contains(".custom-weather"));
+        assert!(result.contains("* { font-family: monospace; }"));
+        assert!(result.contains(".clock { color: blue; }"));
+    }
+
+    #[test]
+    fn remove_module_css_handles_missing_section() {
+        let css = "* { font-family: monospace; }";
+        let result = remove_module_css(css, "nonexistent@test");
+        assert_eq!(result, css);
+    }
+
+    #[test]
+    fn update_module_style_writes_managed_file_and_import() {
+        let update = update_module_style(
+            "* { font-size: 12px; }\n",
+            "",
+            "weather@test",
+            Some(("custom/weather", "#custom-weather { color: #fff; }\n")),
+        );
+
+        assert_eq!(
+            update.style,
+            "@import \"barforge.css\";\n\n* { font-size: 12px; }\n"
+        );
+        assert_eq!(
+            update.managed,
+            format!(
+                "{MANAGED_STYLE_HEADER}\n/* BEGIN barforge:weather@test */\n\
+                 #custom-weather {{ color: #fff; }}\n/* END barforge:weather@test */\n"
+            )
+        );
+        assert!(update.issues.is_empty());
+
+        let again = update_module_style(
+            &update.style,
+            &update.managed,
+            "weather@test",
+            Some(("custom/weather", "#custom-weather { color: #fff; }\n")),
+        );
+        assert_eq!(again, update);
+
+        let removed = update_module_style(&update.style, &update.managed, "weather@test", None);
+        assert_eq!(removed.style, update.style);
+        assert_eq!(removed.managed, format!("{MANAGED_STYLE_HEADER}\n"));
+    }
+
+    #[test]
+    fn update_module_style_moves_legacy_blocks_out_of_style_css() {
+        let style = "* { font-size: 12px; }\n/* BEGIN barforge:clock@test */\n#custom-clock {}\n/* END barforge:clock@test */\n";
+
+        let update = update_module_style(
+            style,
+            "",
+            "weather@test",
+            Some(("custom/weather", "#custom-weather {}")),
+        );
+
+        assert!(!update.style.contains("barforge:clock@test"));
+        let (_, blocks) = take_module_blocks(&update.managed);
+        let uuids: Vec<&str> = blocks.iter().map(|b| b.uuid.as_str()).collect();
+        assert_eq!(uuids, vec!["clock@test", "weather@test"]);
+    }
+
+    #[test]
+    fn update_module_style_reports_conflicts_with_other_modules() {
+        let managed = inject_module_css("", "theme@test", "window#waybar { color: red; }");
+
+        let update = update_module_style(
+            "",
+            &managed,
+            "weather@test",
+            Some(("custom/weather", "window#waybar {}")),
+        );
+
+        assert_eq!(update.issues.len(), 2);
+        assert!(matches!(update.issues[0], StyleIssue::OutOfScope { .. }));
+        assert_eq!(
+            update.issues[1].to_string(),
+            "`window#waybar` is styled by theme@test and weather@test"
+        );
+    }
+}
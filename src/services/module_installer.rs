@@ -1,8 +1,12 @@
 use crate::security::{
-    ExtractionError, OfflinePolicy, RevocationError, SCRIPT_TIMEOUT_SECS, ScriptError, Verifier,
-    VerifyError, check_revocation, compute_sha256, extract_tarball_safe, run_script_sandboxed,
+    ExtractionError, OfflinePolicy, RevocationError, SCRIPT_TIMEOUT_SECS, ScriptError,
+    ScriptResult, Verifier, VerifyError, check_revocation, compute_sha256, extract_tarball_safe,
+    run_script_sandboxed, validate_extraction_path,
+};
+use crate::services::{
+    DepReport, PackageConfigError, PackageToml, ScriptReview, check_dependencies, load_settings,
+    review_scripts,
 };
-use crate::services::{DepReport, PackageConfigError, PackageToml, check_dependencies};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 use thiserror::Error;
@@ -30,6 +34,15 @@ pub enum InstallError {
     #[error("Script execution failed: {0}")]
     ScriptExecution(#[from] ScriptError),
 
+    #[error("Risky patterns in the module's scripts need approval:\n{0}")]
+    UnapprovedScripts(ScriptReview),
+
+    #[error("Install script exited with code {exit_code:?}: {stderr}")]
+    InstallScriptFailed {
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+
     #[error("Network error: {0}")]
     Network(String),
 
@@ -67,6 +80,17 @@ impl InstallStage {
     }
 }
 
+/// Whether the install script may run once the package is extracted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptApproval {
+    /// Extract the package without running it, for previews.
+    Skip,
+    /// Run it only if no script in the package looks risky.
+    IfClean,
+    /// Run it after the findings were shown and approved.
+    Approved,
+}
+
 pub struct InstallParams<'a> {
     pub uuid: &'a str,
     pub version: &'a str,
@@ -74,6 +98,7 @@ pub struct InstallParams<'a> {
     pub signature: &'a str,
     pub expected_hash: &'a str,
     pub dest_dir: &'a Path,
+    pub scripts: ScriptApproval,
 }
 
 pub struct SecureInstaller {
@@ -145,12 +170,21 @@ impl SecureInstaller {
             None
         };
 
+        if params.scripts == ScriptApproval::IfClean {
+            let review = review_scripts(params.dest_dir)?;
+            if !review.is_clean() {
+                return Err(InstallError::UnapprovedScripts(review));
+            }
+        }
+
         progress(InstallStage::RunningInstallScript);
-        let script_result = if let Some(ref config) = package_config {
+        let script_result = if params.scripts == ScriptApproval::Skip {
+            None
+        } else if let Some(ref config) = package_config {
             if let Some(ref script_name) = config.package.install_script {
                 let script_path = params.dest_dir.join(script_name);
                 if script_path.exists() {
-                    let sandbox_config = config.to_sandbox_config();
+                    let sandbox_config = config.to_sandbox_config(load_settings().seccomp_filter);
                     let timeout = Duration::from_secs(SCRIPT_TIMEOUT_SECS);
                     Some(run_script_sandboxed(
                         &script_path,
@@ -168,6 +202,13 @@ impl SecureInstaller {
             None
         };
 
+        if let Some(result) = script_result.as_ref().filter(|r| !r.success) {
+            return Err(InstallError::InstallScriptFailed {
+                exit_code: result.exit_code,
+                stderr: result.stderr.trim().to_string(),
+            });
+        }
+
         progress(InstallStage::Complete);
 
         Ok(InstallResult {
@@ -209,6 +250,41 @@ pub struct InstallResult {
     pub script_output: Option<String>,
 }
 
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct UninstallResult {
+    pub uuid: String,
+    pub script: Option<ScriptResult>,
+}
+
+pub fn run_uninstall_script(module_dir: &Path) -> Result<Option<ScriptResult>, InstallError> {
+    let package_toml_path = module_dir.join("Package.toml");
+    if !package_toml_path.exists() {
+        return Ok(None);
+    }
+
+    let config = PackageToml::from_file(&package_toml_path)?;
+    let Some(ref script_name) = config.package.uninstall_script else {
+        return Ok(None);
+    };
+
+    let script_path = validate_extraction_path(module_dir, Path::new(script_name))
+        .map_err(|e| ScriptError::ExecutionFailed(format!("Invalid uninstall script path: {e}")))?;
+    if !script_path.exists() {
+        tracing::warn!(
+            "Uninstall script {} declared but missing in {}",
+            script_name,
+            module_dir.display()
+        );
+        return Ok(None);
+    }
+
+    let sandbox_config = config.to_sandbox_config(load_settings().seccomp_filter);
+    let timeout = Duration::from_secs(SCRIPT_TIMEOUT_SECS);
+    let result = run_script_sandboxed(&script_path, module_dir, &sandbox_config, timeout)?;
+
+    Ok(Some(result))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -238,6 +314,37 @@ mod tests {
         assert_eq!(installer.offline_policy, OfflinePolicy::AllowOffline);
     }
 
+    #[test]
+    fn uninstall_script_skipped_without_package_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(run_uninstall_script(dir.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn uninstall_script_skipped_when_not_declared() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Package.toml"),
+            "[package]\nname = \"test\"\nversion = \"1.0.0\"\n",
+        )
+        .unwrap();
+        assert!(run_uninstall_script(dir.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn uninstall_script_rejects_path_traversal() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Package.toml"),
+            "[package]\nname = \"test\"\nversion = \"1.0.0\"\nuninstall_script = \"../evil.sh\"\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            run_uninstall_script(dir.path()),
+            Err(InstallError::ScriptExecution(_))
+        ));
+    }
+
     #[test]
     fn verify_only_rejects_hash_mismatch() {
         let installer = SecureInstaller::new();
@@ -0,0 +1,360 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::domain::ModuleVersion;
+use crate::services::own_writes::record_own_write;
+use crate::services::paths::{WAYBAR_MANAGED_INCLUDE, WAYBAR_MANAGED_STYLE, WaybarPaths};
+
+pub const PREVIOUS_VERSIONS_KEPT: usize = 3;
+
+const SNAPSHOT_CONFIG: &str = "config.jsonc";
+const SNAPSHOT_STYLE: &str = "style.css";
+const VERSION_MODULE_DIR: &str = "module";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WaybarSnapshot {
+    pub config: Option<String>,
+    pub style: Option<String>,
+    pub managed_include: Option<String>,
+    pub managed_style: Option<String>,
+}
+
+impl WaybarSnapshot {
+    pub fn capture(paths: &WaybarPaths) -> io::Result<Self> {
+        Ok(Self {
+            config: read_optional(&paths.config)?,
+            style: read_optional(&paths.style)?,
+            managed_include: read_optional(&paths.managed_include)?,
+            managed_style: read_optional(&paths.managed_style)?,
+        })
+    }
+
+    pub fn save_to(&self, dir: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        if let Some(config) = &self.config {
+            std::fs::write(dir.join(SNAPSHOT_CONFIG), config)?;
+        }
+        if let Some(style) = &self.style {
+            std::fs::write(dir.join(SNAPSHOT_STYLE), style)?;
+        }
+        if let Some(managed_include) = &self.managed_include {
+            std::fs::write(dir.join(WAYBAR_MANAGED_INCLUDE), managed_include)?;
+        }
+        if let Some(managed_style) = &self.managed_style {
+            std::fs::write(dir.join(WAYBAR_MANAGED_STYLE), managed_style)?;
+        }
+        Ok(())
+    }
+
+    pub fn restore(&self, paths: &WaybarPaths) -> io::Result<()> {
+        restore_optional(&paths.config, self.config.as_deref())?;
+        restore_optional(&paths.style, self.style.as_deref())?;
+        restore_optional(&paths.managed_include, self.managed_include.as_deref())?;
+        restore_optional(&paths.managed_style, self.managed_style.as_deref())
+    }
+}
+
+fn read_optional(path: &Path) -> io::Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn restore_optional(path: &Path, content: Option<&str>) -> io::Result<()> {
+    record_own_write(path);
+    match content {
+        Some(content) => std::fs::write(path, content),
+        None => match std::fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleSwap {
+    install_path: PathBuf,
+    staging_path: PathBuf,
+    version_dir: PathBuf,
+}
+
+impl ModuleSwap {
+    pub fn new(install_path: PathBuf, staging_path: PathBuf, version_dir: PathBuf) -> Self {
+        Self {
+            install_path,
+            staging_path,
+            version_dir,
+        }
+    }
+
+    pub fn version_dir(&self) -> &Path {
+        &self.version_dir
+    }
+
+    pub fn previous_path(&self) -> PathBuf {
+        self.version_dir.join(VERSION_MODULE_DIR)
+    }
+
+    pub fn prepare(&self, snapshot: &WaybarSnapshot) -> io::Result<()> {
+        if self.version_dir.exists() {
+            std::fs::remove_dir_all(&self.version_dir)?;
+        }
+        snapshot.save_to(&self.version_dir)
+    }
+
+    pub fn commit(&self) -> io::Result<()> {
+        let previous = self.previous_path();
+        let had_previous = self.install_path.exists();
+        if had_previous {
+            std::fs::rename(&self.install_path, &previous)?;
+        }
+
+        if let Err(e) = std::fs::rename(&self.staging_path, &self.install_path) {
+            if had_previous {
+                std::fs::rename(&previous, &self.install_path)?;
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub fn revert(&self) -> io::Result<()> {
+        if self.install_path.exists() {
+            if let Some(parent) = self.staging_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::rename(&self.install_path, &self.staging_path)?;
+        }
+        let previous = self.previous_path();
+        if previous.exists() {
+            std::fs::rename(&previous, &self.install_path)?;
+        }
+        if self.version_dir.exists() {
+            std::fs::remove_dir_all(&self.version_dir)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn stored_version_path(versions_dir: &Path, version: &ModuleVersion) -> PathBuf {
+    versions_dir
+        .join(version.to_string())
+        .join(VERSION_MODULE_DIR)
+}
+
+pub fn discard_staging(staging_path: &Path) -> io::Result<()> {
+    match std::fs::remove_dir_all(staging_path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+pub fn record_previous_version(
+    previous: &[ModuleVersion],
+    replaced: &ModuleVersion,
+    installed: &ModuleVersion,
+    keep: usize,
+) -> Vec<ModuleVersion> {
+    std::iter::once(replaced)
+        .chain(previous)
+        .filter(|v| *v != installed)
+        .fold(Vec::new(), |mut versions, v| {
+            if !versions.contains(v) {
+                versions.push(v.clone());
+            }
+            versions
+        })
+        .into_iter()
+        .take(keep)
+        .collect()
+}
+
+pub fn prune_versions(versions_dir: &Path, keep: &[ModuleVersion]) -> io::Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(versions_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut removed = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let kept = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| keep.iter().any(|v| v.to_string() == name));
+        if path.is_dir() && !kept {
+            std::fs::remove_dir_all(&path)?;
+            removed.push(path);
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn swap_in(root: &Path) -> ModuleSwap {
+        ModuleSwap::new(
+            root.join("weather@test"),
+            root.join(".staging/weather@test"),
+            root.join(".versions/weather@test/1.0.0"),
+        )
+    }
+
+    fn write_module(dir: &Path, version: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("VERSION"), version).unwrap();
+    }
+
+    #[test]
+    fn snapshot_restores_contents_and_missing_files() {
+        let dir = tempdir().unwrap();
+        let paths = WaybarPaths {
+            config: dir.path().join("config.jsonc"),
+            style: dir.path().join("style.css"),
+            managed_include: dir.path().join(WAYBAR_MANAGED_INCLUDE),
+            managed_style: dir.path().join(WAYBAR_MANAGED_STYLE),
+        };
+        std::fs::write(&paths.config, "{}").unwrap();
+
+        let snapshot = WaybarSnapshot::capture(&paths).unwrap();
+        assert_eq!(snapshot.config.as_deref(), Some("{}"));
+        assert!(snapshot.style.is_none());
+        assert!(snapshot.managed_include.is_none());
+
+        std::fs::write(&paths.config, "{\"broken\": }").unwrap();
+        std::fs::write(&paths.style, "#custom-weather {}").unwrap();
+        std::fs::write(&paths.managed_include, "{\"custom/weather\": {}}").unwrap();
+        std::fs::write(&paths.managed_style, "#custom-weather {}").unwrap();
+        snapshot.restore(&paths).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&paths.config).unwrap(), "{}");
+        assert!(!paths.style.exists());
+        assert!(!paths.managed_include.exists());
+        assert!(!paths.managed_style.exists());
+    }
+
+    #[test]
+    fn commit_swaps_staged_files_and_keeps_previous() {
+        let dir = tempdir().unwrap();
+        let swap = swap_in(dir.path());
+        write_module(&dir.path().join("weather@test"), "1.0.0");
+        write_module(&dir.path().join(".staging/weather@test"), "1.1.0");
+
+        swap.prepare(&WaybarSnapshot::default()).unwrap();
+        swap.commit().unwrap();
+
+        let installed = dir.path().join("weather@test/VERSION");
+        assert_eq!(std::fs::read_to_string(installed).unwrap(), "1.1.0");
+        let previous = swap.previous_path().join("VERSION");
+        assert_eq!(std::fs::read_to_string(previous).unwrap(), "1.0.0");
+        assert!(!dir.path().join(".staging/weather@test").exists());
+    }
+
+    #[test]
+    fn commit_without_staged_files_keeps_old_version() {
+        let dir = tempdir().unwrap();
+        let swap = swap_in(dir.path());
+        write_module(&dir.path().join("weather@test"), "1.0.0");
+
+        swap.prepare(&WaybarSnapshot::default()).unwrap();
+        assert!(swap.commit().is_err());
+
+        let installed = dir.path().join("weather@test/VERSION");
+        assert_eq!(std::fs::read_to_string(installed).unwrap(), "1.0.0");
+    }
+
+    #[test]
+    fn revert_restores_previous_version() {
+        let dir = tempdir().unwrap();
+        let swap = swap_in(dir.path());
+        write_module(&dir.path().join("weather@test"), "1.0.0");
+        write_module(&dir.path().join(".staging/weather@test"), "1.1.0");
+
+        swap.prepare(&WaybarSnapshot::default()).unwrap();
+        swap.commit().unwrap();
+        swap.revert().unwrap();
+
+        let installed = dir.path().join("weather@test/VERSION");
+        assert_eq!(std::fs::read_to_string(installed).unwrap(), "1.0.0");
+        assert!(!swap.version_dir().exists());
+
+        let staged = dir.path().join(".staging/weather@test/VERSION");
+        assert_eq!(std::fs::read_to_string(staged).unwrap(), "1.1.0");
+    }
+
+    #[test]
+    fn record_previous_version_puts_newest_first() {
+        let v = |s: &str| ModuleVersion::try_from(s).unwrap();
+        let previous = vec![v("1.1.0"), v("1.0.0")];
+
+        let recorded = record_previous_version(&previous, &v("1.2.0"), &v("1.3.0"), 3);
+        assert_eq!(recorded, vec![v("1.2.0"), v("1.1.0"), v("1.0.0")]);
+
+        let recorded = record_previous_version(&previous, &v("1.2.0"), &v("1.3.0"), 2);
+        assert_eq!(recorded, vec![v("1.2.0"), v("1.1.0")]);
+    }
+
+    #[test]
+    fn record_previous_version_drops_installed_version() {
+        let v = |s: &str| ModuleVersion::try_from(s).unwrap();
+        let previous = vec![v("1.1.0"), v("1.0.0")];
+
+        let recorded = record_previous_version(&previous, &v("1.2.0"), &v("1.1.0"), 3);
+        assert_eq!(recorded, vec![v("1.2.0"), v("1.0.0")]);
+    }
+
+    #[test]
+    fn prepare_saves_waybar_snapshot() {
+        let dir = tempdir().unwrap();
+        let swap = swap_in(dir.path());
+        let snapshot = WaybarSnapshot {
+            config: Some("{}".to_string()),
+            style: Some("window {}".to_string()),
+            managed_include: None,
+            managed_style: None,
+        };
+
+        swap.prepare(&snapshot).unwrap();
+
+        let saved = swap.version_dir();
+        assert_eq!(
+            std::fs::read_to_string(saved.join(SNAPSHOT_CONFIG)).unwrap(),
+            "{}"
+        );
+        assert_eq!(
+            std::fs::read_to_string(saved.join(SNAPSHOT_STYLE)).unwrap(),
+            "window {}"
+        );
+    }
+
+    #[test]
+    fn prune_removes_unrecorded_versions() {
+        let dir = tempdir().unwrap();
+        for version in ["1.0.0", "1.1.0", "1.2.0"] {
+            std::fs::create_dir_all(dir.path().join(version)).unwrap();
+        }
+        let keep = vec![ModuleVersion::try_from("1.2.0").unwrap()];
+
+        let removed = prune_versions(dir.path(), &keep).unwrap();
+
+        assert_eq!(removed.len(), 2);
+        assert!(dir.path().join("1.2.0").exists());
+        assert!(!dir.path().join("1.0.0").exists());
+    }
+
+    #[test]
+    fn prune_missing_dir_is_noop() {
+        let dir = tempdir().unwrap();
+        assert!(
+            prune_versions(&dir.path().join("missing"), &[])
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
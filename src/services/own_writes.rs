@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+
+/// How long after Barforge writes a path that change events for it are
+/// treated as its own.
+pub const OWN_WRITE_WINDOW: Duration = Duration::from_secs(2);
+
+static OWN_WRITES: Lazy<Mutex<OwnWrites>> = Lazy::new(|| Mutex::new(OwnWrites::default()));
+
+#[derive(Default)]
+struct OwnWrites {
+    entries: HashMap<PathBuf, Entry>,
+}
+
+struct Entry {
+    active: usize,
+    last: Instant,
+}
+
+impl OwnWrites {
+    fn begin(&mut self, path: &Path) {
+        let entry = self.entry(path);
+        entry.active += 1;
+    }
+
+    fn end(&mut self, path: &Path) {
+        let entry = self.entry(path);
+        entry.active = entry.active.saturating_sub(1);
+    }
+
+    fn entry(&mut self, path: &Path) -> &mut Entry {
+        let now = Instant::now();
+        let entry = self.entries.entry(path.to_path_buf()).or_insert(Entry {
+            active: 0,
+            last: now,
+        });
+        entry.last = now;
+        entry
+    }
+
+    fn contains(&mut self, path: &Path, window: Duration) -> bool {
+        self.entries
+            .retain(|_, entry| entry.active > 0 || entry.last.elapsed() < window);
+        path.ancestors().any(|p| self.entries.contains_key(p))
+    }
+}
+
+/// Notes that Barforge is about to write `path`, or anything below it.
+pub fn record_own_write(path: &Path) {
+    if let Ok(mut writes) = OWN_WRITES.lock() {
+        writes.entry(path);
+    }
+}
+
+/// Marks `path` as being written by Barforge until the guard is dropped, for
+/// work such as installs that can outlast [`OWN_WRITE_WINDOW`].
+pub fn own_write_guard(path: &Path) -> OwnWriteGuard {
+    if let Ok(mut writes) = OWN_WRITES.lock() {
+        writes.begin(path);
+    }
+    OwnWriteGuard(path.to_path_buf())
+}
+
+pub struct OwnWriteGuard(PathBuf);
+
+impl Drop for OwnWriteGuard {
+    fn drop(&mut self) {
+        if let Ok(mut writes) = OWN_WRITES.lock() {
+            writes.end(&self.0);
+        }
+    }
+}
+
+/// Whether a change to `path` most likely came from Barforge itself.
+pub fn is_own_write(path: &Path) -> bool {
+    OWN_WRITES
+        .lock()
+        .map(|mut writes| writes.contains(path, OWN_WRITE_WINDOW))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_paths_and_their_children_are_own_writes_until_the_window_ends() {
+        let mut writes = OwnWrites::default();
+        writes.entry(Path::new("/modules/weather@test"));
+
+        let window = Duration::from_secs(60);
+        assert!(writes.contains(Path::new("/modules/weather@test"), window));
+        assert!(writes.contains(Path::new("/modules/weather@test/style.css"), window));
+        assert!(!writes.contains(Path::new("/modules/clock@test"), window));
+        assert!(!writes.contains(Path::new("/modules"), window));
+
+        assert!(!writes.contains(Path::new("/modules/weather@test"), Duration::ZERO));
+    }
+
+    #[test]
+    fn active_writes_do_not_expire() {
+        let mut writes = OwnWrites::default();
+        let path = Path::new("/modules/weather@test");
+        writes.begin(path);
+        assert!(writes.contains(path, Duration::ZERO));
+
+        writes.end(path);
+        assert!(!writes.contains(path, Duration::ZERO));
+    }
+}
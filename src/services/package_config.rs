@@ -1,4 +1,4 @@
-use crate::security::SandboxConfig;
+use crate::security::{SandboxConfig, SandboxProfile};
 use crate::services::dependency_checker::{DepSpec, DepType};
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -55,7 +55,7 @@ fn default_dep_type() -> String {
     "binary".to_string()
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Deserialize)]
 pub struct Permissions {
     #[serde(default)]
     pub network: bool,
@@ -65,6 +65,26 @@ pub struct Permissions {
     pub read_paths: Vec<String>,
     #[serde(default)]
     pub write_paths: Vec<String>,
+    /// D-Bus, `playerctl`, `pactl` and compositor IPC all need Unix sockets,
+    /// so modules keep them unless they give them up.
+    #[serde(default = "default_unix_sockets")]
+    pub unix_sockets: bool,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self {
+            network: false,
+            ports: Vec::new(),
+            read_paths: Vec::new(),
+            write_paths: Vec::new(),
+            unix_sockets: true,
+        }
+    }
+}
+
+fn default_unix_sockets() -> bool {
+    true
 }
 
 impl std::str::FromStr for PackageToml {
@@ -108,7 +128,9 @@ impl PackageToml {
             .collect()
     }
 
-    pub fn to_sandbox_config(&self) -> SandboxConfig {
+    /// The sandbox install and uninstall scripts run in. `seccomp` adds the
+    /// system call filter on top of Landlock.
+    pub fn to_sandbox_config(&self, seccomp: bool) -> SandboxConfig {
         SandboxConfig {
             allow_network: self.permissions.network,
             allowed_ports: self.permissions.ports.clone(),
@@ -124,13 +146,43 @@ impl PackageToml {
                 .iter()
                 .map(|s| shellexpand::tilde(s).to_string().into())
                 .collect(),
+            module_dir: None,
+            profile: SandboxProfile::InstallScript,
+            deny_unix_sockets: !self.permissions.unix_sockets,
+            seccomp,
         }
     }
 }
 
+/// The sandbox a module's commands run in at runtime: the permissions its
+/// `Package.toml` declares, or none beyond the defaults when it has none,
+/// plus read access to its own directory. Unlike install scripts, commands
+/// may not write to the Waybar config or Barforge's cache.
+pub fn runtime_sandbox_config(
+    module_dir: &Path,
+    cache_dir: &Path,
+    seccomp: bool,
+) -> Result<SandboxConfig, PackageConfigError> {
+    let package_path = module_dir.join("Package.toml");
+    let config = if package_path.exists() {
+        PackageToml::from_file(&package_path)?.to_sandbox_config(seccomp)
+    } else {
+        SandboxConfig::default()
+    };
+    Ok(SandboxConfig {
+        module_dir: Some(module_dir.to_path_buf()),
+        profile: SandboxProfile::ModuleCommand {
+            cache_dir: cache_dir.to_path_buf(),
+        },
+        seccomp,
+        ..config
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
     use std::str::FromStr;
 
     #[test]
@@ -215,12 +267,64 @@ read_paths = ["/usr/share"]
 write_paths = ["/tmp"]
 "#;
         let pkg = PackageToml::from_str(toml).unwrap();
-        let config = pkg.to_sandbox_config();
+        let config = pkg.to_sandbox_config(true);
 
         assert!(config.allow_network);
         assert_eq!(config.allowed_ports, vec![443]);
         assert_eq!(config.extra_ro_paths.len(), 1);
         assert_eq!(config.extra_rw_paths.len(), 1);
+        assert!(!config.deny_unix_sockets);
+        assert!(config.seccomp);
+    }
+
+    #[test]
+    fn runtime_sandbox_uses_package_permissions_and_module_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Path::new("/cache/test");
+        let config = runtime_sandbox_config(dir.path(), cache, true).unwrap();
+        assert!(!config.allow_network);
+        assert!(config.seccomp);
+        assert!(
+            !runtime_sandbox_config(dir.path(), cache, false)
+                .unwrap()
+                .seccomp
+        );
+        assert_eq!(config.module_dir.as_deref(), Some(dir.path()));
+
+        std::fs::write(
+            dir.path().join("Package.toml"),
+            "[package]\nname = \"test\"\nversion = \"1.0.0\"\n\n[permissions]\nnetwork = true\nports = [443]\n",
+        )
+        .unwrap();
+        let config = runtime_sandbox_config(dir.path(), cache, true).unwrap();
+        assert!(config.allow_network);
+        assert_eq!(config.allowed_ports, vec![443]);
+        assert_eq!(config.module_dir.as_deref(), Some(dir.path()));
+    }
+
+    #[test]
+    fn runtime_sandbox_does_not_grant_waybar_config() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Package.toml"),
+            "[package]\nname = \"test\"\nversion = \"1.0.0\"\n\n[permissions]\nwrite_paths = [\"/tmp/weather\"]\n",
+        )
+        .unwrap();
+        let waybar_dir = dirs::config_dir().unwrap().join("waybar");
+
+        let install = PackageToml::from_file(&dir.path().join("Package.toml"))
+            .unwrap()
+            .to_sandbox_config(true);
+        assert!(install.writable_dirs().contains(&waybar_dir));
+
+        let cache = PathBuf::from("/cache/test");
+        let config = runtime_sandbox_config(dir.path(), &cache, true).unwrap();
+        let writable: Vec<PathBuf> = config
+            .writable_dirs()
+            .into_iter()
+            .chain(config.extra_rw_paths)
+            .collect();
+        assert_eq!(writable, vec![cache, PathBuf::from("/tmp/weather")]);
     }
 
     #[test]
@@ -234,5 +338,7 @@ version = "0.1.0"
         assert!(pkg.dependencies.is_empty());
         assert!(!pkg.permissions.network);
         assert!(pkg.permissions.ports.is_empty());
+        assert!(pkg.permissions.unix_sockets);
+        assert!(!pkg.to_sandbox_config(true).deny_unix_sockets);
     }
 }
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use once_cell::sync::Lazy;
@@ -102,22 +102,179 @@ pub fn screenshot_cache_path(url: &str) -> PathBuf {
     screenshots_cache_dir().join(format!("{:016x}.png", hash))
 }
 
+pub fn manifest_path() -> PathBuf {
+    config_dir().join("barforge.toml")
+}
+
+pub fn lockfile_path() -> PathBuf {
+    config_dir().join("barforge.lock")
+}
+
 pub fn preferences_dir() -> PathBuf {
     config_dir().join("prefs")
 }
 
+const WAYBAR_CONFIG_NAMES: &[&str] = &["config", "config.jsonc"];
+const WAYBAR_STYLE_NAMES: &[&str] = &["style.css"];
+pub const WAYBAR_MANAGED_INCLUDE: &str = "barforge-modules.jsonc";
+pub const WAYBAR_MANAGED_STYLE: &str = "barforge.css";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaybarPaths {
+    pub config: PathBuf,
+    pub style: PathBuf,
+    pub managed_include: PathBuf,
+    pub managed_style: PathBuf,
+}
+
+pub fn waybar_config_dirs() -> Vec<PathBuf> {
+    waybar_config_dirs_from(|key| std::env::var(key).ok(), &HOME_DIR)
+}
+
+fn waybar_user_dirs_from(env: impl Fn(&str) -> Option<String>, home: &Path) -> Vec<PathBuf> {
+    let env = |key: &str| env(key).filter(|value| !value.is_empty());
+
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    if let Some(dir) = env("WAYBAR_CONFIG_DIR") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(config_home) = env("XDG_CONFIG_HOME") {
+        dirs.push(Path::new(&config_home).join("waybar"));
+    }
+    dirs.push(home.join(".config/waybar"));
+    dirs.push(home.join("waybar"));
+    dirs
+}
+
+fn waybar_config_dirs_from(env: impl Fn(&str) -> Option<String>, home: &Path) -> Vec<PathBuf> {
+    let env = |key: &str| env(key).filter(|value| !value.is_empty());
+
+    let mut dirs = waybar_user_dirs_from(env, home);
+    let config_dirs = env("XDG_CONFIG_DIRS").unwrap_or_else(|| "/etc/xdg".to_string());
+    dirs.extend(
+        config_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| Path::new(dir).join("waybar")),
+    );
+    dirs.push(PathBuf::from("/etc/xdg/waybar"));
+
+    let mut seen = std::collections::HashSet::new();
+    dirs.retain(|dir| seen.insert(dir.clone()));
+    dirs
+}
+
+fn find_in_dirs(dirs: &[PathBuf], names: &[&str]) -> Option<PathBuf> {
+    dirs.iter()
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
 pub fn waybar_config_path() -> PathBuf {
-    WAYBAR_CONFIG_DIR.join("config.jsonc")
+    user_waybar_file(WAYBAR_CONFIG_NAMES, "config.jsonc")
 }
 
 pub fn waybar_style_path() -> PathBuf {
-    WAYBAR_CONFIG_DIR.join("style.css")
+    user_waybar_file(WAYBAR_STYLE_NAMES, "style.css")
+}
+
+/// Finds a Waybar file like Waybar does. One that only exists in a system
+/// directory such as `/etc/xdg/waybar` is copied into the user's Waybar
+/// directory first, so edits and managed files never land outside it and
+/// Waybar reads the copy from then on.
+fn user_waybar_file(names: &[&str], default: &str) -> PathBuf {
+    let Some(found) = find_in_dirs(&waybar_config_dirs(), names) else {
+        return WAYBAR_CONFIG_DIR.join(default);
+    };
+    let user_dirs = waybar_user_dirs_from(|key| std::env::var(key).ok(), &HOME_DIR);
+    if found
+        .parent()
+        .is_some_and(|dir| user_dirs.iter().any(|d| d == dir))
+    {
+        return found;
+    }
+    match adopt_waybar_file(&found, &WAYBAR_CONFIG_DIR) {
+        Ok(path) => path,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to copy {} into {}: {e}",
+                found.display(),
+                WAYBAR_CONFIG_DIR.display()
+            );
+            found
+        }
+    }
+}
+
+/// Copies the files next to `found` that `user_dir` lacks, keeping relative
+/// includes and imports working, and returns the copy of `found`.
+fn adopt_waybar_file(found: &Path, user_dir: &Path) -> std::io::Result<PathBuf> {
+    let (Some(system_dir), Some(name)) = (found.parent(), found.file_name()) else {
+        return Ok(found.to_path_buf());
+    };
+    std::fs::create_dir_all(user_dir)?;
+    for entry in std::fs::read_dir(system_dir)? {
+        let entry = entry?;
+        let target = user_dir.join(entry.file_name());
+        if entry.path().is_file() && !target.exists() {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    tracing::info!(
+        "Copied the Waybar files in {} to {}",
+        system_dir.display(),
+        user_dir.display()
+    );
+    Ok(user_dir.join(name))
+}
+
+pub fn waybar_managed_include_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(WAYBAR_MANAGED_INCLUDE)
+}
+
+pub fn waybar_managed_style_path(style_path: &Path) -> PathBuf {
+    style_path.with_file_name(WAYBAR_MANAGED_STYLE)
+}
+
+pub fn waybar_paths() -> WaybarPaths {
+    let config = waybar_config_path();
+    let style = waybar_style_path();
+    WaybarPaths {
+        managed_include: waybar_managed_include_path(&config),
+        managed_style: waybar_managed_style_path(&style),
+        style,
+        config,
+    }
+}
+
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => HOME_DIR.join(rest),
+        None if path == "~" => HOME_DIR.clone(),
+        None => PathBuf::from(path),
+    }
 }
 
 pub fn module_install_path(uuid: &str) -> PathBuf {
     modules_dir().join(uuid)
 }
 
+pub fn module_cache_path(uuid: &str) -> PathBuf {
+    cache_dir().join("modules").join(uuid)
+}
+
+pub fn module_staging_path(uuid: &str) -> PathBuf {
+    modules_dir().join(".staging").join(uuid)
+}
+
+pub fn module_preview_path(uuid: &str) -> PathBuf {
+    modules_dir().join(".preview").join(uuid)
+}
+
+pub fn module_versions_dir(uuid: &str) -> PathBuf {
+    modules_dir().join(".versions").join(uuid)
+}
+
 pub fn module_preferences_path(uuid: &str) -> PathBuf {
     preferences_dir().join(format!("{}.json", uuid))
 }
@@ -164,9 +321,129 @@ mod tests {
     }
 
     #[test]
-    fn test_waybar_config_path_is_jsonc() {
+    fn test_manifest_path_under_config() {
+        let path = manifest_path();
+        assert!(path.starts_with(config_dir()));
+        assert!(path.ends_with("barforge.toml"));
+    }
+
+    #[test]
+    fn test_lockfile_next_to_manifest() {
+        assert_eq!(lockfile_path().parent(), manifest_path().parent());
+        assert!(lockfile_path().ends_with("barforge.lock"));
+    }
+
+    #[test]
+    fn test_update_paths_share_modules_dir() {
+        let uuid = "test@example";
+        assert!(module_staging_path(uuid).starts_with(modules_dir()));
+        assert!(module_versions_dir(uuid).starts_with(modules_dir()));
+        assert_ne!(module_staging_path(uuid), module_install_path(uuid));
+    }
+
+    #[test]
+    fn test_waybar_config_path_uses_known_name() {
         let path = waybar_config_path();
-        assert!(path.to_string_lossy().ends_with("config.jsonc"));
+        let name = path.file_name().unwrap().to_string_lossy();
+        assert!(WAYBAR_CONFIG_NAMES.contains(&name.as_ref()));
+    }
+
+    #[test]
+    fn test_waybar_config_dirs_follow_waybar_order() {
+        let home = Path::new("/home/user");
+        let dirs = waybar_config_dirs_from(
+            |key| match key {
+                "XDG_CONFIG_HOME" => Some("/home/user/.xdg".to_string()),
+                "XDG_CONFIG_DIRS" => Some("/opt/xdg:/etc/xdg".to_string()),
+                _ => None,
+            },
+            home,
+        );
+
+        assert_eq!(
+            dirs,
+            vec![
+                PathBuf::from("/home/user/.xdg/waybar"),
+                PathBuf::from("/home/user/.config/waybar"),
+                PathBuf::from("/home/user/waybar"),
+                PathBuf::from("/opt/xdg/waybar"),
+                PathBuf::from("/etc/xdg/waybar"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_waybar_config_dir_env_takes_priority() {
+        let dirs = waybar_config_dirs_from(
+            |key| (key == "WAYBAR_CONFIG_DIR").then(|| "/srv/waybar".to_string()),
+            Path::new("/home/user"),
+        );
+        assert_eq!(dirs[0], PathBuf::from("/srv/waybar"));
+        assert_eq!(dirs.last(), Some(&PathBuf::from("/etc/xdg/waybar")));
+    }
+
+    #[test]
+    fn test_find_in_dirs_prefers_config_without_extension() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        std::fs::write(first.path().join("config.jsonc"), "{}").unwrap();
+        std::fs::write(first.path().join("config"), "{}").unwrap();
+        std::fs::write(second.path().join("config"), "{}").unwrap();
+        let dirs = vec![
+            first.path().join("missing"),
+            first.path().to_path_buf(),
+            second.path().to_path_buf(),
+        ];
+
+        assert_eq!(
+            find_in_dirs(&dirs, WAYBAR_CONFIG_NAMES),
+            Some(first.path().join("config"))
+        );
+        assert_eq!(find_in_dirs(&dirs, WAYBAR_STYLE_NAMES), None);
+    }
+
+    #[test]
+    fn test_waybar_user_dirs_exclude_system_dirs() {
+        let dirs = waybar_user_dirs_from(
+            |key| (key == "XDG_CONFIG_DIRS").then(|| "/etc/xdg".to_string()),
+            Path::new("/home/user"),
+        );
+        assert_eq!(
+            dirs,
+            vec![
+                PathBuf::from("/home/user/.config/waybar"),
+                PathBuf::from("/home/user/waybar"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_system_config_is_copied_before_managed_files_are_placed() {
+        let system = tempfile::tempdir().unwrap();
+        let home = tempfile::tempdir().unwrap();
+        let user_dir = home.path().join("waybar");
+        std::fs::write(
+            system.path().join("config"),
+            "{\"include\": [\"bars.jsonc\"]}",
+        )
+        .unwrap();
+        std::fs::write(system.path().join("bars.jsonc"), "{}").unwrap();
+        std::fs::write(system.path().join("style.css"), "system").unwrap();
+        std::fs::create_dir_all(&user_dir).unwrap();
+        std::fs::write(user_dir.join("style.css"), "user").unwrap();
+
+        let config = adopt_waybar_file(&system.path().join("config"), &user_dir).unwrap();
+
+        assert_eq!(config, user_dir.join("config"));
+        assert!(user_dir.join("bars.jsonc").exists());
+        assert_eq!(
+            std::fs::read_to_string(user_dir.join("style.css")).unwrap(),
+            "user"
+        );
+        assert_eq!(
+            waybar_managed_include_path(&config),
+            user_dir.join("barforge-modules.jsonc")
+        );
     }
 
     #[test]
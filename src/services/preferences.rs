@@ -93,7 +93,7 @@ pub struct PreferencesSchema {
     pub fields: Vec<PreferenceField>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum PreferenceValue {
     String(String),
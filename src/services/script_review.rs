@@ -0,0 +1,168 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::security::{ScriptFinding, ScriptLanguage};
+
+const MAX_SCRIPT_SIZE: u64 = 1024 * 1024;
+
+/// The scripts in a module that contain risky patterns.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ScriptReview {
+    pub scripts: Vec<ReviewedScript>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReviewedScript {
+    /// Relative to the module directory.
+    pub path: PathBuf,
+    pub findings: Vec<ScriptFinding>,
+}
+
+impl ScriptReview {
+    pub fn is_clean(&self) -> bool {
+        self.scripts.is_empty()
+    }
+
+    pub fn finding_count(&self) -> usize {
+        self.scripts
+            .iter()
+            .map(|script| script.findings.len())
+            .sum()
+    }
+}
+
+impl std::fmt::Display for ScriptReview {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for script in &self.scripts {
+            for finding in &script.findings {
+                writeln!(
+                    f,
+                    "{}:{}: {}",
+                    script.path.display(),
+                    finding.line,
+                    finding.pattern
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Inspects every script below `dir` whose language is known, by its
+/// shebang or extension. Symlinks are not followed.
+pub fn review_scripts(dir: &Path) -> io::Result<ScriptReview> {
+    let mut scripts = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file()
+                && entry.metadata()?.len() <= MAX_SCRIPT_SIZE
+                && let Some((language, content)) = script(&path)
+            {
+                let findings = language.inspect(&content).findings;
+                if !findings.is_empty() {
+                    let relative = path.strip_prefix(dir).unwrap_or(&path).to_path_buf();
+                    scripts.push(ReviewedScript {
+                        path: relative,
+                        findings,
+                    });
+                }
+            }
+        }
+    }
+    scripts.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(ScriptReview { scripts })
+}
+
+/// The language and contents of `path` if it is a script Barforge can
+/// inspect.
+fn script(path: &Path) -> Option<(ScriptLanguage, String)> {
+    let content = std::fs::read_to_string(path).ok()?;
+    ScriptLanguage::detect(path, &content).map(|language| (language, content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::RiskyPattern;
+
+    #[test]
+    fn reports_risky_lines_in_scripts_only() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("scripts")).unwrap();
+        std::fs::write(
+            dir.path().join("scripts/fetch.sh"),
+            "#!/bin/sh\necho start\ncurl https://example.com\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("status"),
+            "#!/usr/bin/env bash\ncat /etc/shadow\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("clean.sh"), "#!/bin/sh\necho hi\n").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "curl https://example.com\n").unwrap();
+        std::fs::write(
+            dir.path().join("tool.py"),
+            "#!/usr/bin/env python3\nimport os  # curl \n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("weather"),
+            "#!/usr/bin/python3\nimport requests\n",
+        )
+        .unwrap();
+
+        let review = review_scripts(dir.path()).unwrap();
+        assert_eq!(
+            review.scripts,
+            vec![
+                ReviewedScript {
+                    path: PathBuf::from("scripts/fetch.sh"),
+                    findings: vec![ScriptFinding {
+                        line: 3,
+                        span: 21..25,
+                        pattern: RiskyPattern::NetworkCommand("curl".to_string()),
+                    }],
+                },
+                ReviewedScript {
+                    path: PathBuf::from("status"),
+                    findings: vec![ScriptFinding {
+                        line: 2,
+                        span: 24..35,
+                        pattern: RiskyPattern::SensitivePath("/etc/shadow".to_string()),
+                    }],
+                },
+                ReviewedScript {
+                    path: PathBuf::from("weather"),
+                    findings: vec![ScriptFinding {
+                        line: 2,
+                        span: 26..34,
+                        pattern: RiskyPattern::NetworkCommand("requests".to_string()),
+                    }],
+                },
+            ]
+        );
+        assert_eq!(review.finding_count(), 3);
+        assert_eq!(
+            review.to_string(),
+            "scripts/fetch.sh:3: network command `curl`\n\
+             status:2: sensitive path `/etc/shadow`\n\
+             weather:2: network command `requests`\n"
+        );
+    }
+
+    #[test]
+    fn modules_without_risky_scripts_are_clean() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("clock.sh"), "#!/bin/sh\ndate +%H:%M\n").unwrap();
+        assert!(review_scripts(dir.path()).unwrap().is_clean());
+    }
+}
@@ -4,6 +4,7 @@ use std::io;
 use std::path::PathBuf;
 
 use crate::app::state::{SortField, SortOrder, ViewMode};
+use crate::services::backups::DEFAULT_BACKUP_RETENTION;
 use crate::theme::ThemeMode;
 
 fn settings_path() -> PathBuf {
@@ -13,7 +14,7 @@ fn settings_path() -> PathBuf {
     config_dir.join("settings.json")
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserSettings {
     #[serde(default)]
     pub theme_mode: ThemeMode,
@@ -25,6 +26,38 @@ pub struct UserSettings {
     pub sort_order: SortOrder,
     #[serde(default)]
     pub tray_enabled: bool,
+    #[serde(default)]
+    pub allow_unsigned_updates: bool,
+    #[serde(default = "default_backup_retention")]
+    pub backup_retention: usize,
+    #[serde(default)]
+    pub sandbox_module_commands: bool,
+    #[serde(default = "default_seccomp_filter")]
+    pub seccomp_filter: bool,
+}
+
+fn default_backup_retention() -> usize {
+    DEFAULT_BACKUP_RETENTION
+}
+
+fn default_seccomp_filter() -> bool {
+    true
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self {
+            theme_mode: ThemeMode::default(),
+            view_mode: ViewMode::default(),
+            sort_field: SortField::default(),
+            sort_order: SortOrder::default(),
+            tray_enabled: false,
+            allow_unsigned_updates: false,
+            backup_retention: DEFAULT_BACKUP_RETENTION,
+            sandbox_module_commands: false,
+            seccomp_filter: true,
+        }
+    }
 }
 
 pub fn load_settings() -> UserSettings {
@@ -62,6 +95,13 @@ mod tests {
         let settings = UserSettings::default();
         assert!(matches!(settings.theme_mode, ThemeMode::System));
         assert!(matches!(settings.view_mode, ViewMode::Cards));
+        assert!(settings.seccomp_filter);
+    }
+
+    #[test]
+    fn test_seccomp_filter_defaults_on_for_older_settings() {
+        let settings: UserSettings = serde_json::from_str("{}").unwrap();
+        assert!(settings.seccomp_filter);
     }
 
     #[test]
@@ -72,6 +112,10 @@ mod tests {
             sort_field: SortField::Downloads,
             sort_order: SortOrder::Descending,
             tray_enabled: true,
+            allow_unsigned_updates: false,
+            backup_retention: 5,
+            sandbox_module_commands: true,
+            seccomp_filter: false,
         };
 
         let json = serde_json::to_string(&settings).unwrap();
@@ -80,5 +124,8 @@ mod tests {
         assert!(matches!(deserialized.theme_mode, ThemeMode::Dark));
         assert!(matches!(deserialized.view_mode, ViewMode::Table));
         assert!(matches!(deserialized.sort_field, SortField::Downloads));
+        assert_eq!(deserialized.backup_retention, 5);
+        assert!(deserialized.sandbox_module_commands);
+        assert!(!deserialized.seccomp_filter);
     }
 }
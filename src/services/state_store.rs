@@ -0,0 +1,318 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::domain::InstalledModule;
+use crate::services::own_writes::record_own_write;
+use crate::services::paths;
+
+pub const STATE_SCHEMA_VERSION: u32 = 1;
+
+const INSTALLED_STATE_FILE: &str = "installed.json";
+
+type Migration = fn(Value) -> Result<Value, StateError>;
+
+/// Indexed by the version a migration upgrades from.
+const MIGRATIONS: [Migration; STATE_SCHEMA_VERSION as usize] = [migrate_v0];
+
+#[derive(Debug, Error)]
+pub enum StateError {
+    #[error("Failed to access installed.json: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Failed to parse installed.json: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("installed.json has version {0}, which is newer than this Barforge supports")]
+    UnsupportedVersion(u32),
+
+    #[error("installed.json has no schema version")]
+    MissingVersion,
+
+    #[error("Module not found: {0}")]
+    ModuleNotFound(String),
+
+    #[error("{0}")]
+    Rejected(String),
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateFile {
+    version: u32,
+    modules: Vec<InstalledModule>,
+}
+
+pub fn installed_state_path() -> PathBuf {
+    paths::data_dir().join(INSTALLED_STATE_FILE)
+}
+
+#[derive(Debug, Clone)]
+pub struct StateStore {
+    path: PathBuf,
+}
+
+impl Default for StateStore {
+    fn default() -> Self {
+        Self::new(installed_state_path())
+    }
+}
+
+impl StateStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn load(&self) -> Result<Vec<InstalledModule>, StateError> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let lock = self.open_lock()?;
+        lock.lock_shared()?;
+        self.read()
+    }
+
+    /// Runs `f` on the current modules while holding the write lock and
+    /// saves the result unless `f` fails.
+    pub fn update<T>(
+        &self,
+        f: impl FnOnce(&mut Vec<InstalledModule>) -> Result<T, String>,
+    ) -> Result<T, StateError> {
+        let lock = self.open_lock()?;
+        lock.lock()?;
+        let mut modules = self.read()?;
+        let result = f(&mut modules).map_err(StateError::Rejected)?;
+        self.write(&modules)?;
+        Ok(result)
+    }
+
+    pub fn update_module(
+        &self,
+        uuid: &str,
+        f: impl FnOnce(&mut InstalledModule),
+    ) -> Result<InstalledModule, StateError> {
+        let lock = self.open_lock()?;
+        lock.lock()?;
+        let mut modules = self.read()?;
+        let module = modules
+            .iter_mut()
+            .find(|m| m.uuid.to_string() == uuid)
+            .ok_or_else(|| StateError::ModuleNotFound(uuid.to_string()))?;
+        f(module);
+        let updated = module.clone();
+        self.write(&modules)?;
+        Ok(updated)
+    }
+
+    fn open_lock(&self) -> Result<File, StateError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut lock_path = self.path.clone().into_os_string();
+        lock_path.push(".lock");
+        Ok(OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_path)?)
+    }
+
+    fn read(&self) -> Result<Vec<InstalledModule>, StateError> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let state: StateFile = serde_json::from_value(migrate(serde_json::from_str(&content)?)?)?;
+        Ok(state.modules)
+    }
+
+    fn write(&self, modules: &[InstalledModule]) -> Result<(), StateError> {
+        let content = serde_json::to_string_pretty(&serde_json::json!({
+            "version": STATE_SCHEMA_VERSION,
+            "modules": modules,
+        }))?;
+
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(format!(".{}.tmp", std::process::id()));
+        let temp_path = PathBuf::from(temp_path);
+
+        record_own_write(&self.path);
+        let written = File::create(&temp_path).and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|()| std::fs::rename(&temp_path, &self.path)) {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+fn schema_version(state: &Value) -> Result<u32, StateError> {
+    match state {
+        Value::Array(_) => Ok(0),
+        Value::Object(object) => object
+            .get("version")
+            .and_then(Value::as_u64)
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or(StateError::MissingVersion),
+        _ => Err(StateError::MissingVersion),
+    }
+}
+
+fn migrate(mut state: Value) -> Result<Value, StateError> {
+    let version = schema_version(&state)?;
+    if version > STATE_SCHEMA_VERSION {
+        return Err(StateError::UnsupportedVersion(version));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        state = migration(state)?;
+    }
+    Ok(state)
+}
+
+/// Version 0 stored the module list as a bare array.
+fn migrate_v0(state: Value) -> Result<Value, StateError> {
+    Ok(serde_json::json!({ "version": 1, "modules": state }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::InstalledModuleBuilder;
+    use tempfile::tempdir;
+
+    fn store_in(dir: &Path) -> StateStore {
+        StateStore::new(dir.join(INSTALLED_STATE_FILE))
+    }
+
+    #[test]
+    fn missing_state_loads_empty() {
+        let dir = tempdir().unwrap();
+        assert!(store_in(dir.path()).load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn update_round_trips_with_version() {
+        let dir = tempdir().unwrap();
+        let store = store_in(dir.path());
+
+        store
+            .update(|modules| {
+                modules.push(InstalledModuleBuilder::new("weather").build());
+                Ok(())
+            })
+            .unwrap();
+
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.len(), 1);
+        let raw: Value =
+            serde_json::from_str(&std::fs::read_to_string(store.path()).unwrap()).unwrap();
+        assert_eq!(raw["version"], STATE_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn legacy_array_is_migrated() {
+        let dir = tempdir().unwrap();
+        let store = store_in(dir.path());
+        let legacy = vec![InstalledModuleBuilder::new("weather").build()];
+        std::fs::write(store.path(), serde_json::to_string(&legacy).unwrap()).unwrap();
+
+        assert_eq!(store.load().unwrap().len(), 1);
+
+        store
+            .update_module("weather@test", |m| m.enabled = true)
+            .unwrap();
+        let raw: Value =
+            serde_json::from_str(&std::fs::read_to_string(store.path()).unwrap()).unwrap();
+        assert_eq!(raw["version"], 1);
+        assert_eq!(raw["modules"][0]["enabled"], true);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let dir = tempdir().unwrap();
+        let store = store_in(dir.path());
+        std::fs::write(store.path(), r#"{"version": 99, "modules": []}"#).unwrap();
+
+        assert!(matches!(
+            store.load(),
+            Err(StateError::UnsupportedVersion(99))
+        ));
+        assert!(store.update(|_| Ok(())).is_err());
+        assert!(
+            std::fs::read_to_string(store.path())
+                .unwrap()
+                .contains("99")
+        );
+    }
+
+    #[test]
+    fn rejected_update_leaves_state_untouched() {
+        let dir = tempdir().unwrap();
+        let store = store_in(dir.path());
+
+        let result = store.update(|modules| {
+            modules.push(InstalledModuleBuilder::new("weather").build());
+            Err::<(), _>("no".to_string())
+        });
+
+        assert!(matches!(result, Err(StateError::Rejected(_))));
+        assert!(!store.path().exists());
+    }
+
+    #[test]
+    fn update_module_reports_missing_module() {
+        let dir = tempdir().unwrap();
+        let result = store_in(dir.path()).update_module("missing@test", |_| {});
+        assert!(matches!(result, Err(StateError::ModuleNotFound(_))));
+    }
+
+    #[test]
+    fn write_leaves_no_temp_files() {
+        let dir = tempdir().unwrap();
+        let store = store_in(dir.path());
+        store.update(|_| Ok(())).unwrap();
+
+        let leftovers: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    #[test]
+    fn concurrent_updates_keep_every_write() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(INSTALLED_STATE_FILE);
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let store = StateStore::new(&path);
+                std::thread::spawn(move || {
+                    store
+                        .update(|modules| {
+                            modules.push(InstalledModuleBuilder::new(&format!("mod{i}")).build());
+                            Ok(())
+                        })
+                        .unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(StateStore::new(&path).load().unwrap().len(), 8);
+    }
+}
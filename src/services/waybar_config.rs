@@ -1,7 +1,18 @@
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
-use crate::domain::BarSection;
+use jsonc_parser::cst::{CstArray, CstInputValue, CstNode, CstObject, CstRootNode};
+use serde::Serialize;
+
+use crate::domain::{BarSection, ExternalModule, ModulePosition};
+use crate::services::backups::BackupStore;
+use crate::services::config_preview::FileChange;
+use crate::services::own_writes::record_own_write;
 use crate::services::paths;
+use crate::services::waybar_process::WaybarReloader;
+
+const INCLUDE_DEPTH_LIMIT: usize = 16;
+const EMPTY_INCLUDE: &str = "{\n}\n";
 
 pub async fn load_config() -> Result<String, String> {
     let path = paths::waybar_config_path();
@@ -15,91 +26,372 @@ pub async fn load_config() -> Result<String, String> {
         .map_err(|e| format!("Failed to read waybar config: {e}"))
 }
 
-pub async fn save_config(content: &str) -> Result<(), String> {
-    let path = paths::waybar_config_path();
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BarInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub outputs: Vec<String>,
+}
 
-    tokio::fs::write(&path, content)
-        .await
-        .map_err(|e| format!("Failed to write waybar config: {e}"))
+impl BarInfo {
+    pub fn id(&self) -> String {
+        self.name
+            .clone()
+            .or_else(|| self.outputs.first().cloned())
+            .unwrap_or_else(|| format!("#{}", self.index + 1))
+    }
+
+    pub fn matches(&self, id: &str) -> bool {
+        self.name.as_deref() == Some(id)
+            || self.outputs.iter().any(|output| output == id)
+            || format!("#{}", self.index + 1) == id
+    }
 }
 
-pub async fn backup_config() -> Result<PathBuf, String> {
-    let path = paths::waybar_config_path();
+impl std::fmt::Display for BarInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name}")?,
+            None => write!(f, "Bar {}", self.index + 1)?,
+        }
+        if !self.outputs.is_empty() {
+            write!(f, " ({})", self.outputs.join(", "))?;
+        }
+        Ok(())
+    }
+}
 
-    if !path.exists() {
-        return Err("No config to backup".to_string());
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConfigIssue {
+    Unparsable { path: PathBuf, error: String },
+    NotAnArray { bar: String, key: String },
+    DuplicateEntry { bar: String, name: String },
+    MissingDefinition { bar: String, name: String },
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unparsable { path, error } => write!(f, "{}: {error}", path.display()),
+            Self::NotAnArray { bar, key } => write!(f, "{bar}: {key} is not an array"),
+            Self::DuplicateEntry { bar, name } => write!(f, "{bar}: {name} is listed twice"),
+            Self::MissingDefinition { bar, name } => {
+                write!(f, "{bar}: {name} is listed but not defined")
+            }
+        }
     }
+}
 
-    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-    let backup_name = format!("config.jsonc.{}.backup", timestamp);
-    let backup_path = path.with_file_name(backup_name);
+fn needs_definition(module_name: &str) -> bool {
+    module_name.starts_with("custom/") || module_name.starts_with("group/")
+}
 
-    tokio::fs::copy(&path, &backup_path)
-        .await
-        .map_err(|e| format!("Failed to create backup: {e}"))?;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutLane {
+    pub section: BarSection,
+    pub entries: Vec<String>,
+}
 
-    tracing::info!("Created waybar config backup at {}", backup_path.display());
+fn parse_config(content: &str) -> Result<CstRootNode, String> {
+    let root = CstRootNode::parse(content, &Default::default())
+        .map_err(|e| format!("Failed to parse waybar config: {e}"))?;
+    if root.value().is_none() {
+        return Err("Empty waybar config".to_string());
+    }
+    Ok(root)
+}
+
+fn bar_objects(root: &CstRootNode) -> Result<Vec<CstObject>, String> {
+    if let Some(obj) = root.object_value() {
+        return Ok(vec![obj]);
+    }
 
-    Ok(backup_path)
+    let bars = root
+        .array_value()
+        .ok_or("Waybar config is not a JSON object or an array of bars")?;
+
+    bars.elements()
+        .into_iter()
+        .map(|bar| {
+            bar.as_object()
+                .ok_or_else(|| "Waybar config bars must be JSON objects".to_string())
+        })
+        .collect()
 }
 
-pub fn add_module(content: &str, module_name: &str, section: BarSection) -> Result<String, String> {
-    let array_key = section.array_key();
+fn bar_info(index: usize, bar: &CstObject) -> BarInfo {
+    let string_value = |key: &str| {
+        bar.get(key)
+            .and_then(|prop| prop.value())
+            .and_then(|value| value.to_serde_value())
+    };
 
-    let value: serde_json::Value = jsonc_parser::parse_to_serde_value(content, &Default::default())
-        .map_err(|e| format!("Failed to parse waybar config: {e}"))?
-        .ok_or("Empty waybar config")?;
+    let name = string_value("name").and_then(|v| v.as_str().map(str::to_string));
+    let outputs = match string_value("output") {
+        Some(serde_json::Value::String(output)) => vec![output],
+        Some(serde_json::Value::Array(outputs)) => outputs
+            .into_iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
 
-    let mut obj = match value {
-        serde_json::Value::Object(obj) => obj,
-        _ => return Err("Waybar config is not a JSON object".to_string()),
+    BarInfo {
+        index,
+        name,
+        outputs,
+    }
+}
+
+fn select_bars(root: &CstRootNode, bar: Option<&str>) -> Result<Vec<CstObject>, String> {
+    let bars = bar_objects(root)?;
+    let Some(id) = bar else {
+        return Ok(bars);
     };
 
-    let modules_array = obj
-        .entry(array_key)
-        .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+    let selected: Vec<CstObject> = bars
+        .into_iter()
+        .enumerate()
+        .filter(|(index, obj)| bar_info(*index, obj).matches(id))
+        .map(|(_, obj)| obj)
+        .collect();
 
-    let arr = modules_array
-        .as_array_mut()
-        .ok_or_else(|| format!("{} is not an array", array_key))?;
+    if selected.is_empty() {
+        return Err(format!("No bar named {id} in waybar config"));
+    }
+    Ok(selected)
+}
 
-    let module_value = serde_json::Value::String(module_name.to_string());
-    if !arr.contains(&module_value) {
-        arr.push(module_value);
-        tracing::info!("Added {} to {}", module_name, array_key);
-    } else {
-        tracing::info!("{} already in {}", module_name, array_key);
+fn target_bar(root: &CstRootNode, bar: Option<&str>) -> Result<CstObject, String> {
+    select_bars(root, bar)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Waybar config has no bars".to_string())
+}
+
+pub fn list_bars(content: &str) -> Result<Vec<BarInfo>, String> {
+    let root = parse_config(content)?;
+    Ok(bar_objects(&root)?
+        .iter()
+        .enumerate()
+        .map(|(index, bar)| bar_info(index, bar))
+        .collect())
+}
+
+fn is_string_entry(node: &CstNode, module_name: &str) -> bool {
+    node.as_string_lit()
+        .and_then(|lit| lit.decoded_value().ok())
+        .is_some_and(|name| name == module_name)
+}
+
+/// Escapes `s` for use as a string value or property name in the CST.
+/// jsonc-parser only escapes quotes when it writes a string literal, so
+/// backslashes and control characters are escaped here.
+fn escape_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
     }
+    escaped
+}
 
-    serde_json::to_string_pretty(&serde_json::Value::Object(obj))
-        .map_err(|e| format!("Failed to serialize config: {e}"))
+fn string_input(s: &str) -> CstInputValue {
+    CstInputValue::String(escape_string(s))
 }
 
-pub fn remove_module(content: &str, module_name: &str) -> Result<String, String> {
-    let value: serde_json::Value = jsonc_parser::parse_to_serde_value(content, &Default::default())
-        .map_err(|e| format!("Failed to parse waybar config: {e}"))?
-        .ok_or("Empty waybar config")?;
+fn to_cst_input(value: serde_json::Value) -> CstInputValue {
+    match value {
+        serde_json::Value::Null => CstInputValue::Null,
+        serde_json::Value::Bool(b) => CstInputValue::Bool(b),
+        serde_json::Value::Number(n) => CstInputValue::Number(n.to_string()),
+        serde_json::Value::String(s) => string_input(&s),
+        serde_json::Value::Array(arr) => {
+            CstInputValue::Array(arr.into_iter().map(to_cst_input).collect())
+        }
+        serde_json::Value::Object(obj) => CstInputValue::Object(
+            obj.into_iter()
+                .map(|(key, value)| (escape_string(&key), to_cst_input(value)))
+                .collect(),
+        ),
+    }
+}
 
-    let mut obj = match value {
-        serde_json::Value::Object(obj) => obj,
-        _ => return Err("Waybar config is not a JSON object".to_string()),
-    };
+pub fn add_module(
+    content: &str,
+    module_name: &str,
+    section: BarSection,
+    bar: Option<&str>,
+) -> Result<String, String> {
+    insert_module(content, module_name, section, bar, None)
+}
 
-    for array_key in ["modules-left", "modules-center", "modules-right"] {
-        if let Some(modules) = obj.get_mut(array_key)
-            && let Some(arr) = modules.as_array_mut()
-        {
-            let original_len = arr.len();
-            arr.retain(|v| v.as_str() != Some(module_name));
+pub fn insert_module(
+    content: &str,
+    module_name: &str,
+    section: BarSection,
+    bar: Option<&str>,
+    index: Option<usize>,
+) -> Result<String, String> {
+    let array_key = section.array_key();
 
-            if arr.len() < original_len {
-                tracing::info!("Removed {} from {}", module_name, array_key);
+    let root = parse_config(content)?;
+    let obj = target_bar(&root, bar)?;
+
+    let arr = section_array(&obj, array_key)?;
+
+    let elements = arr.elements();
+    let existing = elements
+        .iter()
+        .position(|v| is_string_entry(v, module_name));
+    let entry = || string_input(module_name);
+
+    match (existing, index) {
+        (Some(current), Some(index)) if current != index.min(elements.len() - 1) => {
+            elements
+                .into_iter()
+                .nth(current)
+                .expect("position is in bounds")
+                .remove();
+            arr.insert(index.min(arr.elements().len()), entry());
+            tracing::info!("Moved {} to {} in {}", module_name, index, array_key);
+        }
+        (Some(_), _) => {
+            tracing::info!("{} already in {}", module_name, array_key);
+        }
+        (None, Some(index)) => {
+            arr.insert(index.min(elements.len()), entry());
+            tracing::info!("Added {} at {} in {}", module_name, index, array_key);
+        }
+        (None, None) => {
+            arr.append(entry());
+            tracing::info!("Added {} to {}", module_name, array_key);
+        }
+    }
+
+    Ok(root.to_string())
+}
+
+fn section_array(bar: &CstObject, array_key: &str) -> Result<CstArray, String> {
+    match bar.get(array_key) {
+        Some(prop) => prop
+            .value()
+            .and_then(|value| value.as_array())
+            .ok_or_else(|| format!("{} is not an array", array_key)),
+        None => Ok(bar.array_value_or_set(array_key)),
+    }
+}
+
+fn module_index(bar: &CstObject, array_key: &str, module_name: &str) -> Option<usize> {
+    bar.array_value(array_key)?
+        .elements()
+        .iter()
+        .position(|v| is_string_entry(v, module_name))
+}
+
+pub fn locate_module(
+    content: &str,
+    module_name: &str,
+    bar: Option<&str>,
+) -> Result<Option<(BarSection, usize)>, String> {
+    let root = parse_config(content)?;
+    let obj = target_bar(&root, bar)?;
+
+    Ok(BarSection::all().iter().find_map(|section| {
+        module_index(&obj, section.array_key(), module_name).map(|index| (*section, index))
+    }))
+}
+
+fn entry_name(node: &CstNode) -> String {
+    node.as_string_lit()
+        .and_then(|lit| lit.decoded_value().ok())
+        .unwrap_or_else(|| node.to_string())
+}
+
+pub fn section_entries(
+    content: &str,
+    section: BarSection,
+    bar: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let root = parse_config(content)?;
+    let obj = target_bar(&root, bar)?;
+
+    Ok(obj
+        .array_value(section.array_key())
+        .map(|arr| arr.elements().iter().map(entry_name).collect())
+        .unwrap_or_default())
+}
+
+pub fn take_entry(
+    content: &str,
+    section: BarSection,
+    bar: Option<&str>,
+    index: usize,
+) -> Result<(String, String), String> {
+    let array_key = section.array_key();
+
+    let root = parse_config(content)?;
+    let obj = target_bar(&root, bar)?;
+    let node = obj
+        .array_value(array_key)
+        .and_then(|arr| arr.elements().into_iter().nth(index))
+        .ok_or_else(|| format!("No entry at {index} in {array_key}"))?;
+
+    let name = entry_name(&node);
+    node.remove();
+    Ok((root.to_string(), name))
+}
+
+pub fn put_entry(
+    content: &str,
+    entry: &str,
+    section: BarSection,
+    bar: Option<&str>,
+    index: usize,
+) -> Result<String, String> {
+    let array_key = section.array_key();
+
+    let root = parse_config(content)?;
+    let obj = target_bar(&root, bar)?;
+    let arr = section_array(&obj, array_key)?;
+
+    let len = arr.elements().len();
+    arr.insert(index.min(len), string_input(entry));
+    Ok(root.to_string())
+}
+
+pub fn remove_module(
+    content: &str,
+    module_name: &str,
+    bar: Option<&str>,
+) -> Result<String, String> {
+    let root = parse_config(content)?;
+
+    for obj in select_bars(&root, bar)? {
+        for array_key in ["modules-left", "modules-center", "modules-right"] {
+            if let Some(arr) = obj.array_value(array_key) {
+                let matches: Vec<CstNode> = arr
+                    .elements()
+                    .into_iter()
+                    .filter(|v| is_string_entry(v, module_name))
+                    .collect();
+
+                if !matches.is_empty() {
+                    matches.into_iter().for_each(CstNode::remove);
+                    tracing::info!("Removed {} from {}", module_name, array_key);
+                }
             }
         }
     }
 
-    serde_json::to_string_pretty(&serde_json::Value::Object(obj))
-        .map_err(|e| format!("Failed to serialize config: {e}"))
+    Ok(root.to_string())
 }
 
 pub fn substitute_preferences(
@@ -127,103 +419,806 @@ pub fn substitute_preferences(
     result
 }
 
+/// Routes the commands Waybar runs for a module through
+/// `barforge run-sandboxed`, which applies the module's declared permissions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSandbox {
+    pub program: String,
+    pub uuid: String,
+}
+
+impl CommandSandbox {
+    /// The wrapper for `uuid` when sandboxed module commands are turned on
+    /// in the settings.
+    pub fn from_settings(uuid: &str) -> Option<Self> {
+        if !crate::services::load_settings().sandbox_module_commands {
+            return None;
+        }
+        let program = std::env::current_exe()
+            .inspect_err(|e| tracing::warn!("Not sandboxing module commands: {e}"))
+            .ok()?;
+        Some(Self {
+            program: program.to_string_lossy().into_owned(),
+            uuid: uuid.to_string(),
+        })
+    }
+
+    fn prefix(&self) -> String {
+        format!(
+            "{} run-sandboxed {} -- ",
+            shell_quote(&self.program),
+            shell_quote(&self.uuid)
+        )
+    }
+
+    /// Waybar hands commands to `sh -c`, so the original command is passed on
+    /// to another `sh -c` inside the sandbox.
+    pub fn wrap(&self, command: &str) -> String {
+        let prefix = self.prefix();
+        if command.starts_with(&prefix) {
+            return command.to_string();
+        }
+        format!("{prefix}sh -c {}", shell_quote(command))
+    }
+
+    fn wrap_definition(&self, definition: &mut serde_json::Value) {
+        let Some(definition) = definition.as_object_mut() else {
+            return;
+        };
+        for (key, value) in definition.iter_mut() {
+            if is_command_key(key)
+                && let serde_json::Value::String(command) = value
+                && !command.trim().is_empty()
+            {
+                *command = self.wrap(command);
+            }
+        }
+    }
+}
+
+/// Keys whose values Waybar runs as shell commands.
+fn is_command_key(key: &str) -> bool {
+    key == "exec" || key == "exec-if" || key.starts_with("on-")
+}
+
+fn shell_quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c));
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
 pub fn merge_module_config(
     waybar_content: &str,
     module_content: &str,
     install_path: &str,
+    bar: Option<&str>,
+    sandbox: Option<&CommandSandbox>,
 ) -> Result<String, String> {
     let module_content = module_content.replace("$MODULE_PATH", install_path);
 
-    let waybar_value: serde_json::Value =
-        jsonc_parser::parse_to_serde_value(waybar_content, &Default::default())
-            .map_err(|e| format!("Failed to parse waybar config: {e}"))?
-            .ok_or("Empty waybar config")?;
+    let root = parse_config(waybar_content)?;
+    let waybar_obj = target_bar(&root, bar)?;
 
     let module_value: serde_json::Value =
         jsonc_parser::parse_to_serde_value(&module_content, &Default::default())
             .map_err(|e| format!("Failed to parse module config: {e}"))?
             .ok_or("Empty module config")?;
 
-    let mut waybar_obj = match waybar_value {
-        serde_json::Value::Object(obj) => obj,
-        _ => return Err("Waybar config is not a JSON object".to_string()),
-    };
-
     let module_obj = match module_value {
         serde_json::Value::Object(obj) => obj,
         _ => return Err("Module config is not a JSON object".to_string()),
     };
 
-    for (key, value) in module_obj {
-        waybar_obj.insert(key, value);
+    for (key, mut value) in module_obj {
+        if let Some(sandbox) = sandbox {
+            sandbox.wrap_definition(&mut value);
+        }
+        match waybar_obj.get(&key) {
+            Some(prop) => prop.set_value(to_cst_input(value)),
+            None => {
+                waybar_obj.append(&escape_string(&key), to_cst_input(value));
+            }
+        }
     }
 
-    serde_json::to_string_pretty(&serde_json::Value::Object(waybar_obj))
-        .map_err(|e| format!("Failed to serialize config: {e}"))
+    Ok(root.to_string())
+}
+
+pub fn module_definition(content: &str, module_name: &str) -> Option<serde_json::Value> {
+    let root = parse_config(content).ok()?;
+    bar_objects(&root).ok()?.iter().find_map(|bar| {
+        bar.get(module_name)
+            .and_then(|prop| prop.value())
+            .and_then(|value| value.to_serde_value())
+    })
 }
 
-pub fn inject_module_css(existing_css: &str, uuid: &str, module_css: &str) -> String {
-    format!(
-        "{}\n/* BEGIN barforge:{} */\n{}\n/* END barforge:{} */",
-        existing_css.trim_end(),
-        uuid,
-        module_css.trim(),
-        uuid
-    )
-}
+pub fn remove_module_config(
+    waybar_content: &str,
+    module_name: &str,
+    bar: Option<&str>,
+) -> Result<String, String> {
+    let root = parse_config(waybar_content)?;
+
+    for waybar_obj in select_bars(&root, bar)? {
+        if let Some(prop) = waybar_obj.get(module_name) {
+            prop.remove();
+        }
+    }
+
+    Ok(root.to_string())
+}
+
+fn include_entries(bar: &CstObject) -> Vec<String> {
+    match bar
+        .get("include")
+        .and_then(|prop| prop.value())
+        .and_then(|value| value.to_serde_value())
+    {
+        Some(serde_json::Value::String(include)) => vec![include],
+        Some(serde_json::Value::Array(includes)) => includes
+            .into_iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn expand_env(value: &str) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, remainder) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", after),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        if name.is_empty() {
+            expanded.push('$');
+            rest = after;
+        } else {
+            expanded.push_str(&std::env::var(name).unwrap_or_default());
+            rest = remainder;
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+pub fn resolve_include(include: &str, base_dir: &Path) -> PathBuf {
+    let path = paths::expand_home(&expand_env(include));
+    if path.is_absolute() {
+        path
+    } else {
+        base_dir.join(path)
+    }
+}
+
+pub fn add_include(content: &str, include: &str, bar: Option<&str>) -> Result<String, String> {
+    let root = parse_config(content)?;
+    let obj = target_bar(&root, bar)?;
+
+    match obj.get("include") {
+        None => {
+            obj.append("include", CstInputValue::Array(vec![string_input(include)]));
+        }
+        Some(prop) => match prop.value() {
+            Some(value) if value.as_array().is_some() => {
+                let includes = value.as_array().expect("checked above");
+                if !includes
+                    .elements()
+                    .iter()
+                    .any(|v| is_string_entry(v, include))
+                {
+                    includes.append(string_input(include));
+                }
+            }
+            Some(value) if value.as_string_lit().is_some() => {
+                if !is_string_entry(&value, include) {
+                    let existing = value
+                        .as_string_lit()
+                        .and_then(|lit| lit.decoded_value().ok())
+                        .unwrap_or_default();
+                    prop.set_value(CstInputValue::Array(vec![
+                        string_input(&existing),
+                        string_input(include),
+                    ]));
+                }
+            }
+            _ => return Err("include is not a string or an array".to_string()),
+        },
+    }
+
+    Ok(root.to_string())
+}
+
+fn defines_key(content: &str, key: &str) -> bool {
+    parse_config(content)
+        .ok()
+        .and_then(|root| root.object_value())
+        .is_some_and(|obj| obj.get(key).is_some())
+}
+
+#[derive(Debug, Clone)]
+struct ConfigFile {
+    path: PathBuf,
+    content: String,
+    original: Option<String>,
+}
+
+impl ConfigFile {
+    fn read(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            original: Some(content.clone()),
+            content,
+        })
+    }
+
+    fn is_changed(&self) -> bool {
+        self.original.as_deref().unwrap_or(EMPTY_INCLUDE) != self.content
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WaybarFiles {
+    main: ConfigFile,
+    managed: ConfigFile,
+    includes: Vec<ConfigFile>,
+    bar_includes: Vec<Vec<usize>>,
+    /// Where `save` snapshots the files, together with these stylesheets,
+    /// before overwriting them.
+    backups: Option<(BackupStore, Vec<PathBuf>)>,
+}
+
+impl WaybarFiles {
+    pub fn load() -> Result<Self, String> {
+        let style = paths::waybar_style_path();
+        let styles = vec![paths::waybar_managed_style_path(&style), style];
+        Ok(Self::open(&paths::waybar_config_path())?.with_backups(BackupStore::default(), styles))
+    }
+
+    pub fn with_backups(mut self, store: BackupStore, style_paths: Vec<PathBuf>) -> Self {
+        self.backups = Some((store, style_paths));
+        self
+    }
+
+    pub fn open(config_path: &Path) -> Result<Self, String> {
+        if !config_path.exists() {
+            return Err(format!(
+                "Waybar config not found at {}",
+                config_path.display()
+            ));
+        }
+        let main = ConfigFile::read(config_path)?;
+        let managed_path = paths::waybar_managed_include_path(config_path);
+        let managed = if managed_path.exists() {
+            ConfigFile::read(&managed_path)?
+        } else {
+            ConfigFile {
+                path: managed_path,
+                content: EMPTY_INCLUDE.to_string(),
+                original: None,
+            }
+        };
+
+        let mut files = Self {
+            main,
+            managed,
+            includes: Vec::new(),
+            bar_includes: Vec::new(),
+            backups: None,
+        };
+
+        let base_dir = config_path.parent().unwrap_or(Path::new("/")).to_path_buf();
+        let root = parse_config(&files.main.content)?;
+        for bar in bar_objects(&root)? {
+            let mut chain = Vec::new();
+            let mut visited = HashSet::from([files.main.path.clone()]);
+            for include in include_entries(&bar) {
+                files.load_include(
+                    &resolve_include(&include, &base_dir),
+                    0,
+                    &mut visited,
+                    &mut chain,
+                );
+            }
+            files.bar_includes.push(chain);
+        }
+
+        Ok(files)
+    }
+
+    fn load_include(
+        &mut self,
+        path: &Path,
+        depth: usize,
+        visited: &mut HashSet<PathBuf>,
+        chain: &mut Vec<usize>,
+    ) {
+        if depth >= INCLUDE_DEPTH_LIMIT || path == self.managed.path || !visited.insert(path.into())
+        {
+            return;
+        }
+
+        let index = match self.includes.iter().position(|f| f.path == path) {
+            Some(index) => index,
+            None => match ConfigFile::read(path) {
+                Ok(file) => {
+                    self.includes.push(file);
+                    self.includes.len() - 1
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping waybar include: {e}");
+                    return;
+                }
+            },
+        };
+        chain.push(index);
+
+        let nested = parse_config(&self.includes[index].content)
+            .ok()
+            .and_then(|root| root.object_value())
+            .map(|obj| include_entries(&obj))
+            .unwrap_or_default();
+        let base_dir = path.parent().unwrap_or(Path::new("/")).to_path_buf();
+        for include in nested {
+            self.load_include(
+                &resolve_include(&include, &base_dir),
+                depth + 1,
+                visited,
+                chain,
+            );
+        }
+    }
+
+    pub fn config_path(&self) -> &Path {
+        &self.main.path
+    }
+
+    pub fn managed_path(&self) -> &Path {
+        &self.managed.path
+    }
+
+    pub fn config(&self) -> &str {
+        &self.main.content
+    }
+
+    pub fn managed(&self) -> &str {
+        &self.managed.content
+    }
+
+    pub fn include_paths(&self) -> Vec<&Path> {
+        self.includes.iter().map(|f| f.path.as_path()).collect()
+    }
+
+    fn bar_index(&self, bar: Option<&str>) -> Result<usize, String> {
+        let bars = list_bars(&self.main.content)?;
+        match bar {
+            None if bars.is_empty() => Err("Waybar config has no bars".to_string()),
+            None => Ok(0),
+            Some(id) => bars
+                .iter()
+                .position(|b| b.matches(id))
+                .ok_or_else(|| format!("No bar named {id} in waybar config")),
+        }
+    }
+
+    fn ensure_managed_include(&mut self, bar: Option<&str>) -> Result<(), String> {
+        let index = self.bar_index(bar)?;
+        let root = parse_config(&self.main.content)?;
+        let base_dir = self.main.path.parent().unwrap_or(Path::new("/"));
+        let included = bar_objects(&root)?.get(index).is_some_and(|obj| {
+            include_entries(obj)
+                .iter()
+                .any(|include| resolve_include(include, base_dir) == self.managed.path)
+        });
+
+        if !included {
+            let include = self.managed.path.to_string_lossy().into_owned();
+            self.main.content = add_include(&self.main.content, &include, bar)?;
+        }
+        Ok(())
+    }
+
+    fn section_owner(&self, bar_index: usize, array_key: &str) -> Result<Option<usize>, String> {
+        let root = parse_config(&self.main.content)?;
+        let in_main = bar_objects(&root)?
+            .get(bar_index)
+            .is_some_and(|obj| obj.get(array_key).is_some());
+        if in_main {
+            return Ok(None);
+        }
+
+        Ok(self.bar_includes[bar_index]
+            .iter()
+            .copied()
+            .find(|&i| defines_key(&self.includes[i].content, array_key)))
+    }
+
+    pub fn add_module(
+        &mut self,
+        module_name: &str,
+        position: &ModulePosition,
+    ) -> Result<(), String> {
+        let bar = position.bar.as_deref();
+        let section = position.section;
+        let order = position.order.map(|order| order as usize);
+
+        match self.section_owner(self.bar_index(bar)?, section.array_key())? {
+            Some(i) => {
+                let file = &mut self.includes[i];
+                file.content = insert_module(&file.content, module_name, section, None, order)?;
+            }
+            None => {
+                self.main.content =
+                    insert_module(&self.main.content, module_name, section, bar, order)?;
+            }
+        }
+
+        if defines_key(&self.managed.content, module_name) {
+            self.ensure_managed_include(bar)?;
+        }
+        Ok(())
+    }
+
+    pub fn locate_module(
+        &self,
+        module_name: &str,
+        bar: Option<&str>,
+    ) -> Result<Option<(BarSection, usize)>, String> {
+        let bar_index = self.bar_index(bar)?;
+        for section in BarSection::all() {
+            let found = match self.section_owner(bar_index, section.array_key())? {
+                Some(i) => locate_module(&self.includes[i].content, module_name, None)?,
+                None => locate_module(&self.main.content, module_name, bar)?,
+            };
+            if let Some((found_section, index)) = found
+                && found_section == *section
+            {
+                return Ok(Some((found_section, index)));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn layout(&self, bar: Option<&str>) -> Result<Vec<LayoutLane>, String> {
+        let bar_index = self.bar_index(bar)?;
+        BarSection::all()
+            .iter()
+            .map(|&section| {
+                let entries = match self.section_owner(bar_index, section.array_key())? {
+                    Some(i) => section_entries(&self.includes[i].content, section, None)?,
+                    None => section_entries(&self.main.content, section, bar)?,
+                };
+                Ok(LayoutLane { section, entries })
+            })
+            .collect()
+    }
+
+    pub fn move_entry(
+        &mut self,
+        bar: Option<&str>,
+        from: (BarSection, usize),
+        to: (BarSection, usize),
+    ) -> Result<String, String> {
+        let bar_index = self.bar_index(bar)?;
+
+        let entry = match self.section_owner(bar_index, from.0.array_key())? {
+            Some(i) => {
+                let file = &mut self.includes[i];
+                let (content, entry) = take_entry(&file.content, from.0, None, from.1)?;
+                file.content = content;
+                entry
+            }
+            None => {
+                let (content, entry) = take_entry(&self.main.content, from.0, bar, from.1)?;
+                self.main.content = content;
+                entry
+            }
+        };
+
+        match self.section_owner(bar_index, to.0.array_key())? {
+            Some(i) => {
+                let file = &mut self.includes[i];
+                file.content = put_entry(&file.content, &entry, to.0, None, to.1)?;
+            }
+            None => {
+                self.main.content = put_entry(&self.main.content, &entry, to.0, bar, to.1)?;
+            }
+        }
+
+        Ok(entry)
+    }
+
+    pub fn remove_module(&mut self, module_name: &str) -> Result<(), String> {
+        self.main.content = remove_module(&self.main.content, module_name, None)?;
+        for file in &mut self.includes {
+            if let Ok(content) = remove_module(&file.content, module_name, None) {
+                file.content = content;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remove_module_from_bar(
+        &mut self,
+        module_name: &str,
+        bar: Option<&str>,
+    ) -> Result<(), String> {
+        let bar_index = self.bar_index(bar)?;
+        let bar_id = list_bars(&self.main.content)?[bar_index].id();
+
+        self.main.content = remove_module(&self.main.content, module_name, Some(&bar_id))?;
+        for &i in &self.bar_includes[bar_index] {
+            let file = &mut self.includes[i];
+            if let Ok(content) = remove_module(&file.content, module_name, None) {
+                file.content = content;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_placed(&self, module_name: &str) -> Result<bool, String> {
+        for bar in list_bars(&self.main.content)? {
+            if self.locate_module(module_name, Some(&bar.id()))?.is_some() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn definition(&self, module_name: &str) -> Option<serde_json::Value> {
+        std::iter::once(&self.managed)
+            .chain(std::iter::once(&self.main))
+            .chain(&self.includes)
+            .find_map(|file| module_definition(&file.content, module_name))
+    }
+
+    pub fn remove_definition(&mut self, module_name: &str) -> Result<(), String> {
+        self.remove_module_config(module_name)?;
+        for file in &mut self.includes {
+            if let Ok(content) = remove_module_config(&file.content, module_name, None) {
+                file.content = content;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn external_modules(&self, managed: &[&str]) -> Result<Vec<ExternalModule>, String> {
+        let bars = list_bars(&self.main.content)?;
+        let mut modules: Vec<ExternalModule> = Vec::new();
+
+        for bar in &bars {
+            let bar_id = (bars.len() > 1).then(|| bar.id());
+            for lane in self.layout(bar_id.as_deref())? {
+                for (index, name) in lane.entries.iter().enumerate() {
+                    let known = managed.contains(&name.as_str())
+                        || modules
+                            .iter()
+                            .any(|m| &m.name == name && m.position.bar == bar_id);
+                    if known {
+                        continue;
+                    }
+                    modules.push(ExternalModule {
+                        name: name.clone(),
+                        enabled: true,
+                        position: ModulePosition::new(lane.section)
+                            .on_bar(bar_id.clone())
+                            .at(Some(index as u32)),
+                        has_definition: self.definition(name).is_some(),
+                    });
+                }
+            }
+        }
+        Ok(modules)
+    }
+
+    pub fn merge_module_config(
+        &mut self,
+        module_content: &str,
+        install_path: &str,
+        bar: Option<&str>,
+        sandbox: Option<&CommandSandbox>,
+    ) -> Result<(), String> {
+        self.managed.content = merge_module_config(
+            &self.managed.content,
+            module_content,
+            install_path,
+            None,
+            sandbox,
+        )?;
+
+        let module_value = jsonc_parser::parse_to_serde_value(module_content, &Default::default())
+            .map_err(|e| format!("Failed to parse module config: {e}"))?;
+        if let Some(serde_json::Value::Object(definitions)) = module_value {
+            for name in definitions.keys() {
+                self.main.content = remove_module_config(&self.main.content, name, None)?;
+            }
+        }
+
+        self.ensure_managed_include(bar)
+    }
+
+    pub fn remove_module_config(&mut self, module_name: &str) -> Result<(), String> {
+        self.managed.content = remove_module_config(&self.managed.content, module_name, None)?;
+        self.main.content = remove_module_config(&self.main.content, module_name, None)?;
+        Ok(())
+    }
+
+    fn files(&self) -> impl Iterator<Item = &ConfigFile> {
+        std::iter::once(&self.main)
+            .chain(std::iter::once(&self.managed))
+            .chain(&self.includes)
+    }
+
+    fn section_value(&self, bar_index: usize, array_key: &str) -> Option<serde_json::Value> {
+        let (content, index) = match self.section_owner(bar_index, array_key).ok()? {
+            Some(i) => (&self.includes[i].content, 0),
+            None => (&self.main.content, bar_index),
+        };
+        let root = parse_config(content).ok()?;
+        bar_objects(&root)
+            .ok()?
+            .get(index)?
+            .get(array_key)?
+            .value()?
+            .to_serde_value()
+    }
 
-pub fn remove_module_css(css_content: &str, uuid: &str) -> String {
-    let begin_marker = format!("/* BEGIN barforge:{} */", uuid);
-    let end_marker = format!("/* END barforge:{} */", uuid);
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues: Vec<ConfigIssue> = self
+            .files()
+            .filter_map(|file| {
+                parse_config(&file.content)
+                    .err()
+                    .map(|error| ConfigIssue::Unparsable {
+                        path: file.path.clone(),
+                        error,
+                    })
+            })
+            .collect();
+        let Ok(bars) = list_bars(&self.main.content) else {
+            return issues;
+        };
 
-    let Some(begin_pos) = css_content.find(&begin_marker) else {
-        return css_content.to_string();
-    };
+        for bar in &bars {
+            let mut seen = HashSet::new();
+            for section in BarSection::all() {
+                let key = section.array_key();
+                let entries = match self.section_value(bar.index, key) {
+                    None => continue,
+                    Some(serde_json::Value::Array(entries)) => entries,
+                    Some(_) => {
+                        issues.push(ConfigIssue::NotAnArray {
+                            bar: bar.to_string(),
+                            key: key.to_string(),
+                        });
+                        continue;
+                    }
+                };
+                for name in entries.iter().filter_map(|entry| entry.as_str()) {
+                    if !seen.insert(name.to_string()) {
+                        issues.push(ConfigIssue::DuplicateEntry {
+                            bar: bar.to_string(),
+                            name: name.to_string(),
+                        });
+                    } else if needs_definition(name) && self.definition(name).is_none() {
+                        issues.push(ConfigIssue::MissingDefinition {
+                            bar: bar.to_string(),
+                            name: name.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        issues
+    }
 
-    let Some(end_pos) = css_content.find(&end_marker) else {
-        return css_content.to_string();
-    };
+    /// Issues in the edited config that the files on disk did not have.
+    pub fn introduced_issues(&self) -> Vec<ConfigIssue> {
+        let mut original = self.clone();
+        for file in std::iter::once(&mut original.main)
+            .chain(std::iter::once(&mut original.managed))
+            .chain(&mut original.includes)
+        {
+            file.content = file.original.clone().unwrap_or(EMPTY_INCLUDE.to_string());
+        }
+        let existing = original.validate();
+        self.validate()
+            .into_iter()
+            .filter(|issue| !existing.contains(issue))
+            .collect()
+    }
 
-    let before = css_content[..begin_pos].trim_end();
-    let after = css_content[end_pos + end_marker.len()..].trim_start();
+    pub fn changes(&self) -> Vec<FileChange> {
+        self.files()
+            .filter(|file| file.is_changed())
+            .filter_map(|file| {
+                let original = file.original.as_deref().unwrap_or_default();
+                FileChange::between(file.path.clone(), original, &file.content)
+            })
+            .collect()
+    }
 
-    if after.is_empty() {
-        before.to_string()
-    } else {
-        format!("{}\n{}", before, after)
+    /// Puts the files `save` wrote back the way they were when loaded.
+    pub fn revert(&self) -> Result<(), String> {
+        for file in self.files().filter(|file| file.is_changed()) {
+            record_own_write(&file.path);
+            let reverted = match &file.original {
+                Some(original) => std::fs::write(&file.path, original),
+                None => match std::fs::remove_file(&file.path) {
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                    other => other,
+                },
+            };
+            reverted.map_err(|e| format!("Failed to restore {}: {e}", file.path.display()))?;
+        }
+        Ok(())
     }
-}
 
-pub fn remove_module_config(waybar_content: &str, module_name: &str) -> Result<String, String> {
-    let waybar_value: serde_json::Value =
-        jsonc_parser::parse_to_serde_value(waybar_content, &Default::default())
-            .map_err(|e| format!("Failed to parse waybar config: {e}"))?
-            .ok_or("Empty waybar config")?;
+    pub fn has_changes(&self) -> bool {
+        self.files().any(ConfigFile::is_changed)
+    }
 
-    let mut waybar_obj = match waybar_value {
-        serde_json::Value::Object(obj) => obj,
-        _ => return Err("Waybar config is not a JSON object".to_string()),
-    };
+    pub fn save(&self) -> Result<Vec<PathBuf>, String> {
+        if !self.has_changes() {
+            return Ok(Vec::new());
+        }
+        let issues = self.introduced_issues();
+        if !issues.is_empty() {
+            let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+            return Err(format!(
+                "Refusing to write an invalid Waybar config: {}",
+                issues.join("; ")
+            ));
+        }
 
-    waybar_obj.remove(module_name);
+        if let Some((store, style_paths)) = &self.backups {
+            let paths: Vec<PathBuf> = self
+                .files()
+                .map(|file| file.path.clone())
+                .chain(style_paths.iter().cloned())
+                .collect();
+            if let Err(e) = store.snapshot(&paths) {
+                tracing::warn!("Failed to back up waybar files: {e}");
+            }
+        }
 
-    serde_json::to_string_pretty(&serde_json::Value::Object(waybar_obj))
-        .map_err(|e| format!("Failed to serialize config: {e}"))
+        let mut written = Vec::new();
+        for file in self.files().filter(|file| file.is_changed()) {
+            record_own_write(&file.path);
+            std::fs::write(&file.path, &file.content)
+                .map_err(|e| format!("Failed to write {}: {e}", file.path.display()))?;
+            written.push(file.path.clone());
+        }
+        Ok(written)
+    }
 }
 
 pub async fn reload_waybar() -> Result<(), String> {
-    let status = tokio::process::Command::new("pkill")
-        .args(["-x", "-SIGUSR2", "waybar"])
-        .status()
+    WaybarReloader::default()
+        .reload()
         .await
-        .map_err(|e| format!("Failed to send reload signal: {e}"))?;
-
-    if status.success() || status.code() == Some(1) {
-        tracing::info!("Sent reload signal to waybar");
-        Ok(())
-    } else {
-        Err(format!("pkill failed with status: {}", status))
-    }
+        .map(|_| ())
+        .map_err(|e| e.to_string())
 }
 
 #[cfg(test)]
@@ -240,7 +1235,7 @@ mod tests {
 
     #[test]
     fn test_add_module_to_left() {
-        let result = add_module(SAMPLE_CONFIG, "custom/weather", BarSection::Left).unwrap();
+        let result = add_module(SAMPLE_CONFIG, "custom/weather", BarSection::Left, None).unwrap();
         assert!(result.contains("custom/weather"));
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
@@ -250,7 +1245,7 @@ mod tests {
 
     #[test]
     fn test_add_module_to_center() {
-        let result = add_module(SAMPLE_CONFIG, "custom/music", BarSection::Center).unwrap();
+        let result = add_module(SAMPLE_CONFIG, "custom/music", BarSection::Center, None).unwrap();
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         let center = parsed["modules-center"].as_array().unwrap();
@@ -259,7 +1254,7 @@ mod tests {
 
     #[test]
     fn test_add_module_to_right() {
-        let result = add_module(SAMPLE_CONFIG, "custom/cpu", BarSection::Right).unwrap();
+        let result = add_module(SAMPLE_CONFIG, "custom/cpu", BarSection::Right, None).unwrap();
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         let right = parsed["modules-right"].as_array().unwrap();
@@ -268,8 +1263,8 @@ mod tests {
 
     #[test]
     fn test_add_module_idempotent() {
-        let result1 = add_module(SAMPLE_CONFIG, "clock", BarSection::Center).unwrap();
-        let result2 = add_module(&result1, "clock", BarSection::Center).unwrap();
+        let result1 = add_module(SAMPLE_CONFIG, "clock", BarSection::Center, None).unwrap();
+        let result2 = add_module(&result1, "clock", BarSection::Center, None).unwrap();
 
         let parsed: serde_json::Value = serde_json::from_str(&result2).unwrap();
         let center = parsed["modules-center"].as_array().unwrap();
@@ -282,7 +1277,7 @@ mod tests {
 
     #[test]
     fn test_remove_module() {
-        let result = remove_module(SAMPLE_CONFIG, "clock").unwrap();
+        let result = remove_module(SAMPLE_CONFIG, "clock", None).unwrap();
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         let center = parsed["modules-center"].as_array().unwrap();
@@ -291,7 +1286,7 @@ mod tests {
 
     #[test]
     fn test_remove_module_from_any_section() {
-        let result = remove_module(SAMPLE_CONFIG, "battery").unwrap();
+        let result = remove_module(SAMPLE_CONFIG, "battery", None).unwrap();
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         let right = parsed["modules-right"].as_array().unwrap();
@@ -300,7 +1295,7 @@ mod tests {
 
     #[test]
     fn test_remove_nonexistent_module() {
-        let result = remove_module(SAMPLE_CONFIG, "nonexistent").unwrap();
+        let result = remove_module(SAMPLE_CONFIG, "nonexistent", None).unwrap();
         let original: serde_json::Value = serde_json::from_str(SAMPLE_CONFIG).unwrap();
         let new: serde_json::Value = serde_json::from_str(&result).unwrap();
 
@@ -310,10 +1305,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_insert_module_at_index() {
+        let result = insert_module(
+            SAMPLE_CONFIG,
+            "custom/weather",
+            BarSection::Right,
+            None,
+            Some(0),
+        )
+        .unwrap();
+        let parsed = parse_jsonc(&result);
+        assert_eq!(
+            parsed["modules-right"],
+            serde_json::json!(["custom/weather", "battery", "network"])
+        );
+
+        let result =
+            insert_module(SAMPLE_CONFIG, "clock", BarSection::Center, None, Some(99)).unwrap();
+        let parsed = parse_jsonc(&result);
+        assert_eq!(parsed["modules-center"], serde_json::json!(["clock"]));
+    }
+
+    #[test]
+    fn test_insert_module_moves_existing_entry() {
+        let result =
+            insert_module(SAMPLE_CONFIG, "network", BarSection::Right, None, Some(0)).unwrap();
+        let parsed = parse_jsonc(&result);
+        assert_eq!(
+            parsed["modules-right"],
+            serde_json::json!(["network", "battery"])
+        );
+
+        let result = insert_module(&result, "network", BarSection::Right, None, Some(5)).unwrap();
+        let parsed = parse_jsonc(&result);
+        assert_eq!(
+            parsed["modules-right"],
+            serde_json::json!(["battery", "network"])
+        );
+    }
+
+    #[test]
+    fn test_locate_module_reports_section_and_index() {
+        assert_eq!(
+            locate_module(SAMPLE_CONFIG, "network", None).unwrap(),
+            Some((BarSection::Right, 1))
+        );
+        assert_eq!(
+            locate_module(SAMPLE_CONFIG, "custom/none", None).unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn test_add_module_creates_missing_array() {
         let config = r#"{"layer": "top"}"#;
-        let result = add_module(config, "custom/test", BarSection::Left).unwrap();
+        let result = add_module(config, "custom/test", BarSection::Left, None).unwrap();
 
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
         assert!(parsed["modules-left"].is_array());
@@ -324,7 +1371,7 @@ mod tests {
         let waybar = r#"{"layer": "top", "modules-center": ["clock"]}"#;
         let module = r#"{"custom/weather": {"exec": "curl wttr.in", "interval": 600}}"#;
 
-        let result = merge_module_config(waybar, module, "/path/to/module").unwrap();
+        let result = merge_module_config(waybar, module, "/path/to/module", None, None).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
 
         assert!(parsed["custom/weather"].is_object());
@@ -340,6 +1387,8 @@ mod tests {
             waybar,
             module,
             "/home/user/.local/share/barforge/modules/test@ns",
+            None,
+            None,
         )
         .unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
@@ -350,11 +1399,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_merge_module_config_sandboxes_commands() {
+        let sandbox = CommandSandbox {
+            program: "/usr/bin/barforge".to_string(),
+            uuid: "weather@test".to_string(),
+        };
+        let module = r#"{"custom/weather": {
+            "exec": "$MODULE_PATH/run.sh --city 'New York'",
+            "on-click": "xdg-open https://wttr.in",
+            "on-scroll-up": "",
+            "format": "{}",
+            "interval": 600
+        }}"#;
+
+        let result = merge_module_config("{}", module, "/m", None, Some(&sandbox)).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let weather = &parsed["custom/weather"];
+
+        assert_eq!(
+            weather["exec"],
+            r#"/usr/bin/barforge run-sandboxed weather@test -- sh -c '/m/run.sh --city '\''New York'\'''"#
+        );
+        assert_eq!(
+            weather["on-click"],
+            "/usr/bin/barforge run-sandboxed weather@test -- sh -c 'xdg-open https://wttr.in'"
+        );
+        assert_eq!(weather["on-scroll-up"], "");
+        assert_eq!(weather["format"], "{}");
+
+        let again = weather["exec"].as_str().unwrap();
+        assert_eq!(sandbox.wrap(again), again);
+    }
+
+    #[test]
+    fn test_merge_module_config_escapes_strings() {
+        let module = r#"{"custom/re\\x": {
+            "exec": "grep -E '\\d+' C:\\temp\\log",
+            "tooltip-format": "{title}\n{artist}\t\u0007",
+            "format": "say \"hi\""
+        }}"#;
+
+        let result = merge_module_config("{}", module, "/m", None, None).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let module = &parsed["custom/re\\x"];
+
+        assert_eq!(module["exec"], r"grep -E '\d+' C:\temp\log");
+        assert_eq!(module["tooltip-format"], "{title}\n{artist}\t\u{7}");
+        assert_eq!(module["format"], "say \"hi\"");
+    }
+
+    #[test]
+    fn test_add_module_escapes_names() {
+        let result = add_module(
+            r#"{"modules-right": []}"#,
+            r"custom/a\b",
+            BarSection::Right,
+            None,
+        )
+        .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(parsed["modules-right"], serde_json::json!([r"custom/a\b"]));
+    }
+
     #[test]
     fn test_remove_module_config_strips_definition() {
         let waybar = r#"{"layer": "top", "custom/weather": {"exec": "curl"}, "clock": {}}"#;
 
-        let result = remove_module_config(waybar, "custom/weather").unwrap();
+        let result = remove_module_config(waybar, "custom/weather", None).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
 
         assert!(parsed.get("custom/weather").is_none());
@@ -362,40 +1475,638 @@ mod tests {
         assert!(parsed.get("layer").is_some());
     }
 
+    const COMMENTED_CONFIG: &str = r#"{
+    // Top bar
+    "layer": "top",
+    "modules-left": [
+        "sway/workspaces", // keep first
+    ],
+    "modules-right": ["battery", "network"],
+    /* clock settings */
+    "clock": {"format": "{:%H:%M}"},
+}"#;
+
+    fn parse_jsonc(content: &str) -> serde_json::Value {
+        jsonc_parser::parse_to_serde_value(content, &Default::default())
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_add_module_preserves_comments() {
+        let result =
+            add_module(COMMENTED_CONFIG, "custom/weather", BarSection::Left, None).unwrap();
+
+        assert!(result.contains("// Top bar"));
+        assert!(result.contains("// keep first"));
+        assert!(result.contains("/* clock settings */"));
+        assert!(result.contains(r#""modules-right": ["battery", "network"]"#));
+
+        let parsed = parse_jsonc(&result);
+        assert_eq!(
+            parsed["modules-left"],
+            serde_json::json!(["sway/workspaces", "custom/weather"])
+        );
+    }
+
+    #[test]
+    fn test_remove_module_preserves_comments_and_key_order() {
+        let result = remove_module(COMMENTED_CONFIG, "battery", None).unwrap();
+
+        assert!(result.contains("// Top bar"));
+        assert!(result.contains("/* clock settings */"));
+        let layer = result.find("\"layer\"").unwrap();
+        let clock = result.find("\"clock\"").unwrap();
+        assert!(layer < clock);
+
+        let parsed = parse_jsonc(&result);
+        assert_eq!(parsed["modules-right"], serde_json::json!(["network"]));
+    }
+
+    #[test]
+    fn test_add_module_rejects_non_array_section() {
+        let config = r#"{"modules-left": "clock"}"#;
+        let result = add_module(config, "custom/test", BarSection::Left, None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_merge_module_config_preserves_comments() {
+        let module = r#"{"custom/weather": {"exec": "curl wttr.in", "interval": 600}}"#;
+
+        let result =
+            merge_module_config(COMMENTED_CONFIG, module, "/path/to/module", None, None).unwrap();
+
+        assert!(result.contains("// Top bar"));
+        assert!(result.contains("/* clock settings */"));
+        let parsed = parse_jsonc(&result);
+        assert_eq!(parsed["custom/weather"]["interval"], 600);
+    }
+
+    #[test]
+    fn test_merge_module_config_replaces_existing_definition() {
+        let module = r#"{"clock": {"format": "{:%H:%M:%S}"}}"#;
+
+        let result =
+            merge_module_config(COMMENTED_CONFIG, module, "/path/to/module", None, None).unwrap();
+
+        assert!(result.contains("/* clock settings */"));
+        let parsed = parse_jsonc(&result);
+        assert_eq!(parsed["clock"]["format"], "{:%H:%M:%S}");
+        assert_eq!(result.matches("\"clock\"").count(), 1);
+    }
+
+    #[test]
+    fn test_remove_module_config_preserves_comments() {
+        let result = remove_module_config(COMMENTED_CONFIG, "clock", None).unwrap();
+
+        assert!(result.contains("// Top bar"));
+        assert!(result.contains("// keep first"));
+        let parsed = parse_jsonc(&result);
+        assert!(parsed.get("clock").is_none());
+        assert_eq!(parsed["layer"], "top");
+    }
+
+    const MULTI_BAR_CONFIG: &str = r#"[
+    {
+        "name": "main",
+        "output": "DP-1",
+        "modules-right": ["clock"],
+    },
+    {
+        // secondary monitors
+        "output": ["HDMI-A-1", "HDMI-A-2"],
+        "modules-right": ["clock", "battery"],
+        "battery": {},
+    },
+]"#;
+
+    #[test]
+    fn test_list_bars_single_object() {
+        let bars = list_bars(SAMPLE_CONFIG).unwrap();
+
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].id(), "#1");
+        assert!(bars[0].name.is_none());
+    }
+
+    #[test]
+    fn test_list_bars_array_reports_name_and_output() {
+        let bars = list_bars(MULTI_BAR_CONFIG).unwrap();
+
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].name.as_deref(), Some("main"));
+        assert_eq!(bars[0].id(), "main");
+        assert_eq!(bars[1].outputs, vec!["HDMI-A-1", "HDMI-A-2"]);
+        assert_eq!(bars[1].id(), "HDMI-A-1");
+        assert_eq!(bars[1].to_string(), "Bar 2 (HDMI-A-1, HDMI-A-2)");
+    }
+
+    #[test]
+    fn test_add_module_defaults_to_first_bar() {
+        let result =
+            add_module(MULTI_BAR_CONFIG, "custom/weather", BarSection::Left, None).unwrap();
+
+        let parsed = parse_jsonc(&result);
+        assert_eq!(
+            parsed[0]["modules-left"],
+            serde_json::json!(["custom/weather"])
+        );
+        assert!(parsed[1].get("modules-left").is_none());
+        assert!(result.contains("// secondary monitors"));
+    }
+
+    #[test]
+    fn test_add_module_to_named_bar() {
+        let result = add_module(
+            MULTI_BAR_CONFIG,
+            "custom/weather",
+            BarSection::Right,
+            Some("HDMI-A-2"),
+        )
+        .unwrap();
+
+        let parsed = parse_jsonc(&result);
+        assert_eq!(parsed[0]["modules-right"], serde_json::json!(["clock"]));
+        assert_eq!(
+            parsed[1]["modules-right"],
+            serde_json::json!(["clock", "battery", "custom/weather"])
+        );
+    }
+
+    #[test]
+    fn test_add_module_unknown_bar_fails() {
+        let result = add_module(MULTI_BAR_CONFIG, "clock", BarSection::Left, Some("DP-9"));
+        assert!(result.unwrap_err().contains("DP-9"));
+    }
+
+    #[test]
+    fn test_remove_module_from_every_bar() {
+        let result = remove_module(MULTI_BAR_CONFIG, "clock", None).unwrap();
+
+        let parsed = parse_jsonc(&result);
+        assert_eq!(parsed[0]["modules-right"], serde_json::json!([]));
+        assert_eq!(parsed[1]["modules-right"], serde_json::json!(["battery"]));
+    }
+
+    #[test]
+    fn test_remove_module_from_selected_bar() {
+        let result = remove_module(MULTI_BAR_CONFIG, "clock", Some("main")).unwrap();
+
+        let parsed = parse_jsonc(&result);
+        assert_eq!(parsed[0]["modules-right"], serde_json::json!([]));
+        assert_eq!(
+            parsed[1]["modules-right"],
+            serde_json::json!(["clock", "battery"])
+        );
+    }
+
+    #[test]
+    fn test_merge_and_remove_module_config_on_selected_bar() {
+        let module = r#"{"custom/weather": {"interval": 600}}"#;
+
+        let merged =
+            merge_module_config(MULTI_BAR_CONFIG, module, "/path", Some("#2"), None).unwrap();
+        let parsed = parse_jsonc(&merged);
+        assert!(parsed[0].get("custom/weather").is_none());
+        assert_eq!(parsed[1]["custom/weather"]["interval"], 600);
+
+        let removed = remove_module_config(&merged, "custom/weather", None).unwrap();
+        let parsed = parse_jsonc(&removed);
+        assert!(parsed[1].get("custom/weather").is_none());
+        assert!(parsed[1].get("battery").is_some());
+    }
+
+    #[test]
+    fn test_add_include_extends_string_and_array() {
+        let result = add_include(r#"{"include": "~/a.jsonc"}"#, "/b.jsonc", None).unwrap();
+        assert_eq!(
+            parse_jsonc(&result)["include"],
+            serde_json::json!(["~/a.jsonc", "/b.jsonc"])
+        );
+
+        let again = add_include(&result, "/b.jsonc", None).unwrap();
+        assert_eq!(parse_jsonc(&again)["include"].as_array().unwrap().len(), 2);
+
+        let created = add_include(SAMPLE_CONFIG, "/b.jsonc", None).unwrap();
+        assert_eq!(
+            parse_jsonc(&created)["include"],
+            serde_json::json!(["/b.jsonc"])
+        );
+    }
+
+    #[test]
+    fn test_resolve_include_relative_to_config_dir() {
+        let base = Path::new("/home/user/.config/waybar");
+        assert_eq!(
+            resolve_include("modules.jsonc", base),
+            base.join("modules.jsonc")
+        );
+        assert_eq!(
+            resolve_include("/etc/waybar/extra", base),
+            PathBuf::from("/etc/waybar/extra")
+        );
+        assert!(!resolve_include("~/extra.jsonc", base).starts_with("~"));
+    }
+
+    fn write_waybar_dir(main: &str, includes: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("config"), main).unwrap();
+        for (name, content) in includes {
+            std::fs::write(dir.path().join(name), content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_files_add_module_edits_include_that_owns_section() {
+        let dir = write_waybar_dir(
+            r#"{"layer": "top", "include": ["modules.jsonc"]}"#,
+            &[(
+                "modules.jsonc",
+                r#"{"modules-right": ["clock"], "custom/weather": {}}"#,
+            )],
+        );
+        let mut files = WaybarFiles::open(&dir.path().join("config")).unwrap();
+
+        files
+            .add_module("custom/weather", &ModulePosition::new(BarSection::Right))
+            .unwrap();
+        files.save().unwrap();
+
+        let main = std::fs::read_to_string(dir.path().join("config")).unwrap();
+        assert!(!main.contains("custom/weather"));
+        let include = std::fs::read_to_string(dir.path().join("modules.jsonc")).unwrap();
+        assert_eq!(
+            parse_jsonc(&include)["modules-right"],
+            serde_json::json!(["clock", "custom/weather"])
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_broken_sections() {
+        let dir = write_waybar_dir(
+            r#"{
+    "modules-left": "clock",
+    "modules-right": ["battery", "custom/weather", "battery"]
+}"#,
+            &[],
+        );
+        let files = WaybarFiles::open(&dir.path().join("config")).unwrap();
+
+        let issues = files.validate();
+        let bar = "Bar 1".to_string();
+        assert_eq!(
+            issues,
+            vec![
+                ConfigIssue::NotAnArray {
+                    bar: bar.clone(),
+                    key: "modules-left".to_string(),
+                },
+                ConfigIssue::MissingDefinition {
+                    bar: bar.clone(),
+                    name: "custom/weather".to_string(),
+                },
+                ConfigIssue::DuplicateEntry {
+                    bar,
+                    name: "battery".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_finds_definitions_in_includes() {
+        let dir = write_waybar_dir(
+            r#"{"modules-right": ["custom/weather"], "include": ["modules.jsonc"]}"#,
+            &[("modules.jsonc", r#"{"custom/weather": {"exec": "wttr"}}"#)],
+        );
+        let files = WaybarFiles::open(&dir.path().join("config")).unwrap();
+
+        assert!(files.validate().is_empty());
+    }
+
+    #[test]
+    fn test_save_refuses_new_issues_but_keeps_existing_ones() {
+        let dir = write_waybar_dir(r#"{"modules-right": ["clock", "clock"]}"#, &[]);
+        let config = dir.path().join("config");
+        let mut files = WaybarFiles::open(&config).unwrap();
+
+        files
+            .add_module("battery", &ModulePosition::new(BarSection::Left))
+            .unwrap();
+        assert!(files.introduced_issues().is_empty());
+        files.save().unwrap();
+
+        let mut files = WaybarFiles::open(&config).unwrap();
+        files
+            .add_module("custom/weather", &ModulePosition::new(BarSection::Left))
+            .unwrap();
+        let err = files.save().unwrap_err();
+        assert!(err.contains("custom/weather is listed but not defined"));
+        assert!(
+            !std::fs::read_to_string(&config)
+                .unwrap()
+                .contains("weather")
+        );
+    }
+
+    #[test]
+    fn test_files_changes_diff_each_edited_file() {
+        let dir = write_waybar_dir(r#"{"modules-right": ["clock"]}"#, &[]);
+        let mut files = WaybarFiles::open(&dir.path().join("config")).unwrap();
+        assert!(files.changes().is_empty());
+
+        files
+            .merge_module_config(r#"{"custom/weather": {"exec": "wttr"}}"#, "/m", None, None)
+            .unwrap();
+        files
+            .add_module("custom/weather", &ModulePosition::new(BarSection::Right))
+            .unwrap();
+
+        let changes = files.changes();
+        assert_eq!(changes.len(), 2);
+        assert!(changes[0].hunks.contains("+"));
+        assert_eq!(changes[1].path, files.managed_path());
+    }
+
+    #[test]
+    fn test_files_add_module_honors_order_and_locates_it() {
+        let dir = write_waybar_dir(
+            r#"{"modules-left": ["workspaces"], "include": ["modules.jsonc"]}"#,
+            &[(
+                "modules.jsonc",
+                r#"{"modules-right": ["clock", "battery"]}"#,
+            )],
+        );
+        let mut files = WaybarFiles::open(&dir.path().join("config")).unwrap();
+
+        files
+            .add_module(
+                "custom/weather",
+                &ModulePosition::new(BarSection::Right).at(Some(1)),
+            )
+            .unwrap();
+
+        assert_eq!(
+            files.locate_module("custom/weather", None).unwrap(),
+            Some((BarSection::Right, 1))
+        );
+        assert_eq!(
+            files.locate_module("workspaces", None).unwrap(),
+            Some((BarSection::Left, 0))
+        );
+        assert_eq!(
+            files.locate_module("battery", None).unwrap(),
+            Some((BarSection::Right, 2))
+        );
+    }
+
+    #[test]
+    fn test_take_and_put_entry_move_builtin_modules() {
+        let (content, entry) = take_entry(SAMPLE_CONFIG, BarSection::Right, None, 0).unwrap();
+        assert_eq!(entry, "battery");
+        let content = put_entry(&content, &entry, BarSection::Left, None, 0).unwrap();
+
+        let parsed = parse_jsonc(&content);
+        assert_eq!(
+            parsed["modules-left"],
+            serde_json::json!(["battery", "sway/workspaces"])
+        );
+        assert_eq!(parsed["modules-right"], serde_json::json!(["network"]));
+        assert!(take_entry(SAMPLE_CONFIG, BarSection::Right, None, 5).is_err());
+    }
+
+    #[test]
+    fn test_files_layout_and_move_entry_across_includes() {
+        let dir = write_waybar_dir(
+            r#"{"modules-left": ["workspaces"], "include": ["modules.jsonc"]}"#,
+            &[(
+                "modules.jsonc",
+                r#"{"modules-right": ["clock", "battery"]}"#,
+            )],
+        );
+        let mut files = WaybarFiles::open(&dir.path().join("config")).unwrap();
+
+        let lanes = files.layout(None).unwrap();
+        assert_eq!(lanes.len(), 3);
+        assert_eq!(lanes[0].entries, vec!["workspaces"]);
+        assert!(lanes[1].entries.is_empty());
+        assert_eq!(lanes[2].entries, vec!["clock", "battery"]);
+
+        let moved = files
+            .move_entry(None, (BarSection::Right, 1), (BarSection::Left, 0))
+            .unwrap();
+        assert_eq!(moved, "battery");
+        files.save().unwrap();
+
+        let main = parse_jsonc(&std::fs::read_to_string(dir.path().join("config")).unwrap());
+        assert_eq!(
+            main["modules-left"],
+            serde_json::json!(["battery", "workspaces"])
+        );
+        let include =
+            parse_jsonc(&std::fs::read_to_string(dir.path().join("modules.jsonc")).unwrap());
+        assert_eq!(include["modules-right"], serde_json::json!(["clock"]));
+    }
+
     #[test]
-    fn test_inject_module_css_appends_with_markers() {
-        let existing = "* { font-family: monospace; }";
-        let module_css = ".custom-weather { color: #fff; }";
+    fn test_files_list_external_modules() {
+        let dir = write_waybar_dir(
+            r#"{
+    "modules-left": ["custom/foo", "custom/weather"],
+    "modules-right": ["clock", "clock"],
+    "custom/foo": {"exec": "~/bin/foo"}
+}"#,
+            &[],
+        );
+        let files = WaybarFiles::open(&dir.path().join("config")).unwrap();
+
+        let external = files.external_modules(&["custom/weather"]).unwrap();
+        let names: Vec<&str> = external.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["custom/foo", "clock"]);
+        assert!(external[0].can_adopt());
+        assert_eq!(external[1].position.section, BarSection::Right);
+        assert!(!external[1].has_definition);
+    }
+
+    #[test]
+    fn test_files_remove_external_entry_and_definition() {
+        let dir = write_waybar_dir(
+            r#"[
+    {"name": "main", "modules-left": ["custom/foo"], "custom/foo": {"exec": "foo"}},
+    {"name": "side", "modules-left": ["custom/foo"]}
+]"#,
+            &[],
+        );
+        let mut files = WaybarFiles::open(&dir.path().join("config")).unwrap();
+
+        files
+            .remove_module_from_bar("custom/foo", Some("side"))
+            .unwrap();
+        assert!(files.is_placed("custom/foo").unwrap());
+        assert_eq!(
+            files.definition("custom/foo"),
+            Some(serde_json::json!({"exec": "foo"}))
+        );
+
+        files.remove_module_from_bar("custom/foo", None).unwrap();
+        assert!(!files.is_placed("custom/foo").unwrap());
+        files.remove_definition("custom/foo").unwrap();
+        assert!(files.definition("custom/foo").is_none());
+    }
+
+    #[test]
+    fn test_files_merge_writes_definitions_to_managed_include() {
+        let dir = write_waybar_dir(
+            r#"{
+    // legacy definition written by older versions
+    "custom/weather": {"exec": "old"},
+    "modules-right": ["clock"]
+}"#,
+            &[],
+        );
+        let mut files = WaybarFiles::open(&dir.path().join("config")).unwrap();
+        let managed_path = dir.path().join(paths::WAYBAR_MANAGED_INCLUDE);
+        assert_eq!(files.managed_path(), managed_path);
+
+        files
+            .merge_module_config(
+                r#"{"custom/weather": {"exec": "$MODULE_PATH/run.sh"}}"#,
+                "/modules/weather@test",
+                None,
+                None,
+            )
+            .unwrap();
+        files
+            .add_module("custom/weather", &ModulePosition::new(BarSection::Right))
+            .unwrap();
+        let written = files.save().unwrap();
+        assert_eq!(written.len(), 2);
+
+        let main = parse_jsonc(&std::fs::read_to_string(dir.path().join("config")).unwrap());
+        assert!(main.get("custom/weather").is_none());
+        assert_eq!(
+            main["include"],
+            serde_json::json!([managed_path.to_string_lossy()])
+        );
+        assert_eq!(
+            main["modules-right"],
+            serde_json::json!(["clock", "custom/weather"])
+        );
+
+        let managed = parse_jsonc(&std::fs::read_to_string(&managed_path).unwrap());
+        assert_eq!(
+            managed["custom/weather"]["exec"],
+            "/modules/weather@test/run.sh"
+        );
+    }
+
+    #[test]
+    fn test_files_remove_module_everywhere() {
+        let dir = write_waybar_dir(
+            r#"{"modules-left": ["custom/weather"], "include": ["modules.jsonc"]}"#,
+            &[
+                ("modules.jsonc", r#"{"modules-right": ["custom/weather"]}"#),
+                (
+                    paths::WAYBAR_MANAGED_INCLUDE,
+                    r#"{"custom/weather": {}, "custom/cpu": {}}"#,
+                ),
+            ],
+        );
+        let mut files = WaybarFiles::open(&dir.path().join("config")).unwrap();
 
-        let result = inject_module_css(existing, "weather@test", module_css);
+        files.remove_module_config("custom/weather").unwrap();
+        files.remove_module("custom/weather").unwrap();
 
-        assert!(result.contains("/* BEGIN barforge:weather@test */"));
-        assert!(result.contains(".custom-weather { color: #fff; }"));
-        assert!(result.contains("/* END barforge:weather@test */"));
-        assert!(result.starts_with("* { font-family: monospace; }"));
+        assert!(!files.config().contains("custom/weather"));
+        assert!(!files.managed().contains("custom/weather"));
+        assert!(files.managed().contains("custom/cpu"));
+        files.save().unwrap();
+        let include = std::fs::read_to_string(dir.path().join("modules.jsonc")).unwrap();
+        assert!(!include.contains("custom/weather"));
     }
 
     #[test]
-    fn test_remove_module_css_strips_marked_section() {
-        let css = r#"* { font-family: monospace; }
-/* BEGIN barforge:weather@test */
-.custom-weather { color: #fff; }
-/* END barforge:weather@test */
-.clock { color: blue; }"#;
+    fn test_save_backs_up_config_and_style_together() {
+        let dir = write_waybar_dir(
+            r#"{"modules-right": ["clock"]}"#,
+            &[("style.css", "#clock {}\n")],
+        );
+        let store = BackupStore::new(dir.path().join("backups"), 3);
+        let mut files = WaybarFiles::open(&dir.path().join("config"))
+            .unwrap()
+            .with_backups(store.clone(), vec![dir.path().join("style.css")]);
+
+        files
+            .add_module("custom/weather", &ModulePosition::new(BarSection::Left))
+            .unwrap();
+        files
+            .merge_module_config(
+                r#"{"custom/weather": {}}"#,
+                "/modules/weather@test",
+                None,
+                None,
+            )
+            .unwrap();
+        files.save().unwrap();
+
+        let backups = store.list().unwrap();
+        assert_eq!(backups.len(), 1);
+        let saved: Vec<_> = backups[0].files.iter().map(|f| f.path.clone()).collect();
+        assert!(saved.contains(&dir.path().join("style.css")));
+        assert!(saved.contains(&dir.path().join(paths::WAYBAR_MANAGED_INCLUDE)));
+
+        store.restore(&backups[0].id).unwrap();
+        assert!(!dir.path().join(paths::WAYBAR_MANAGED_INCLUDE).exists());
+        assert!(
+            !std::fs::read_to_string(dir.path().join("config"))
+                .unwrap()
+                .contains("custom/weather")
+        );
+    }
 
-        let result = remove_module_css(css, "weather@test");
+    #[test]
+    fn test_revert_puts_back_written_files() {
+        let dir = write_waybar_dir(r#"{"modules-right": ["clock"]}"#, &[]);
+        let config_path = dir.path().join("config");
+        let mut files = WaybarFiles::open(&config_path).unwrap();
+        files
+            .add_module("custom/weather", &ModulePosition::new(BarSection::Left))
+            .unwrap();
+        files
+            .merge_module_config(
+                r#"{"custom/weather": {}}"#,
+                "/modules/weather@test",
+                None,
+                None,
+            )
+            .unwrap();
+        files.save().unwrap();
+        assert!(dir.path().join(paths::WAYBAR_MANAGED_INCLUDE).exists());
+
+        files.revert().unwrap();
 
-        assert!(!result.contains("barforge:weather@test"));
-        assert!(!result.contains(".custom-weather"));
-        assert!(result.contains("* { font-family: monospace; }"));
-        assert!(result.contains(".clock { color: blue; }"));
+        assert_eq!(
+            std::fs::read_to_string(&config_path).unwrap(),
+            r#"{"modules-right": ["clock"]}"#
+        );
+        assert!(!dir.path().join(paths::WAYBAR_MANAGED_INCLUDE).exists());
     }
 
     #[test]
-    fn test_remove_module_css_handles_missing_section() {
-        let css = "* { font-family: monospace; }";
-        let result = remove_module_css(css, "nonexistent@test");
-        assert_eq!(result, css);
+    fn test_files_survive_include_cycles() {
+        let dir = write_waybar_dir(
+            r#"{"include": ["a.jsonc"]}"#,
+            &[
+                ("a.jsonc", r#"{"include": ["b.jsonc"]}"#),
+                ("b.jsonc", r#"{"include": ["a.jsonc", "missing.jsonc"]}"#),
+            ],
+        );
+        let files = WaybarFiles::open(&dir.path().join("config")).unwrap();
+
+        assert_eq!(files.include_paths().len(), 2);
+        assert!(files.save().unwrap().is_empty());
     }
 
     #[test]
@@ -0,0 +1,403 @@
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::services::paths;
+
+const WAYBAR_PROGRAM: &str = "waybar";
+const RELOAD_GRACE: Duration = Duration::from_millis(1500);
+const OUTPUT_LINES: usize = 20;
+
+#[derive(Debug, Error)]
+pub enum ReloadError {
+    #[error("Failed to send reload signal: {0}")]
+    Signal(String),
+
+    #[error("Failed to start waybar: {0}")]
+    Start(#[from] io::Error),
+
+    #[error("{}", exited_message(output))]
+    Exited {
+        output: String,
+        /// Arguments the instance was running with, to start it again.
+        args: Vec<OsString>,
+    },
+}
+
+impl ReloadError {
+    /// The arguments to start Waybar again with, if it exited.
+    pub fn exit_args(&self) -> Option<&[OsString]> {
+        match self {
+            Self::Exited { args, .. } => Some(args),
+            _ => None,
+        }
+    }
+}
+
+fn exited_message(output: &str) -> String {
+    if output.is_empty() {
+        "Waybar exited after reloading".to_string()
+    } else {
+        format!("Waybar exited after reloading: {output}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadOutcome {
+    NotRunning,
+    Reloaded,
+    /// Waybar exited on the reload signal but a fresh instance stayed up.
+    Restarted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaybarInstance {
+    pub pid: u32,
+    pub args: Vec<OsString>,
+}
+
+/// Reloads Waybar and checks that it is still running afterwards.
+#[derive(Debug, Clone)]
+pub struct WaybarReloader {
+    program: String,
+    search_path: Option<OsString>,
+    grace: Duration,
+    log_path: PathBuf,
+}
+
+impl Default for WaybarReloader {
+    fn default() -> Self {
+        Self {
+            program: WAYBAR_PROGRAM.to_string(),
+            search_path: None,
+            grace: RELOAD_GRACE,
+            log_path: paths::cache_dir().join("waybar.log"),
+        }
+    }
+}
+
+impl WaybarReloader {
+    /// Looks `waybar` up in `path` instead of `$PATH`, and only treats
+    /// instances of that binary as running.
+    pub fn with_search_path(mut self, path: impl Into<OsString>) -> Self {
+        self.search_path = Some(path.into());
+        self
+    }
+
+    pub fn with_grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    pub fn with_log_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.log_path = path.into();
+        self
+    }
+
+    fn binary(&self) -> io::Result<PathBuf> {
+        let found = match &self.search_path {
+            Some(path) => which::which_in(&self.program, Some(path), "/"),
+            None => which::which(&self.program),
+        };
+        found.map_err(|e| io::Error::new(io::ErrorKind::NotFound, format!("{}: {e}", self.program)))
+    }
+
+    pub fn running(&self) -> Vec<WaybarInstance> {
+        let binary = match &self.search_path {
+            Some(_) => match self.binary() {
+                Ok(binary) => Some(binary),
+                Err(_) => return Vec::new(),
+            },
+            None => None,
+        };
+        let Ok(entries) = fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+        let found: Vec<(WaybarInstance, u32)> = entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+            .filter_map(|pid| self.instance(pid, binary.as_deref()))
+            .collect();
+        // Waybar forks to run module scripts, and the child shows up as
+        // `waybar` until it execs. Only the parent is the real bar.
+        let pids: Vec<u32> = found.iter().map(|(instance, _)| instance.pid).collect();
+        found
+            .into_iter()
+            .filter(|(_, parent)| !pids.contains(parent))
+            .map(|(instance, _)| instance)
+            .collect()
+    }
+
+    fn instance(&self, pid: u32, binary: Option<&Path>) -> Option<(WaybarInstance, u32)> {
+        let comm = fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+        if comm.trim_end() != self.program {
+            return None;
+        }
+        let (state, parent) = process_stat(pid)?;
+        if matches!(state, 'Z' | 'X') {
+            return None;
+        }
+        let cmdline = fs::read(format!("/proc/{pid}/cmdline")).ok()?;
+        let cmdline = cmdline.strip_suffix(&[0]).unwrap_or(&cmdline);
+        let argv: Vec<OsString> = cmdline
+            .split(|byte| *byte == 0)
+            .map(|arg| OsString::from_vec(arg.to_vec()))
+            .collect();
+
+        // Scripts run as `interpreter script args...`, so look for the binary
+        // itself rather than assuming it is argv[0].
+        let position = argv.iter().position(|arg| match binary {
+            Some(binary) => Path::new(arg) == binary,
+            None => Path::new(arg).file_name() == Some(OsStr::new(&self.program)),
+        });
+        let args = match (position, binary) {
+            (Some(position), _) => argv[position + 1..].to_vec(),
+            (None, Some(_)) => return None,
+            (None, None) => Vec::new(),
+        };
+        Some((WaybarInstance { pid, args }, parent))
+    }
+
+    /// Sends SIGUSR2 to every running Waybar and waits to see that one
+    /// survives. If they all exit, a new instance is started with stderr
+    /// captured so the error can be shown.
+    pub async fn reload(&self) -> Result<ReloadOutcome, ReloadError> {
+        let instances = self.running();
+        if instances.is_empty() {
+            return Ok(ReloadOutcome::NotRunning);
+        }
+        for instance in &instances {
+            signal_reload(instance.pid).await?;
+        }
+        tracing::info!("Sent reload signal to waybar");
+
+        tokio::time::sleep(self.grace).await;
+        if instances.iter().any(|instance| is_alive(instance.pid)) {
+            return Ok(ReloadOutcome::Reloaded);
+        }
+
+        tracing::warn!("Waybar exited after the reload signal, starting it again");
+        self.start(&instances[0].args).await?;
+        Ok(ReloadOutcome::Restarted)
+    }
+
+    /// Starts Waybar in the background and checks it is still up after the
+    /// grace period. Its stderr goes to the log file.
+    pub async fn start(&self, args: &[OsString]) -> Result<(), ReloadError> {
+        let binary = self.binary()?;
+        if let Some(parent) = self.log_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let log = fs::File::create(&self.log_path)?;
+
+        let mut child = tokio::process::Command::new(&binary)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(log)
+            .process_group(0)
+            .spawn()?;
+
+        tokio::time::sleep(self.grace).await;
+        if child.try_wait()?.is_some() {
+            let output = fs::read_to_string(&self.log_path).unwrap_or_default();
+            return Err(ReloadError::Exited {
+                output: last_lines(&output, OUTPUT_LINES),
+                args: args.to_vec(),
+            });
+        }
+
+        tokio::spawn(async move {
+            let _ = child.wait().await;
+        });
+        tracing::info!("Started waybar ({})", binary.display());
+        Ok(())
+    }
+}
+
+async fn signal_reload(pid: u32) -> Result<(), ReloadError> {
+    let status = tokio::process::Command::new("kill")
+        .args(["-s", "USR2", &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .map_err(|e| ReloadError::Signal(e.to_string()))?;
+
+    // The process may have exited on its own since it was found.
+    if status.success() || !is_alive(pid) {
+        Ok(())
+    } else {
+        Err(ReloadError::Signal(format!(
+            "kill failed with status: {status}"
+        )))
+    }
+}
+
+fn is_alive(pid: u32) -> bool {
+    process_stat(pid).is_some_and(|(state, _)| !matches!(state, 'Z' | 'X'))
+}
+
+/// Reads the state and parent pid of a process.
+fn process_stat(pid: u32) -> Option<(char, u32)> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // Both follow the parenthesised command name, which may itself contain
+    // spaces or parentheses.
+    let (_, rest) = stat.rsplit_once(')')?;
+    let mut fields = rest.split_whitespace();
+    let state = fields.next()?.chars().next()?;
+    let parent = fields.next()?.parse().ok()?;
+    Some((state, parent))
+}
+
+fn last_lines(output: &str, count: usize) -> String {
+    let lines: Vec<&str> = output.trim_end().lines().collect();
+    lines[lines.len().saturating_sub(count)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    const STUB: &str = r#"#!/bin/sh
+dir=$(dirname "$0")
+if [ -e "$dir/broken" ]; then
+    echo "[error] Error parsing config" >&2
+    exit 1
+fi
+trap 'if [ -e "$dir/crash-on-reload" ]; then exit 1; fi' USR2
+touch "$dir/$$.ready"
+while :; do sleep 0.05; done
+"#;
+
+    struct Stub {
+        dir: TempDir,
+        reloader: WaybarReloader,
+        children: Vec<std::process::Child>,
+    }
+
+    impl Stub {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let binary = dir.path().join(WAYBAR_PROGRAM);
+            fs::write(&binary, STUB).unwrap();
+            fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+            let reloader = WaybarReloader::default()
+                .with_search_path(dir.path())
+                .with_grace(Duration::from_millis(400))
+                .with_log_path(dir.path().join("waybar.log"));
+            Self {
+                dir,
+                reloader,
+                children: Vec::new(),
+            }
+        }
+
+        fn mark(&self, name: &str) {
+            fs::write(self.dir.path().join(name), "").unwrap();
+        }
+
+        fn launch(&mut self) -> u32 {
+            let binary = self.dir.path().join(WAYBAR_PROGRAM);
+            // Another test forking while the stub was open for writing makes
+            // exec fail with ETXTBSY for a moment.
+            let child = (0..50)
+                .find_map(|_| match std::process::Command::new(&binary).spawn() {
+                    Ok(child) => Some(child),
+                    Err(_) => {
+                        std::thread::sleep(Duration::from_millis(20));
+                        None
+                    }
+                })
+                .expect("stub waybar should start");
+            let pid = child.id();
+            self.children.push(child);
+            // Signals sent before the stub installs its trap would kill it.
+            let ready = self.dir.path().join(format!("{pid}.ready"));
+            for _ in 0..100 {
+                if ready.exists() && self.reloader.running().iter().any(|i| i.pid == pid) {
+                    return pid;
+                }
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            panic!("stub waybar was not found running");
+        }
+    }
+
+    impl Drop for Stub {
+        fn drop(&mut self) {
+            for instance in self.reloader.running() {
+                let _ = std::process::Command::new("kill")
+                    .args(["-9", &instance.pid.to_string()])
+                    .status();
+            }
+            for child in &mut self.children {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn reload_without_waybar_running_does_nothing() {
+        let stub = Stub::new();
+        assert_eq!(
+            stub.reloader.reload().await.unwrap(),
+            ReloadOutcome::NotRunning
+        );
+    }
+
+    #[tokio::test]
+    async fn reload_keeps_healthy_waybar_running() {
+        let mut stub = Stub::new();
+        let pid = stub.launch();
+
+        assert_eq!(
+            stub.reloader.reload().await.unwrap(),
+            ReloadOutcome::Reloaded
+        );
+        assert!(is_alive(pid));
+    }
+
+    #[tokio::test]
+    async fn waybar_that_exits_on_reload_is_started_again() {
+        let mut stub = Stub::new();
+        stub.mark("crash-on-reload");
+        let pid = stub.launch();
+
+        assert_eq!(
+            stub.reloader.reload().await.unwrap(),
+            ReloadOutcome::Restarted
+        );
+        assert!(!is_alive(pid));
+        assert_eq!(stub.reloader.running().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn broken_config_reports_waybar_stderr() {
+        let mut stub = Stub::new();
+        stub.mark("crash-on-reload");
+        stub.launch();
+        stub.mark("broken");
+
+        match stub.reloader.reload().await {
+            Err(ReloadError::Exited { output, .. }) => {
+                assert_eq!(output, "[error] Error parsing config");
+            }
+            other => panic!("expected waybar to exit, got {other:?}"),
+        }
+        assert!(stub.reloader.running().is_empty());
+    }
+
+    #[test]
+    fn last_lines_keeps_the_tail() {
+        assert_eq!(last_lines("a\nb\nc\n", 2), "b\nc");
+        assert_eq!(last_lines("", 2), "");
+    }
+}
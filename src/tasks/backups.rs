@@ -0,0 +1,58 @@
+use iced::Task;
+
+use crate::app::Message;
+use crate::services::{Backup, BackupStore, ConfigPreview};
+
+use super::waybar::reload_or_revert;
+
+pub fn load_backups() -> Task<Message> {
+    Task::perform(load_backups_async(), Message::BackupsLoaded)
+}
+
+pub fn diff_backup(id: String) -> Task<Message> {
+    Task::perform(diff_backup_async(id.clone()), move |result| {
+        Message::BackupDiffLoaded(id, result)
+    })
+}
+
+pub fn restore_backup(id: String) -> Task<Message> {
+    Task::perform(restore_backup_async(id.clone()), move |result| {
+        Message::BackupRestored(id, result.map(|_| ()))
+    })
+}
+
+pub fn prune_backups(keep: usize) -> Task<Message> {
+    Task::perform(prune_backups_async(keep), Message::BackupsPruned)
+}
+
+pub(crate) async fn load_backups_async() -> Result<Vec<Backup>, String> {
+    BackupStore::default().list().map_err(|e| e.to_string())
+}
+
+pub(crate) async fn diff_backup_async(id: String) -> Result<ConfigPreview, String> {
+    BackupStore::default().diff(&id).map_err(|e| e.to_string())
+}
+
+/// Restores a backup and reloads Waybar, putting the replaced files back if
+/// Waybar exits on the restored ones. Returns the backup of the replaced
+/// files, if one was taken.
+pub(crate) async fn restore_backup_async(id: String) -> Result<Option<Backup>, String> {
+    let store = BackupStore::default();
+    let previous = store
+        .restore(&id)
+        .map_err(|e| format!("Failed to restore backup {id}: {e}"))?;
+    reload_or_revert(|| match &previous {
+        Some(previous) => store
+            .restore_without_backup(&previous.id)
+            .map_err(|e| e.to_string()),
+        None => Err("no backup of the replaced files was kept".to_string()),
+    })
+    .await?;
+    Ok(previous)
+}
+
+pub(crate) async fn prune_backups_async(keep: usize) -> Result<Vec<String>, String> {
+    BackupStore::default()
+        .prune(keep)
+        .map_err(|e| format!("Failed to prune backups: {e}"))
+}
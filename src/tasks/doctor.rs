@@ -0,0 +1,62 @@
+use iced::Task;
+
+use crate::app::Message;
+use crate::services::module_css::take_module_blocks;
+use crate::services::waybar_config::WaybarFiles;
+use crate::services::{Drift, StateStore, find_drift, module_directories, own_write_guard, paths};
+
+use super::module::{reapply_module, uninstall_module_async};
+use super::waybar::planned_css_removal;
+
+pub fn check_drift() -> Task<Message> {
+    Task::perform(check_drift_async(), Message::DriftChecked)
+}
+
+pub fn fix_drift(drift: Drift) -> Task<Message> {
+    Task::perform(fix_drift_async(drift.clone()), move |result| {
+        Message::DriftFixed(drift, result)
+    })
+}
+
+pub(crate) async fn check_drift_async() -> Result<Vec<Drift>, String> {
+    let installed = StateStore::default().load().map_err(|e| e.to_string())?;
+    let dirs = module_directories(&paths::modules_dir());
+    let files = match WaybarFiles::load() {
+        Ok(files) => Some(files),
+        Err(e) => {
+            tracing::debug!("Skipping Waybar config checks: {e}");
+            None
+        }
+    };
+
+    let style_path = paths::waybar_style_path();
+    let mut styled = Vec::new();
+    for path in [paths::waybar_managed_style_path(&style_path), style_path] {
+        if let Ok(css) = tokio::fs::read_to_string(&path).await {
+            let (_, blocks) = take_module_blocks(&css);
+            styled.extend(blocks.into_iter().map(|block| block.uuid));
+        }
+    }
+
+    Ok(find_drift(&installed, &dirs, files.as_ref(), &styled))
+}
+
+pub(crate) async fn fix_drift_async(drift: Drift) -> Result<(), String> {
+    match &drift {
+        Drift::MissingDirectory { uuid } => uninstall_module_async(uuid.clone())
+            .await
+            .map(|_| ())
+            .map_err(|(_, e)| e),
+        Drift::UntrackedDirectory { path, .. } => {
+            let _own_write = own_write_guard(path);
+            tokio::fs::remove_dir_all(path)
+                .await
+                .map_err(|e| format!("Failed to delete {}: {e}", path.display()))
+        }
+        Drift::NotPlaced { uuid, .. }
+        | Drift::MissingDefinition { uuid, .. }
+        | Drift::MissingStyle { uuid } => reapply_module(uuid, true).await,
+        Drift::StillPlaced { uuid, .. } => reapply_module(uuid, false).await,
+        Drift::LeftoverStyle { uuid } => planned_css_removal(uuid).await.apply().await,
+    }
+}
@@ -0,0 +1,207 @@
+use iced::Task;
+
+use crate::app::Message;
+use crate::domain::{BarSection, ExternalModule, InstalledModule, ModulePosition, ModuleUuid};
+use crate::services::paths;
+use crate::services::waybar_config::WaybarFiles;
+use crate::services::{
+    StateStore, disabled_external_path, load_disabled_external, merge_external, own_write_guard,
+    save_disabled_external,
+};
+
+use super::module::DEFAULT_VERSION;
+use super::waybar::try_save_and_reload;
+
+pub fn load_external_modules() -> Task<Message> {
+    Task::perform(
+        load_external_modules_async(),
+        Message::ExternalModulesLoaded,
+    )
+}
+
+pub fn toggle_external(module: ExternalModule, enabled: bool) -> Task<Message> {
+    Task::perform(
+        toggle_external_async(module, enabled),
+        Message::ExternalChanged,
+    )
+}
+
+pub fn move_external(module: ExternalModule, section: BarSection) -> Task<Message> {
+    Task::perform(
+        move_external_async(module, section),
+        Message::ExternalChanged,
+    )
+}
+
+pub fn remove_external(module: ExternalModule) -> Task<Message> {
+    Task::perform(remove_external_async(module), Message::ExternalChanged)
+}
+
+pub fn adopt_external(module: ExternalModule) -> Task<Message> {
+    Task::perform(adopt_external_async(module), Message::ExternalChanged)
+}
+
+async fn load_external_modules_async() -> Result<Vec<ExternalModule>, String> {
+    let installed = super::load_installed_async().await?;
+    let managed: Vec<&str> = installed
+        .iter()
+        .map(|m| m.waybar_module_name.as_str())
+        .collect();
+
+    let placed = WaybarFiles::load()?.external_modules(&managed)?;
+    let disabled = load_disabled_external(&disabled_external_path());
+    Ok(merge_external(placed, disabled))
+}
+
+fn update_disabled(
+    module: &ExternalModule,
+    disabled: Option<ExternalModule>,
+) -> Result<(), String> {
+    let path = disabled_external_path();
+    let mut modules = load_disabled_external(&path);
+    modules.retain(|m| !m.same_entry(module));
+    modules.extend(disabled);
+    save_disabled_external(&path, &modules)
+        .map_err(|e| format!("Failed to save external modules: {e}"))
+}
+
+async fn toggle_external_async(module: ExternalModule, enabled: bool) -> Result<String, String> {
+    let mut files = WaybarFiles::load()?;
+    let bar = module.position.bar.as_deref();
+
+    if enabled {
+        files.add_module(&module.name, &module.position)?;
+        try_save_and_reload(&files).await?;
+        update_disabled(&module, None)?;
+    } else {
+        let position = files
+            .locate_module(&module.name, bar)?
+            .map(|(section, index)| {
+                ModulePosition::new(section)
+                    .on_bar(module.position.bar.clone())
+                    .at(Some(index as u32))
+            })
+            .unwrap_or_else(|| module.position.clone());
+        files.remove_module_from_bar(&module.name, bar)?;
+        try_save_and_reload(&files).await?;
+        update_disabled(
+            &module,
+            Some(ExternalModule {
+                enabled: false,
+                position,
+                ..module.clone()
+            }),
+        )?;
+    }
+
+    let state = if enabled { "enabled" } else { "disabled" };
+    tracing::info!("External module {} {}", module.name, state);
+    Ok(format!("{} {}", module.name, state))
+}
+
+async fn move_external_async(
+    module: ExternalModule,
+    section: BarSection,
+) -> Result<String, String> {
+    let position = ModulePosition::new(section).on_bar(module.position.bar.clone());
+
+    if module.enabled {
+        let mut files = WaybarFiles::load()?;
+        files.remove_module_from_bar(&module.name, position.bar.as_deref())?;
+        files.add_module(&module.name, &position)?;
+        try_save_and_reload(&files).await?;
+    } else {
+        update_disabled(
+            &module,
+            Some(ExternalModule {
+                position,
+                ..module.clone()
+            }),
+        )?;
+    }
+
+    tracing::info!("Moved external module {} to {}", module.name, section);
+    Ok(format!("Moved {} to {}", module.name, section))
+}
+
+async fn remove_external_async(module: ExternalModule) -> Result<String, String> {
+    let mut files = WaybarFiles::load()?;
+    files.remove_module_from_bar(&module.name, module.position.bar.as_deref())?;
+    if !files.is_placed(&module.name)? {
+        files.remove_definition(&module.name)?;
+    }
+    try_save_and_reload(&files).await?;
+    update_disabled(&module, None)?;
+
+    tracing::info!("Removed external module {}", module.name);
+    Ok(format!("Removed {}", module.name))
+}
+
+async fn adopt_external_async(module: ExternalModule) -> Result<String, String> {
+    if !module.can_adopt() {
+        return Err(format!("{} has no custom module definition", module.name));
+    }
+
+    let uuid = ModuleUuid::try_from(module.adopted_uuid().as_str())
+        .map_err(|e| format!("Invalid module name {}: {e}", module.name))?;
+    let modules = super::load_installed_async().await?;
+    if modules.iter().any(|m| m.uuid == uuid) {
+        return Err(format!("{uuid} is already installed"));
+    }
+
+    let mut files = WaybarFiles::load()?;
+    let definition = files
+        .definition(&module.name)
+        .ok_or_else(|| format!("No definition for {} in waybar config", module.name))?;
+
+    let install_path = paths::module_install_path(&uuid.to_string());
+    let _own_write = own_write_guard(&install_path);
+    if install_path.exists() {
+        return Err(format!(
+            "Module directory already exists: {}",
+            install_path.display()
+        ));
+    }
+    let module_config = serde_json::to_string_pretty(&serde_json::json!({
+        module.name.clone(): definition
+    }))
+    .map_err(|e| format!("Failed to serialize module config: {e}"))?;
+    tokio::fs::create_dir_all(&install_path)
+        .await
+        .map_err(|e| format!("Failed to create module directory: {e}"))?;
+    tokio::fs::write(install_path.join("config.jsonc"), &module_config)
+        .await
+        .map_err(|e| format!("Failed to write module config: {e}"))?;
+
+    files.remove_definition(&module.name)?;
+    files.merge_module_config(
+        &module_config,
+        &install_path.to_string_lossy(),
+        module.position.bar.as_deref(),
+        None,
+    )?;
+    try_save_and_reload(&files).await?;
+
+    let adopted = InstalledModule {
+        uuid: uuid.clone(),
+        version: DEFAULT_VERSION.clone(),
+        install_path,
+        enabled: module.enabled,
+        waybar_module_name: module.name.clone(),
+        has_preferences: false,
+        installed_at: chrono::Utc::now(),
+        registry_version: None,
+        position: Some(module.position.clone()),
+        previous_versions: Vec::new(),
+    };
+    StateStore::default()
+        .update(|modules| {
+            modules.push(adopted);
+            Ok(())
+        })
+        .map_err(|e| format!("Failed to save state: {e}"))?;
+    update_disabled(&module, None)?;
+
+    tracing::info!("Adopted {} as {}", module.name, uuid);
+    Ok(format!("Adopted {} as {uuid}", module.name))
+}
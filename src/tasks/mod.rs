@@ -1,14 +1,38 @@
+mod backups;
+mod doctor;
+mod external;
 mod module;
+mod preview;
 mod registry;
+mod sync;
 mod watchers;
 mod waybar;
 
+pub use backups::{diff_backup, load_backups, prune_backups, restore_backup};
+pub(crate) use backups::{
+    diff_backup_async, load_backups_async, prune_backups_async, restore_backup_async,
+};
+pub use doctor::{check_drift, fix_drift};
+pub(crate) use doctor::{check_drift_async, fix_drift_async};
+pub use external::{
+    adopt_external, load_external_modules, move_external, remove_external, toggle_external,
+};
 pub use module::{
-    change_module_position, install_module, make_scripts_executable, toggle_module,
-    uninstall_module, update_all_modules, update_module,
+    InstallFailure, UpdateRequest, change_module_position, install_module, make_scripts_executable,
+    move_module, rollback_module, toggle_module, uninstall_module, update_all_modules,
+    update_module,
 };
+pub(crate) use module::{
+    change_module_position_async, install_module_async, rollback_module_async, toggle_module_async,
+    uninstall_module_async, update_all_modules_async, update_module_async,
+};
+pub(crate) use preview::preview_change_async;
+pub use preview::{PendingChange, preview_change};
+pub(crate) use registry::{fetch_registry_async, refresh_registry_async};
 pub use registry::{load_author_profile, load_module_reviews, load_registry, refresh_registry};
-pub use watchers::watch_omarchy_theme;
+pub(crate) use sync::apply_sync_action;
+pub use watchers::{watch_omarchy_theme, watch_waybar_files};
+pub use waybar::{load_bar_layout, load_waybar_bars, move_layout_entry};
 
 use std::time::Duration;
 
@@ -17,30 +41,19 @@ use iced::widget::image;
 
 use crate::app::Message;
 use crate::domain::InstalledModule;
+use crate::services::StateStore;
 use crate::services::paths::{self, HTTP_CLIENT};
 
 pub fn initial_load() -> Task<Message> {
-    Task::batch([load_installed(), load_registry()])
+    Task::batch([load_installed(), load_registry(), load_waybar_bars()])
 }
 
 pub fn load_installed() -> Task<Message> {
     Task::perform(load_installed_async(), Message::InstalledLoaded)
 }
 
-async fn load_installed_async() -> Result<Vec<InstalledModule>, String> {
-    let state_path = paths::data_dir().join("installed.json");
-
-    if !state_path.exists() {
-        tracing::debug!("No installed modules file found");
-        return Ok(Vec::new());
-    }
-
-    let content = tokio::fs::read_to_string(&state_path)
-        .await
-        .map_err(|e| format!("Failed to read installed modules: {e}"))?;
-
-    let modules: Vec<InstalledModule> = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse installed modules: {e}"))?;
+pub(crate) async fn load_installed_async() -> Result<Vec<InstalledModule>, String> {
+    let modules = StateStore::default().load().map_err(|e| e.to_string())?;
 
     tracing::info!("Loaded {} installed modules", modules.len());
     Ok(modules)
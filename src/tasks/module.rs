@@ -7,79 +7,171 @@ use once_cell::sync::Lazy;
 use tar::Archive;
 
 use crate::app::Message;
-use crate::domain::{BarSection, InstalledModule, ModuleVersion};
-use crate::security::{parse_github_url_safe, validate_extraction_path};
+use crate::domain::{InstalledModule, ModuleMove, ModulePosition, ModuleVersion};
+use crate::security::{ScriptResult, parse_github_url_safe, validate_extraction_path};
 use crate::services::paths::{self, HTTP_CLIENT};
-use crate::services::{InstallParams, SecureInstaller};
-
-use super::waybar::{handle_css_injection, handle_css_removal};
-
-static DEFAULT_VERSION: Lazy<ModuleVersion> = Lazy::new(|| {
+use crate::services::waybar_config::WaybarFiles;
+use crate::services::{
+    InstallError, InstallParams, LockedModule, Lockfile, ModuleSwap, PREVIOUS_VERSIONS_KEPT,
+    ScriptApproval, ScriptReview, SecureInstaller, StateStore, UninstallResult, WaybarSnapshot,
+    discard_staging, own_write_guard, prune_versions, record_previous_version, review_scripts,
+    run_uninstall_script, stored_version_path,
+};
+
+use super::waybar::{
+    WaybarEdit, handle_css_removal, plan_module_style, planned_css_injection, planned_css_removal,
+    reload_or_revert, save_and_reload, try_save_and_reload,
+};
+
+pub(super) static DEFAULT_VERSION: Lazy<ModuleVersion> = Lazy::new(|| {
     ModuleVersion::try_from("1.0.0")
         .unwrap_or_else(|_| unreachable!("1.0.0 is always valid semver"))
 });
 
-pub fn toggle_module(uuid: String, enabled: bool) -> Task<Message> {
-    Task::perform(toggle_module_async(uuid, enabled), Message::ToggleCompleted)
+pub fn toggle_module(uuid: String, enabled: bool, scripts: ScriptApproval) -> Task<Message> {
+    Task::perform(toggle_module_async(uuid, enabled, scripts), |result| {
+        Message::ToggleCompleted(result.map_err(|(uuid, e)| (uuid, e.to_string())))
+    })
 }
 
 pub fn uninstall_module(uuid: String) -> Task<Message> {
     Task::perform(uninstall_module_async(uuid), Message::UninstallCompleted)
 }
 
-pub fn change_module_position(uuid: String, section: BarSection) -> Task<Message> {
+pub fn change_module_position(uuid: String, position: ModulePosition) -> Task<Message> {
     Task::perform(
-        change_module_position_async(uuid, section),
+        change_module_position_async(uuid, position),
         Message::PositionChanged,
     )
 }
 
-pub fn update_module(uuid: String, repo_url: String, new_version: ModuleVersion) -> Task<Message> {
+pub fn move_module(uuid: String, to: ModuleMove) -> Task<Message> {
+    Task::perform(move_module_async(uuid, to), Message::PositionChanged)
+}
+
+#[derive(Debug, Clone)]
+pub struct UpdateRequest {
+    pub uuid: String,
+    pub repo_url: String,
+    pub version: ModuleVersion,
+    pub checksum: Option<String>,
+}
+
+pub fn update_module(
+    request: UpdateRequest,
+    allow_unsigned: bool,
+    scripts: ScriptApproval,
+) -> Task<Message> {
     Task::perform(
-        update_module_async(uuid, repo_url, new_version),
+        update_module_async(request, allow_unsigned, scripts),
         Message::UpdateCompleted,
     )
 }
 
-pub fn update_all_modules(updates: Vec<(String, String, ModuleVersion)>) -> Task<Message> {
+pub fn update_all_modules(updates: Vec<UpdateRequest>, allow_unsigned: bool) -> Task<Message> {
     Task::perform(
-        update_all_modules_async(updates),
+        update_all_modules_async(updates, allow_unsigned, ScriptApproval::IfClean),
         Message::UpdateAllCompleted,
     )
 }
 
+pub fn rollback_module(uuid: String, version: Option<ModuleVersion>) -> Task<Message> {
+    Task::perform(
+        async move {
+            rollback_module_async(uuid.clone(), version)
+                .await
+                .map_err(|e| (uuid, e))
+        },
+        Message::RollbackCompleted,
+    )
+}
+
+/// Why an install did not finish.
+#[derive(Debug, Clone)]
+pub enum InstallFailure {
+    /// The package's scripts look risky and the findings were not approved.
+    Unapproved(ScriptReview),
+    Failed(String),
+}
+
+impl From<String> for InstallFailure {
+    fn from(error: String) -> Self {
+        Self::Failed(error)
+    }
+}
+
+impl std::fmt::Display for InstallFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unapproved(review) => write!(
+                f,
+                "{} risky patterns in the module's scripts need approval:\n{review}",
+                review.finding_count()
+            ),
+            Self::Failed(error) => f.write_str(error),
+        }
+    }
+}
+
 pub fn install_module(
     uuid: String,
     name: String,
     version: Option<ModuleVersion>,
     repo_url: String,
     checksum: Option<String>,
+    scripts: ScriptApproval,
 ) -> Task<Message> {
+    let reviewed = uuid.clone();
     Task::perform(
-        install_module_async(uuid, name, version, repo_url, checksum),
-        Message::InstallCompleted,
+        install_module_async(uuid, name, version, repo_url, checksum, scripts, false),
+        move |result| match result {
+            Err(InstallFailure::Unapproved(review)) => Message::ScriptsNeedReview(reviewed, review),
+            result => Message::InstallCompleted(result.map_err(|e| e.to_string())),
+        },
     )
 }
 
-async fn install_module_async(
+pub(crate) async fn install_module_async(
     uuid: String,
     name: String,
     version: Option<ModuleVersion>,
     repo_url: String,
     checksum: Option<String>,
-) -> Result<InstalledModule, String> {
+    scripts: ScriptApproval,
+    update_lock: bool,
+) -> Result<InstalledModule, InstallFailure> {
     let install_path = paths::module_install_path(&uuid);
+    let _own_write = own_write_guard(&install_path);
     let version = version.unwrap_or_else(|| DEFAULT_VERSION.clone());
 
-    if let Some(expected_hash) = checksum {
-        install_secure(&uuid, &version.to_string(), &expected_hash, &install_path).await?;
+    // Installs reproduce barforge.lock; only an explicit update_lock moves
+    // the entry to the requested version.
+    let (version, checksum) = if update_lock {
+        (version, checksum)
     } else {
-        tokio::fs::create_dir_all(&install_path)
-            .await
-            .map_err(|e| format!("Failed to create install directory: {e}"))?;
+        let lockfile = Lockfile::load(&paths::lockfile_path()).map_err(|e| e.to_string())?;
+        let (locked, checksum) = lockfile.pin(&uuid, version.clone(), checksum);
+        if locked != version {
+            tracing::info!("Installing {uuid} {locked} from barforge.lock instead of {version}");
+        }
+        (locked, checksum)
+    };
 
-        download_module_files(&repo_url, &install_path).await?;
-        make_scripts_executable(&install_path).await?;
+    let existed = install_path.exists();
+    if let Err(e) = install_module_files(
+        &uuid,
+        &version,
+        &repo_url,
+        checksum.as_deref(),
+        &install_path,
+        scripts,
+    )
+    .await
+    {
+        if !existed && let Err(cleanup) = discard_staging(&install_path) {
+            tracing::warn!("Failed to remove partial install of {uuid}: {cleanup}");
+        }
+        return Err(e);
     }
 
     let has_preferences = install_path.join("preferences.schema.json").exists();
@@ -96,35 +188,66 @@ async fn install_module_async(
         installed_at: chrono::Utc::now(),
         registry_version: Some(version),
         position: None,
+        previous_versions: Vec::new(),
     };
 
-    let state_path = paths::data_dir().join("installed.json");
-    let mut modules: Vec<InstalledModule> = if state_path.exists() {
-        let content = tokio::fs::read_to_string(&state_path)
-            .await
-            .map_err(|e| format!("Failed to read state: {e}"))?;
-        serde_json::from_str(&content).map_err(|e| format!("Failed to parse state: {e}"))?
-    } else {
-        Vec::new()
-    };
+    StateStore::default()
+        .update(|modules| {
+            modules.push(installed.clone());
+            Ok(())
+        })
+        .map_err(|e| format!("Failed to write state: {e}"))?;
+
+    tracing::info!("Installed module: {}", uuid);
+    Ok(installed)
+}
 
-    modules.push(installed.clone());
+async fn install_module_files(
+    uuid: &str,
+    version: &ModuleVersion,
+    repo_url: &str,
+    checksum: Option<&str>,
+    install_path: &Path,
+    scripts: ScriptApproval,
+) -> Result<(), InstallFailure> {
+    let lock_path = paths::lockfile_path();
+    let mut lockfile = Lockfile::load(&lock_path).map_err(|e| e.to_string())?;
 
-    let content = serde_json::to_string_pretty(&modules)
-        .map_err(|e| format!("Failed to serialize state: {e}"))?;
+    if let Some(expected_hash) = checksum {
+        lockfile
+            .verify(uuid, version, expected_hash)
+            .map_err(|e| e.to_string())?;
+
+        let signature = install_secure(
+            uuid,
+            &version.to_string(),
+            expected_hash,
+            install_path,
+            scripts,
+        )
+        .await?;
+
+        lockfile.upsert(LockedModule {
+            uuid: crate::domain::ModuleUuid::try_from(uuid)
+                .map_err(|e| format!("Invalid UUID: {e}"))?,
+            version: version.clone(),
+            sha256: expected_hash.to_lowercase(),
+            signature,
+        });
+        lockfile
+            .save(&lock_path)
+            .map_err(|e| format!("Failed to update barforge.lock: {e}"))?;
+    } else {
+        lockfile.verify_unsigned(uuid).map_err(|e| e.to_string())?;
 
-    if let Some(parent) = state_path.parent() {
-        tokio::fs::create_dir_all(parent)
+        tokio::fs::create_dir_all(install_path)
             .await
-            .map_err(|e| format!("Failed to create data directory: {e}"))?;
-    }
-
-    tokio::fs::write(&state_path, content)
-        .await
-        .map_err(|e| format!("Failed to write state: {e}"))?;
+            .map_err(|e| format!("Failed to create install directory: {e}"))?;
 
-    tracing::info!("Installed module: {}", uuid);
-    Ok(installed)
+        download_module_files(repo_url, install_path).await?;
+        make_scripts_executable(install_path).await?;
+    }
+    Ok(())
 }
 
 async fn install_secure(
@@ -132,7 +255,8 @@ async fn install_secure(
     version: &str,
     expected_hash: &str,
     dest_dir: &Path,
-) -> Result<(), String> {
+    scripts: ScriptApproval,
+) -> Result<String, InstallFailure> {
     let package_url = paths::package_url(uuid, version);
     let signature_url = paths::signature_url(uuid, version);
 
@@ -164,152 +288,421 @@ async fn install_secure(
         signature: &signature,
         expected_hash,
         dest_dir,
+        scripts,
     };
-    installer
+    match installer
         .install(params, |stage| {
             tracing::debug!("Install stage: {}", stage.description());
         })
         .await
-        .map_err(|e| format!("Secure installation failed: {e}"))?;
+    {
+        Ok(_) => {}
+        Err(InstallError::UnapprovedScripts(review)) => {
+            return Err(InstallFailure::Unapproved(review));
+        }
+        Err(e) => return Err(format!("Secure installation failed: {e}").into()),
+    }
 
-    Ok(())
+    Ok(signature)
 }
 
-async fn update_module_async(
-    uuid: String,
-    repo_url: String,
-    new_version: ModuleVersion,
+pub(crate) async fn update_module_async(
+    request: UpdateRequest,
+    allow_unsigned: bool,
+    scripts: ScriptApproval,
 ) -> Result<InstalledModule, String> {
     use crate::services::waybar_config;
 
+    let UpdateRequest {
+        uuid,
+        repo_url,
+        version: new_version,
+        checksum,
+    } = request;
+
     let install_path = paths::module_install_path(&uuid);
-    let state_path = paths::data_dir().join("installed.json");
+    let _own_write = own_write_guard(&install_path);
+    let module = find_installed(&uuid)?;
+
+    let lock_path = paths::lockfile_path();
+    let mut lockfile = Lockfile::load(&lock_path).map_err(|e| e.to_string())?;
+
+    match &checksum {
+        Some(expected_hash) => lockfile
+            .verify(&uuid, &new_version, expected_hash)
+            .map_err(|e| e.to_string())?,
+        None if allow_unsigned => {
+            lockfile.verify_unsigned(&uuid).map_err(|e| e.to_string())?;
+            tracing::warn!(
+                "No signed package for {} {}, falling back to unsigned GitHub download",
+                uuid,
+                new_version
+            );
+        }
+        None => {
+            return Err(format!(
+                "No signed package is available for {uuid} {new_version}. \
+                 Enable unsigned updates to download it from GitHub instead"
+            ));
+        }
+    }
 
-    let content = tokio::fs::read_to_string(&state_path)
-        .await
-        .map_err(|e| format!("Failed to read state: {e}"))?;
+    let was_enabled = module.enabled;
+    let old_version = module.version.clone();
+
+    let staging_path = paths::module_staging_path(&uuid);
+    let signature = match stage_module_files(
+        &uuid,
+        &new_version,
+        &repo_url,
+        checksum.as_deref(),
+        &staging_path,
+        scripts,
+    )
+    .await
+    {
+        Ok(signature) => signature,
+        Err(e) => {
+            if let Err(cleanup) = discard_staging(&staging_path) {
+                tracing::warn!("Failed to remove staged update for {uuid}: {cleanup}");
+            }
+            return Err(e);
+        }
+    };
 
-    let mut modules: Vec<InstalledModule> =
-        serde_json::from_str(&content).map_err(|e| format!("Failed to parse state: {e}"))?;
+    let waybar_paths = paths::waybar_paths();
+    let swap = ModuleSwap::new(
+        install_path.clone(),
+        staging_path.clone(),
+        paths::module_versions_dir(&uuid).join(old_version.to_string()),
+    );
 
-    let module = modules
-        .iter()
-        .find(|m| m.uuid.to_string() == uuid)
-        .ok_or_else(|| format!("Module not found: {uuid}"))?;
+    let prepared = WaybarSnapshot::capture(&waybar_paths).and_then(|snapshot| {
+        swap.prepare(&snapshot)?;
+        swap.commit()?;
+        Ok(snapshot)
+    });
+    let snapshot = match prepared {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            if let Err(cleanup) = discard_staging(&staging_path) {
+                tracing::warn!("Failed to remove staged update for {uuid}: {cleanup}");
+            }
+            return Err(format!("Failed to swap in new module files: {e}"));
+        }
+    };
 
-    let was_enabled = module.enabled;
-    let waybar_module_name = module.waybar_module_name.clone();
-    let section = module
-        .position
-        .as_ref()
-        .map(|p| p.section)
-        .unwrap_or(BarSection::Center);
+    let mut updated = module.clone();
+    updated.previous_versions = record_previous_version(
+        &updated.previous_versions,
+        &old_version,
+        &new_version,
+        PREVIOUS_VERSIONS_KEPT,
+    );
+    updated.version = new_version.clone();
+    updated.registry_version = Some(new_version.clone());
+    updated.has_preferences = install_path.join("preferences.schema.json").exists();
+
+    if let Err(e) = apply_staged_update(&updated, &snapshot).await {
+        tracing::warn!(
+            "Update of {} failed, restoring {}: {}",
+            uuid,
+            old_version,
+            e
+        );
+        let restored = swap
+            .revert()
+            .and_then(|()| discard_staging(&staging_path))
+            .and_then(|()| snapshot.restore(&waybar_paths));
+        if was_enabled && let Err(reload) = waybar_config::reload_waybar().await {
+            tracing::warn!("Failed to reload waybar: {reload}");
+        }
+        return Err(match restored {
+            Ok(()) => format!("{e}. Restored {uuid} {old_version}"),
+            Err(restore) => format!("{e}. Restoring {uuid} {old_version} also failed: {restore}"),
+        });
+    }
 
     if was_enabled {
-        handle_css_removal(&uuid).await;
-
-        if let Ok(waybar_content) = waybar_config::load_config().await {
-            let without_config =
-                waybar_config::remove_module_config(&waybar_content, &waybar_module_name)
-                    .unwrap_or_else(|e| {
-                        tracing::warn!("Failed to remove module config during update: {e}");
-                        waybar_content.clone()
-                    });
+        reload_or_revert(|| {
+            swap.revert()
+                .and_then(|()| discard_staging(&staging_path))
+                .and_then(|()| snapshot.restore(&waybar_paths))
+                .map_err(|e| e.to_string())?;
+            record_installed_version(&module).map(|_| ())
+        })
+        .await
+        .map_err(|e| format!("{e}\n{uuid} was kept at {old_version}"))?;
+    }
 
-            if let Ok(new_waybar_content) =
-                waybar_config::remove_module(&without_config, &waybar_module_name)
-            {
-                let _ = waybar_config::save_config(&new_waybar_content).await;
-            }
+    if let Some(signature) = signature
+        && let Some(expected_hash) = checksum
+    {
+        lockfile.upsert(LockedModule {
+            uuid: updated.uuid.clone(),
+            version: new_version,
+            sha256: expected_hash.to_lowercase(),
+            signature,
+        });
+        if let Err(e) = lockfile.save(&lock_path) {
+            tracing::warn!("Failed to update barforge.lock: {e}");
         }
     }
 
-    if install_path.exists() {
-        tokio::fs::remove_dir_all(&install_path)
-            .await
-            .map_err(|e| format!("Failed to remove old module files: {e}"))?;
+    let versions_dir = paths::module_versions_dir(&uuid);
+    if let Err(e) = prune_versions(&versions_dir, &updated.previous_versions) {
+        tracing::warn!("Failed to prune old versions of {uuid}: {e}");
     }
 
-    tokio::fs::create_dir_all(&install_path)
+    tracing::info!("Updated module: {}", uuid);
+    Ok(updated)
+}
+
+pub(super) async fn stage_module_files(
+    uuid: &str,
+    version: &ModuleVersion,
+    repo_url: &str,
+    checksum: Option<&str>,
+    staging_path: &Path,
+    scripts: ScriptApproval,
+) -> Result<Option<String>, String> {
+    discard_staging(staging_path).map_err(|e| format!("Failed to clear staging directory: {e}"))?;
+
+    match checksum {
+        Some(expected_hash) => install_secure(
+            uuid,
+            &version.to_string(),
+            expected_hash,
+            staging_path,
+            scripts,
+        )
         .await
-        .map_err(|e| format!("Failed to create install directory: {e}"))?;
+        .map(Some)
+        .map_err(|e| e.to_string()),
+        None => {
+            tokio::fs::create_dir_all(staging_path)
+                .await
+                .map_err(|e| format!("Failed to create staging directory: {e}"))?;
+            download_module_files(repo_url, staging_path).await?;
+            prepare_unsigned_files(staging_path, scripts).await?;
+            Ok(None)
+        }
+    }
+}
 
-    download_module_files(&repo_url, &install_path).await?;
+/// Unsigned downloads skip the installer, so their scripts are reviewed
+/// here before the update can swap them in.
+async fn prepare_unsigned_files(
+    staging_path: &Path,
+    scripts: ScriptApproval,
+) -> Result<(), String> {
+    make_scripts_executable(staging_path).await?;
+    check_scripts(staging_path, scripts).map_err(|e| e.to_string())
+}
 
-    let has_preferences = install_path.join("preferences.schema.json").exists();
+async fn apply_staged_update(
+    module: &InstalledModule,
+    snapshot: &WaybarSnapshot,
+) -> Result<(), String> {
+    if module.enabled {
+        plan_staged_update(module, &module.install_path, snapshot)
+            .await?
+            .write()
+            .await?;
+    }
 
-    let module = modules
-        .iter_mut()
+    record_installed_version(module).map(|_| ())
+}
+
+/// Plans the config and style of `module` using the files in `source`, which
+/// is the install path once swapped in or a staging directory for a preview.
+pub(super) async fn plan_staged_update(
+    module: &InstalledModule,
+    source: &Path,
+    snapshot: &WaybarSnapshot,
+) -> Result<WaybarEdit, String> {
+    use crate::services::waybar_config;
+
+    let uuid = module.uuid.to_string();
+
+    let files = if snapshot.config.is_some() {
+        let name = &module.waybar_module_name;
+        let position = module.position.clone().unwrap_or_default();
+        let bar = position.bar.as_deref();
+
+        let mut files = WaybarFiles::load()?;
+        files.remove_module_config(name)?;
+        files.remove_module(name)?;
+
+        let module_config_path = source.join("config.jsonc");
+        if module_config_path.exists() {
+            let module_config = tokio::fs::read_to_string(&module_config_path)
+                .await
+                .map_err(|e| format!("Failed to read module config: {e}"))?;
+            let prefs = crate::services::preferences::load_preferences(&uuid);
+            let module_config = waybar_config::substitute_preferences(&module_config, &prefs);
+            let sandbox = waybar_config::CommandSandbox::from_settings(&uuid);
+            files
+                .merge_module_config(
+                    &module_config,
+                    &module.install_path.to_string_lossy(),
+                    bar,
+                    sandbox.as_ref(),
+                )
+                .map_err(|e| format!("Failed to merge module config: {e}"))?;
+        }
+        files.add_module(name, &position)?;
+        Some(files)
+    } else {
+        None
+    };
+
+    let module_css_path = source.join("style.css");
+    let module_css = if module_css_path.exists() {
+        Some(
+            tokio::fs::read_to_string(&module_css_path)
+                .await
+                .map_err(|e| format!("Failed to read module CSS: {e}"))?,
+        )
+    } else {
+        None
+    };
+    let style = plan_module_style(
+        &uuid,
+        module_css
+            .as_deref()
+            .map(|css| (module.waybar_module_name.as_str(), css)),
+        snapshot.style.clone(),
+        snapshot.managed_style.clone(),
+    );
+
+    Ok(WaybarEdit { files, ..style })
+}
+
+pub(super) fn find_installed(uuid: &str) -> Result<InstalledModule, String> {
+    StateStore::default()
+        .load()
+        .map_err(|e| e.to_string())?
+        .into_iter()
         .find(|m| m.uuid.to_string() == uuid)
-        .ok_or_else(|| format!("Module not found: {uuid}"))?;
+        .ok_or_else(|| format!("Module not found: {uuid}"))
+}
 
-    module.version = new_version.clone();
-    module.registry_version = Some(new_version);
-    module.has_preferences = has_preferences;
+fn record_installed_version(module: &InstalledModule) -> Result<InstalledModule, String> {
+    StateStore::default()
+        .update_module(&module.uuid.to_string(), |m| {
+            m.version = module.version.clone();
+            m.registry_version = module.registry_version.clone();
+            m.previous_versions = module.previous_versions.clone();
+            m.has_preferences = module.has_preferences;
+        })
+        .map_err(|e| format!("Failed to save state: {e}"))
+}
 
-    let updated = module.clone();
+pub(crate) async fn rollback_module_async(
+    uuid: String,
+    version: Option<ModuleVersion>,
+) -> Result<InstalledModule, String> {
+    let mut module = find_installed(&uuid)?;
+
+    let target = match version {
+        Some(version) => version,
+        None => module
+            .previous_versions
+            .first()
+            .cloned()
+            .ok_or_else(|| format!("No previous version of {uuid} is kept"))?,
+    };
+    if !module.previous_versions.contains(&target) {
+        return Err(format!(
+            "{uuid} {target} is not kept, cannot roll back to it"
+        ));
+    }
 
-    let new_content =
-        serde_json::to_string_pretty(&modules).map_err(|e| format!("Failed to serialize: {e}"))?;
+    let versions_dir = paths::module_versions_dir(&uuid);
+    let target_path = stored_version_path(&versions_dir, &target);
+    if !target_path.exists() {
+        return Err(format!("Files for {uuid} {target} are missing"));
+    }
 
-    tokio::fs::write(&state_path, new_content)
-        .await
-        .map_err(|e| format!("Failed to save state: {e}"))?;
+    let current = module.version.clone();
+    let enabled = module.enabled;
+    let install_path = module.install_path.clone();
+    let _own_write = own_write_guard(&install_path);
+    let waybar_module_name = module.waybar_module_name.clone();
+    let position = module.position.clone().unwrap_or_default();
 
-    if was_enabled {
-        if let Ok(waybar_content) = waybar_config::load_config().await {
-            let config_path = install_path.join("config.jsonc");
-            let with_module_config = if config_path.exists() {
-                if let Ok(module_config) = tokio::fs::read_to_string(&config_path).await {
-                    let prefs = crate::services::preferences::load_preferences(&uuid);
-                    let module_config =
-                        waybar_config::substitute_preferences(&module_config, &prefs);
-                    let install_path_str = install_path.to_string_lossy();
-                    waybar_config::merge_module_config(
-                        &waybar_content,
-                        &module_config,
-                        &install_path_str,
-                    )
-                    .unwrap_or_else(|e| {
-                        tracing::warn!("Failed to merge module config during update: {e}");
-                        waybar_content.clone()
-                    })
-                } else {
-                    waybar_content.clone()
-                }
-            } else {
-                waybar_content.clone()
-            };
+    let snapshot = WaybarSnapshot::capture(&paths::waybar_paths())
+        .map_err(|e| format!("Failed to snapshot waybar config: {e}"))?;
 
-            if let Ok(with_module) =
-                waybar_config::add_module(&with_module_config, &waybar_module_name, section)
-            {
-                if let Err(e) = waybar_config::backup_config().await {
-                    tracing::warn!("Failed to backup waybar config: {e}");
-                }
+    if enabled
+        && let Err(e) =
+            apply_module_to_waybar(&uuid, &install_path, &waybar_module_name, &position, false)
+                .await
+    {
+        tracing::warn!("{e}");
+    }
 
-                if waybar_config::save_config(&with_module).await.is_ok()
-                    && let Err(e) = waybar_config::reload_waybar().await
-                {
-                    tracing::warn!("Failed to reload waybar: {e}");
-                }
-            }
+    let swap = ModuleSwap::new(
+        install_path.clone(),
+        target_path,
+        versions_dir.join(current.to_string()),
+    );
+    if let Err(e) = swap.prepare(&snapshot).and_then(|()| swap.commit()) {
+        if enabled
+            && let Err(e) =
+                apply_module_to_waybar(&uuid, &install_path, &waybar_module_name, &position, true)
+                    .await
+        {
+            tracing::warn!("{e}");
         }
+        return Err(format!("Failed to restore {uuid} {target}: {e}"));
+    }
 
-        handle_css_injection(&uuid, &install_path).await;
+    if let Err(e) = tokio::fs::remove_dir_all(versions_dir.join(target.to_string())).await {
+        tracing::warn!("Failed to clean up stored version {target} of {uuid}: {e}");
     }
 
-    tracing::info!("Updated module: {}", uuid);
-    Ok(updated)
+    module.previous_versions = record_previous_version(
+        &module.previous_versions,
+        &current,
+        &target,
+        PREVIOUS_VERSIONS_KEPT,
+    );
+    module.version = target.clone();
+    module.has_preferences = install_path.join("preferences.schema.json").exists();
+    let rolled_back = record_installed_version(&module)?;
+
+    if let Err(e) = prune_versions(&versions_dir, &rolled_back.previous_versions) {
+        tracing::warn!("Failed to prune old versions of {uuid}: {e}");
+    }
+
+    if enabled
+        && let Err(e) =
+            apply_module_to_waybar(&uuid, &install_path, &waybar_module_name, &position, true).await
+    {
+        if let Err(state) = StateStore::default().update_module(&uuid, |m| m.enabled = false) {
+            tracing::warn!("Failed to save state: {state}");
+        }
+        return Err(format!(
+            "Rolled back {uuid} to {target}, but it was disabled: {e}"
+        ));
+    }
+
+    tracing::info!("Rolled back {} from {} to {}", uuid, current, target);
+    Ok(rolled_back)
 }
 
-async fn update_all_modules_async(
-    updates: Vec<(String, String, ModuleVersion)>,
+pub(crate) async fn update_all_modules_async(
+    updates: Vec<UpdateRequest>,
+    allow_unsigned: bool,
+    scripts: ScriptApproval,
 ) -> Result<usize, String> {
     let mut success_count = 0;
 
-    for (uuid, repo_url, new_version) in updates {
-        match update_module_async(uuid.clone(), repo_url, new_version).await {
+    for request in updates {
+        let uuid = request.uuid.clone();
+        match update_module_async(request, allow_unsigned, scripts).await {
             Ok(_) => {
                 success_count += 1;
                 tracing::info!("Updated module: {}", uuid);
@@ -441,242 +834,368 @@ fn extract_tarball_sync(bytes: &[u8], install_path: &Path) -> Result<(), String>
     Ok(())
 }
 
-async fn toggle_module_async(uuid: String, enabled: bool) -> Result<String, (String, String)> {
-    use crate::services::waybar_config;
-
-    let state_path = paths::data_dir().join("installed.json");
+/// Enables or disables a module. Enabling refuses modules whose scripts
+/// look risky unless `scripts` says the findings were approved.
+pub(crate) async fn toggle_module_async(
+    uuid: String,
+    enabled: bool,
+    scripts: ScriptApproval,
+) -> Result<String, (String, InstallFailure)> {
+    if enabled {
+        let module = find_installed(&uuid).map_err(|e| (uuid.clone(), e.into()))?;
+        check_scripts(&module.install_path, scripts).map_err(|e| (uuid.clone(), e))?;
+    }
 
-    let content = tokio::fs::read_to_string(&state_path)
-        .await
-        .map_err(|e| (uuid.clone(), format!("Failed to read state: {e}")))?;
+    let module = StateStore::default()
+        .update_module(&uuid, |m| m.enabled = enabled)
+        .map_err(|e| (uuid.clone(), format!("Failed to save state: {e}").into()))?;
 
-    let mut modules: Vec<InstalledModule> = serde_json::from_str(&content)
-        .map_err(|e| (uuid.clone(), format!("Failed to parse state: {e}")))?;
+    let waybar_module_name = module.waybar_module_name;
+    let install_path = module.install_path;
+    let position = module.position.unwrap_or_default();
 
-    let module = modules
-        .iter_mut()
-        .find(|m| m.uuid.to_string() == uuid)
-        .ok_or_else(|| (uuid.clone(), format!("Module not found: {uuid}")))?;
+    if let Err(e) = apply_module_to_waybar(
+        &uuid,
+        &install_path,
+        &waybar_module_name,
+        &position,
+        enabled,
+    )
+    .await
+    {
+        if let Err(state) = StateStore::default().update_module(&uuid, |m| m.enabled = !enabled) {
+            tracing::warn!("Failed to save state: {state}");
+        }
+        return Err((uuid, e.into()));
+    }
 
-    let waybar_module_name = module.waybar_module_name.clone();
-    let install_path = module.install_path.clone();
-    let section = module
-        .position
-        .as_ref()
-        .map(|p| p.section)
-        .unwrap_or(BarSection::Center);
+    tracing::info!(
+        "Module {} {}",
+        uuid,
+        if enabled { "enabled" } else { "disabled" }
+    );
+    Ok(uuid)
+}
 
-    module.enabled = enabled;
+/// Fails with the review when the scripts in `dir` look risky and the
+/// findings were not approved. `Skip` leaves the review to a preview.
+fn check_scripts(dir: &Path, scripts: ScriptApproval) -> Result<(), InstallFailure> {
+    if scripts != ScriptApproval::IfClean {
+        return Ok(());
+    }
+    let review =
+        review_scripts(dir).map_err(|e| format!("Failed to inspect module scripts: {e}"))?;
+    if review.is_clean() {
+        Ok(())
+    } else {
+        Err(InstallFailure::Unapproved(review))
+    }
+}
 
-    let new_content = serde_json::to_string_pretty(&modules)
-        .map_err(|e| (uuid.clone(), format!("Failed to serialize: {e}")))?;
+/// Writes the module into the Waybar files again, or takes it out, without
+/// changing `installed.json` or reviewing its scripts.
+pub(super) async fn reapply_module(uuid: &str, enabled: bool) -> Result<(), String> {
+    let module = find_installed(uuid)?;
+    plan_module_toggle(
+        uuid,
+        &module.install_path,
+        &module.waybar_module_name,
+        &module.position.unwrap_or_default(),
+        enabled,
+    )
+    .await
+    .apply()
+    .await
+}
 
-    tokio::fs::write(&state_path, new_content)
+async fn apply_module_to_waybar(
+    uuid: &str,
+    install_path: &Path,
+    waybar_module_name: &str,
+    position: &ModulePosition,
+    enabled: bool,
+) -> Result<(), String> {
+    plan_module_toggle(uuid, install_path, waybar_module_name, position, enabled)
         .await
-        .map_err(|e| (uuid.clone(), format!("Failed to save state: {e}")))?;
+        .apply()
+        .await
+}
 
-    if let Ok(waybar_content) = waybar_config::load_config().await {
-        let modified = if enabled {
-            let config_path = install_path.join("config.jsonc");
-            let with_module_config = if config_path.exists() {
-                if let Ok(module_config) = tokio::fs::read_to_string(&config_path).await {
-                    let prefs = crate::services::preferences::load_preferences(&uuid);
+pub(super) async fn plan_module_toggle(
+    uuid: &str,
+    install_path: &Path,
+    waybar_module_name: &str,
+    position: &ModulePosition,
+    enabled: bool,
+) -> WaybarEdit {
+    use crate::services::waybar_config;
+
+    let files = match WaybarFiles::load() {
+        Ok(mut files) => {
+            let modified = if enabled {
+                let config_path = install_path.join("config.jsonc");
+                if !config_path.exists() {
+                    tracing::debug!("No config.jsonc found at {:?}", config_path);
+                } else if let Ok(module_config) = tokio::fs::read_to_string(&config_path).await {
+                    let prefs = crate::services::preferences::load_preferences(uuid);
                     tracing::debug!("Loaded {} preferences for {}", prefs.len(), uuid);
                     let module_config =
                         waybar_config::substitute_preferences(&module_config, &prefs);
                     tracing::debug!("Substituted config: {}", module_config);
-                    let install_path_str = install_path.to_string_lossy();
-                    waybar_config::merge_module_config(
-                        &waybar_content,
+                    let sandbox = waybar_config::CommandSandbox::from_settings(uuid);
+                    if let Err(e) = files.merge_module_config(
                         &module_config,
-                        &install_path_str,
-                    )
-                    .unwrap_or_else(|e| {
+                        &install_path.to_string_lossy(),
+                        position.bar.as_deref(),
+                        sandbox.as_ref(),
+                    ) {
                         tracing::warn!("Failed to merge module config: {e}");
-                        waybar_content.clone()
-                    })
+                    }
                 } else {
                     tracing::warn!("Failed to read module config from {:?}", config_path);
-                    waybar_content.clone()
                 }
+                files.add_module(waybar_module_name, position)
             } else {
-                tracing::debug!("No config.jsonc found at {:?}", config_path);
-                waybar_content.clone()
+                if let Err(e) = files.remove_module_config(waybar_module_name) {
+                    tracing::warn!("Failed to remove module config: {e}");
+                }
+                files.remove_module(waybar_module_name)
             };
-            waybar_config::add_module(&with_module_config, &waybar_module_name, section)
-        } else {
-            let without_config =
-                waybar_config::remove_module_config(&waybar_content, &waybar_module_name)
-                    .unwrap_or_else(|e| {
-                        tracing::warn!("Failed to remove module config: {e}");
-                        waybar_content.clone()
-                    });
-            waybar_config::remove_module(&without_config, &waybar_module_name)
-        };
-
-        if let Ok(new_waybar_content) = modified {
-            if let Err(e) = waybar_config::backup_config().await {
-                tracing::warn!("Failed to backup waybar config: {e}");
-            }
 
-            if waybar_config::save_config(&new_waybar_content)
-                .await
-                .is_ok()
-                && let Err(e) = waybar_config::reload_waybar().await
-            {
-                tracing::warn!("Failed to reload waybar: {e}");
+            match modified {
+                Ok(()) => Some(files),
+                Err(e) => {
+                    tracing::warn!("Failed to update waybar config: {e}");
+                    None
+                }
             }
         }
-    }
+        Err(e) => {
+            tracing::warn!("Failed to load waybar config: {e}");
+            None
+        }
+    };
 
-    if enabled {
-        handle_css_injection(&uuid, &install_path).await;
+    let style = if enabled {
+        planned_css_injection(uuid, waybar_module_name, install_path).await
     } else {
-        handle_css_removal(&uuid).await;
-    }
+        planned_css_removal(uuid).await
+    };
+    WaybarEdit { files, ..style }
+}
 
-    tracing::info!(
-        "Module {} {}",
-        uuid,
-        if enabled { "enabled" } else { "disabled" }
-    );
-    Ok(uuid)
+pub(super) fn plan_module_move(
+    waybar_module_name: &str,
+    position: &ModulePosition,
+) -> Result<WaybarFiles, String> {
+    let mut files = WaybarFiles::load()?;
+    files.remove_module(waybar_module_name)?;
+    files.add_module(waybar_module_name, position)?;
+    Ok(files)
 }
 
-async fn change_module_position_async(
+pub(crate) async fn change_module_position_async(
     uuid: String,
-    new_section: BarSection,
+    new_position: ModulePosition,
 ) -> Result<String, String> {
-    use crate::services::waybar_config;
-
-    let state_path = paths::data_dir().join("installed.json");
-
-    let content = tokio::fs::read_to_string(&state_path)
-        .await
-        .map_err(|e| format!("Failed to read state: {e}"))?;
-
-    let mut modules: Vec<InstalledModule> =
-        serde_json::from_str(&content).map_err(|e| format!("Failed to parse state: {e}"))?;
-
-    let module = modules
-        .iter_mut()
-        .find(|m| m.uuid.to_string() == uuid)
-        .ok_or_else(|| format!("Module not found: {uuid}"))?;
-
-    let waybar_module_name = module.waybar_module_name.clone();
+    let module = find_installed(&uuid)?;
+    let waybar_module_name = module.waybar_module_name;
     let was_enabled = module.enabled;
-    let old_section = module.position.as_ref().map(|p| p.section);
-
-    module.position = Some(crate::domain::ModulePosition {
-        section: new_section,
-        order: None,
-    });
-
-    let new_content =
-        serde_json::to_string_pretty(&modules).map_err(|e| format!("Failed to serialize: {e}"))?;
+    let old_position = module.position;
 
-    tokio::fs::write(&state_path, new_content)
-        .await
-        .map_err(|e| format!("Failed to save state: {e}"))?;
-
-    if was_enabled && let Ok(waybar_content) = waybar_config::load_config().await {
-        let modified = waybar_config::remove_module(&waybar_content, &waybar_module_name).and_then(
-            |content| waybar_config::add_module(&content, &waybar_module_name, new_section),
-        );
+    let moved = if was_enabled {
+        plan_module_move(&waybar_module_name, &new_position)
+            .inspect_err(|e| tracing::warn!("Failed to move module in waybar config: {e}"))
+            .ok()
+    } else {
+        None
+    };
 
-        if let Ok(new_waybar_content) = modified {
-            if let Err(e) = waybar_config::backup_config().await {
-                tracing::warn!("Failed to backup waybar config: {e}");
+    StateStore::default()
+        .update(|modules| {
+            let module = modules
+                .iter_mut()
+                .find(|m| m.uuid.to_string() == uuid)
+                .ok_or_else(|| format!("Module not found: {uuid}"))?;
+            module.position = Some(new_position.clone());
+            if let Some(files) = &moved {
+                record_module_orders(modules, files);
             }
+            Ok(())
+        })
+        .map_err(|e| format!("Failed to save state: {e}"))?;
 
-            if waybar_config::save_config(&new_waybar_content)
-                .await
-                .is_ok()
-                && let Err(e) = waybar_config::reload_waybar().await
-            {
-                tracing::warn!("Failed to reload waybar: {e}");
-            }
+    if let Some(files) = &moved
+        && let Err(e) = try_save_and_reload(files).await
+    {
+        let restored = StateStore::default().update_module(&uuid, |m| {
+            m.position = old_position.clone();
+        });
+        if let Err(state) = restored {
+            tracing::warn!("Failed to save state: {state}");
         }
+        return Err(e);
     }
 
     tracing::info!(
         "Changed position of {} from {:?} to {:?}",
         uuid,
-        old_section,
-        new_section
+        old_position,
+        new_position
     );
     Ok(uuid)
 }
 
-async fn uninstall_module_async(uuid: String) -> Result<String, (String, String)> {
-    use crate::services::waybar_config;
+pub(super) fn record_module_orders(modules: &mut [InstalledModule], files: &WaybarFiles) {
+    for module in modules.iter_mut().filter(|m| m.enabled) {
+        let bar = module.position.as_ref().and_then(|p| p.bar.clone());
+        if let Ok(Some((section, index))) =
+            files.locate_module(&module.waybar_module_name, bar.as_deref())
+        {
+            module.position = Some(
+                ModulePosition::new(section)
+                    .on_bar(bar)
+                    .at(Some(index as u32)),
+            );
+        }
+    }
+}
 
-    let state_path = paths::data_dir().join("installed.json");
-    let install_path = paths::module_install_path(&uuid);
+async fn move_module_async(uuid: String, to: ModuleMove) -> Result<String, String> {
+    match resolve_move(&uuid, to).await? {
+        Some(position) => change_module_position_async(uuid, position).await,
+        None => Ok(uuid),
+    }
+}
 
-    let content = tokio::fs::read_to_string(&state_path)
-        .await
-        .map_err(|e| (uuid.clone(), format!("Failed to read state: {e}")))?;
+/// Turns a relative move into the position it ends up at, or `None` when the
+/// module is already there.
+pub(super) async fn resolve_move(
+    uuid: &str,
+    to: ModuleMove,
+) -> Result<Option<ModulePosition>, String> {
+    let modules = super::load_installed_async().await?;
+    let find = |uuid: &str| {
+        modules
+            .iter()
+            .find(|m| m.uuid.to_string() == uuid)
+            .ok_or_else(|| format!("Module not found: {uuid}"))
+    };
 
-    let mut modules: Vec<InstalledModule> = serde_json::from_str(&content)
-        .map_err(|e| (uuid.clone(), format!("Failed to parse state: {e}")))?;
+    let module = find(uuid)?;
+    let bar = module.position.as_ref().and_then(|p| p.bar.clone());
+    let files = WaybarFiles::load()?;
+    let (section, index) = files
+        .locate_module(&module.waybar_module_name, bar.as_deref())?
+        .ok_or_else(|| format!("{} is not in the waybar config", module.waybar_module_name))?;
+
+    let position = match to {
+        ModuleMove::Up if index == 0 => return Ok(None),
+        ModuleMove::Up => ModulePosition::new(section)
+            .on_bar(bar)
+            .at(Some(index as u32 - 1)),
+        ModuleMove::Down => ModulePosition::new(section)
+            .on_bar(bar)
+            .at(Some(index as u32 + 1)),
+        ModuleMove::Onto(target) => {
+            let target = find(&target)?;
+            let target_bar = target.position.as_ref().and_then(|p| p.bar.clone());
+            let (target_section, target_index) = files
+                .locate_module(&target.waybar_module_name, target_bar.as_deref())?
+                .ok_or_else(|| {
+                    format!("{} is not in the waybar config", target.waybar_module_name)
+                })?;
+            ModulePosition::new(target_section)
+                .on_bar(target_bar)
+                .at(Some(target_index as u32))
+        }
+    };
 
-    let module = modules
-        .iter()
-        .find(|m| m.uuid.to_string() == uuid)
-        .ok_or_else(|| (uuid.clone(), format!("Module not found: {uuid}")))?;
+    Ok(Some(position))
+}
+
+pub(crate) async fn uninstall_module_async(
+    uuid: String,
+) -> Result<UninstallResult, (String, String)> {
+    let install_path = paths::module_install_path(&uuid);
+    let _own_write = own_write_guard(&install_path);
+    let module = find_installed(&uuid).map_err(|e| (uuid.clone(), e))?;
 
     let was_enabled = module.enabled;
     let waybar_module_name = module.waybar_module_name.clone();
 
     if was_enabled {
-        if let Ok(waybar_content) = waybar_config::load_config().await {
-            let without_config =
-                waybar_config::remove_module_config(&waybar_content, &waybar_module_name)
-                    .unwrap_or_else(|e| {
-                        tracing::warn!("Failed to remove module config: {e}");
-                        waybar_content.clone()
-                    });
-
-            let without_module = waybar_config::remove_module(&without_config, &waybar_module_name);
-
-            if let Ok(new_waybar_content) = without_module {
-                if let Err(e) = waybar_config::backup_config().await {
-                    tracing::warn!("Failed to backup waybar config: {e}");
-                }
-
-                if waybar_config::save_config(&new_waybar_content)
-                    .await
-                    .is_ok()
-                    && let Err(e) = waybar_config::reload_waybar().await
-                {
-                    tracing::warn!("Failed to reload waybar: {e}");
-                }
-            }
+        let removed = WaybarFiles::load().and_then(|mut files| {
+            files.remove_module_config(&waybar_module_name)?;
+            files.remove_module(&waybar_module_name)?;
+            Ok(files)
+        });
+
+        match removed {
+            Ok(files) => save_and_reload(&files).await,
+            Err(e) => tracing::warn!("Failed to remove module from waybar config: {e}"),
         }
 
         handle_css_removal(&uuid).await;
     }
 
+    let script = if install_path.exists() {
+        let module_dir = install_path.clone();
+        match tokio::task::spawn_blocking(move || run_uninstall_script(&module_dir)).await {
+            Ok(Ok(script)) => script,
+            Ok(Err(e)) => Some(ScriptResult {
+                success: false,
+                exit_code: None,
+                stdout: String::new(),
+                stderr: e.to_string(),
+            }),
+            Err(e) => return Err((uuid, format!("Uninstall script task failed: {e}"))),
+        }
+    } else {
+        None
+    };
+
+    if let Some(result) = script.as_ref().filter(|r| !r.success) {
+        tracing::warn!(
+            "Uninstall script for {} failed (exit code {:?}): {}",
+            uuid,
+            result.exit_code,
+            result.stderr.trim()
+        );
+    }
+
     match tokio::fs::remove_dir_all(&install_path).await {
         Ok(()) => {}
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
         Err(e) => return Err((uuid, format!("Failed to remove module files: {e}"))),
     }
 
-    modules.retain(|m| m.uuid.to_string() != uuid);
-
-    let new_content = serde_json::to_string_pretty(&modules)
-        .map_err(|e| (uuid.clone(), format!("Failed to serialize: {e}")))?;
+    let lock_path = paths::lockfile_path();
+    match Lockfile::load(&lock_path) {
+        Ok(mut lockfile) => {
+            if lockfile.remove(&uuid)
+                && let Err(e) = lockfile.save(&lock_path)
+            {
+                tracing::warn!("Failed to update barforge.lock: {e}");
+            }
+        }
+        Err(e) => tracing::warn!("Failed to read barforge.lock: {e}"),
+    }
 
-    tokio::fs::write(&state_path, new_content)
-        .await
+    StateStore::default()
+        .update(|modules| {
+            modules.retain(|m| m.uuid.to_string() != uuid);
+            Ok(())
+        })
         .map_err(|e| (uuid.clone(), format!("Failed to save state: {e}")))?;
 
     tracing::info!("Uninstalled module {}", uuid);
-    Ok(uuid)
+    Ok(UninstallResult { uuid, script })
 }
 
+/// Marks the scripts at the top of a module directory executable: `.sh`
+/// files and files with a shebang. Python and JavaScript files without one
+/// are libraries, not scripts to run.
 pub async fn make_scripts_executable(install_path: &Path) -> Result<(), String> {
     use std::os::unix::fs::PermissionsExt;
 
@@ -686,7 +1205,7 @@ pub async fn make_scripts_executable(install_path: &Path) -> Result<(), String>
 
     while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
         let path = entry.path();
-        if path.extension().is_some_and(|ext| ext == "sh") {
+        if is_script(&path).await {
             let metadata = tokio::fs::metadata(&path)
                 .await
                 .map_err(|e| format!("Failed to get metadata: {e}"))?;
@@ -702,6 +1221,22 @@ pub async fn make_scripts_executable(install_path: &Path) -> Result<(), String>
     Ok(())
 }
 
+async fn is_script(path: &Path) -> bool {
+    use tokio::io::AsyncReadExt;
+
+    if !tokio::fs::metadata(path).await.is_ok_and(|m| m.is_file()) {
+        return false;
+    }
+    if path.extension().is_some_and(|ext| ext == "sh") {
+        return true;
+    }
+    let mut shebang = [0u8; 2];
+    match tokio::fs::File::open(path).await {
+        Ok(mut file) => file.read_exact(&mut shebang).await.is_ok() && &shebang == b"#!",
+        Err(_) => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -723,6 +1258,59 @@ mod tests {
         assert_ne!(meta_after.permissions().mode() & 0o111, 0);
     }
 
+    #[tokio::test]
+    async fn test_make_scripts_executable_handles_interpreted_scripts() {
+        let dir = tempdir().unwrap();
+        let python = dir.path().join("weather.py");
+        let shebang = dir.path().join("status");
+        let library = dir.path().join("helpers.py");
+        let module = dir.path().join("format.mjs");
+        std::fs::write(&python, "#!/usr/bin/env python3\nimport json\n").unwrap();
+        std::fs::write(&shebang, "#!/usr/bin/env node\nconsole.log('{}')\n").unwrap();
+        std::fs::write(&library, "import json\n").unwrap();
+        std::fs::write(&module, "export const x = 1;\n").unwrap();
+
+        make_scripts_executable(dir.path()).await.unwrap();
+
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode();
+        for path in [&python, &shebang] {
+            assert_ne!(mode(path) & 0o111, 0, "{}", path.display());
+        }
+        for path in [&library, &module] {
+            assert_eq!(mode(path) & 0o111, 0, "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn test_check_scripts_needs_approval_for_risky_scripts() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("fetch.sh"), "curl https://example.com\n").unwrap();
+
+        assert!(matches!(
+            check_scripts(dir.path(), ScriptApproval::IfClean),
+            Err(InstallFailure::Unapproved(review)) if review.finding_count() == 1
+        ));
+        assert!(check_scripts(dir.path(), ScriptApproval::Approved).is_ok());
+
+        std::fs::write(dir.path().join("fetch.sh"), "echo '{}'\n").unwrap();
+        assert!(check_scripts(dir.path(), ScriptApproval::IfClean).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unsigned_update_with_risky_scripts_needs_approval() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("fetch.sh"), "curl https://example.com\n").unwrap();
+
+        let error = prepare_unsigned_files(dir.path(), ScriptApproval::IfClean)
+            .await
+            .unwrap_err();
+        assert!(error.contains("risky patterns"), "{error}");
+
+        prepare_unsigned_files(dir.path(), ScriptApproval::Approved)
+            .await
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn test_make_scripts_executable_ignores_non_sh() {
         let dir = tempdir().unwrap();
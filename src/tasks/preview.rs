@@ -0,0 +1,172 @@
+use iced::Task;
+
+use crate::app::Message;
+use crate::domain::{ModuleMove, ModulePosition};
+use crate::services::{
+    ConfigPreview, ScriptApproval, ScriptReview, WaybarSnapshot, discard_staging, paths,
+    review_scripts,
+};
+
+use super::module::{
+    UpdateRequest, find_installed, plan_module_move, plan_module_toggle, plan_staged_update,
+    resolve_move, stage_module_files,
+};
+use super::waybar::WaybarEdit;
+
+/// A change to an installed module that touches the Waybar config.
+#[derive(Debug, Clone)]
+pub enum PendingChange {
+    Toggle {
+        uuid: String,
+        enabled: bool,
+    },
+    Position {
+        uuid: String,
+        position: ModulePosition,
+    },
+    Move {
+        uuid: String,
+        to: ModuleMove,
+    },
+    Update {
+        request: UpdateRequest,
+        allow_unsigned: bool,
+    },
+}
+
+impl PendingChange {
+    pub fn uuid(&self) -> &str {
+        match self {
+            Self::Toggle { uuid, .. } | Self::Position { uuid, .. } | Self::Move { uuid, .. } => {
+                uuid
+            }
+            Self::Update { request, .. } => &request.uuid,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            Self::Toggle {
+                uuid,
+                enabled: true,
+            } => format!("Enable {uuid}"),
+            Self::Toggle {
+                uuid,
+                enabled: false,
+            } => format!("Disable {uuid}"),
+            Self::Position { uuid, .. } | Self::Move { uuid, .. } => format!("Move {uuid}"),
+            Self::Update { request, .. } => {
+                format!("Update {} to {}", request.uuid, request.version)
+            }
+        }
+    }
+
+    /// Applies the change. `scripts` says whether risky findings in the
+    /// module's scripts were approved in the preview.
+    pub fn apply(self, scripts: ScriptApproval) -> Task<Message> {
+        match self {
+            Self::Toggle { uuid, enabled } => super::toggle_module(uuid, enabled, scripts),
+            Self::Position { uuid, position } => super::change_module_position(uuid, position),
+            Self::Move { uuid, to } => super::move_module(uuid, to),
+            Self::Update {
+                request,
+                allow_unsigned,
+            } => super::update_module(request, allow_unsigned, scripts),
+        }
+    }
+}
+
+pub fn preview_change(change: PendingChange) -> Task<Message> {
+    Task::perform(preview_change_async(change), |(change, preview)| {
+        Message::ChangePreviewed(change, preview)
+    })
+}
+
+pub(crate) async fn preview_change_async(
+    change: PendingChange,
+) -> (PendingChange, Result<ConfigPreview, String>) {
+    let preview = plan_change(&change).await;
+    (change, preview)
+}
+
+async fn plan_change(change: &PendingChange) -> Result<ConfigPreview, String> {
+    let module = find_installed(change.uuid())?;
+    let position = module.position.clone().unwrap_or_default();
+
+    match change {
+        PendingChange::Toggle { uuid, enabled } => {
+            let mut preview = plan_module_toggle(
+                uuid,
+                &module.install_path,
+                &module.waybar_module_name,
+                &position,
+                *enabled,
+            )
+            .await
+            .preview();
+            if *enabled {
+                preview.scripts = review(&module.install_path)?;
+            }
+            Ok(preview)
+        }
+        PendingChange::Position { .. } | PendingChange::Move { .. } if !module.enabled => {
+            Ok(ConfigPreview::default())
+        }
+        PendingChange::Position { position, .. } => Ok(WaybarEdit {
+            files: Some(plan_module_move(&module.waybar_module_name, position)?),
+            ..WaybarEdit::default()
+        }
+        .preview()),
+        PendingChange::Move { uuid, to } => match resolve_move(uuid, to.clone()).await? {
+            Some(position) => Ok(WaybarEdit {
+                files: Some(plan_module_move(&module.waybar_module_name, &position)?),
+                ..WaybarEdit::default()
+            }
+            .preview()),
+            None => Ok(ConfigPreview::default()),
+        },
+        PendingChange::Update {
+            request,
+            allow_unsigned,
+        } => {
+            if request.checksum.is_none() && !allow_unsigned {
+                return Err(format!(
+                    "No signed package is available for {} {}",
+                    request.uuid, request.version
+                ));
+            }
+            let preview_path = paths::module_preview_path(&request.uuid);
+            let planned = async {
+                stage_module_files(
+                    &request.uuid,
+                    &request.version,
+                    &request.repo_url,
+                    request.checksum.as_deref(),
+                    &preview_path,
+                    ScriptApproval::Skip,
+                )
+                .await?;
+                let mut preview = if module.enabled {
+                    let snapshot = WaybarSnapshot::capture(&paths::waybar_paths())
+                        .map_err(|e| format!("Failed to snapshot waybar config: {e}"))?;
+                    plan_staged_update(&module, &preview_path, &snapshot)
+                        .await?
+                        .preview()
+                } else {
+                    ConfigPreview::default()
+                };
+                preview.scripts = review(&preview_path)?;
+                Ok(preview)
+            }
+            .await;
+            if let Err(e) = discard_staging(&preview_path) {
+                tracing::warn!("Failed to remove preview of {}: {e}", request.uuid);
+            }
+            planned
+        }
+    }
+}
+
+fn review(dir: &std::path::Path) -> Result<ScriptReview, String> {
+    review_scripts(dir).map_err(|e| format!("Failed to inspect module scripts: {e}"))
+}
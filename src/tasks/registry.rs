@@ -12,7 +12,7 @@ pub fn refresh_registry() -> Task<Message> {
     Task::perform(refresh_registry_async(), Message::RegistryRefreshed)
 }
 
-async fn fetch_registry_async() -> Result<RegistryIndex, String> {
+pub(crate) async fn fetch_registry_async() -> Result<RegistryIndex, String> {
     let cache_path = paths::registry_cache_path();
 
     if let Ok(content) = tokio::fs::read_to_string(&cache_path).await
@@ -52,7 +52,7 @@ async fn fetch_registry_async() -> Result<RegistryIndex, String> {
     Ok(index)
 }
 
-async fn refresh_registry_async() -> Result<RegistryIndex, String> {
+pub(crate) async fn refresh_registry_async() -> Result<RegistryIndex, String> {
     let cache_path = paths::registry_cache_path();
     if let Err(e) = tokio::fs::remove_file(&cache_path).await {
         tracing::debug!("Cache file removal skipped: {e}");
@@ -0,0 +1,177 @@
+use crate::domain::{ModulePosition, ModuleVersion, RegistryIndex, RegistryModule};
+use crate::services::paths;
+use crate::services::preferences::{load_preferences, save_preferences};
+use crate::services::{Lockfile, ScriptApproval, SyncAction};
+
+use super::module::{
+    UpdateRequest, change_module_position_async, install_module_async, reapply_module,
+    toggle_module_async, uninstall_module_async, update_module_async,
+};
+
+pub(crate) async fn apply_sync_action(
+    action: &SyncAction,
+    registry: Option<&RegistryIndex>,
+) -> Result<(), String> {
+    match action {
+        SyncAction::Install { uuid, version } => {
+            let lockfile = Lockfile::load(&paths::lockfile_path()).map_err(|e| e.to_string())?;
+            let locked = lockfile
+                .find(uuid)
+                .filter(|locked| version.as_ref().is_none_or(|v| v == &locked.version));
+
+            let (version, checksum, module) = match locked {
+                Some(locked) => (
+                    Some(locked.version.clone()),
+                    Some(locked.sha256.clone()),
+                    registry_module(registry, uuid, None)?,
+                ),
+                None => {
+                    let module = registry_module(registry, uuid, version.as_ref())?;
+                    (module.version.clone(), module.checksum.clone(), module)
+                }
+            };
+
+            install_module_async(
+                uuid.clone(),
+                module.name.clone(),
+                version,
+                module.repo_url.clone(),
+                checksum,
+                ScriptApproval::IfClean,
+                // The version above was already resolved against barforge.lock.
+                true,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        SyncAction::Remove { uuid } => {
+            uninstall_module_async(uuid.clone())
+                .await
+                .map_err(|(_, e)| e)?;
+        }
+        SyncAction::ChangeVersion { uuid, to, .. } => {
+            let lockfile = Lockfile::load(&paths::lockfile_path()).map_err(|e| e.to_string())?;
+            let (module, checksum) = match lockfile.find(uuid).filter(|l| &l.version == to) {
+                Some(locked) => (
+                    registry_module(registry, uuid, None)?,
+                    Some(locked.sha256.clone()),
+                ),
+                None => {
+                    let module = registry_module(registry, uuid, Some(to))?;
+                    (module, module.checksum.clone())
+                }
+            };
+
+            let request = UpdateRequest {
+                uuid: uuid.clone(),
+                repo_url: module.repo_url.clone(),
+                version: to.clone(),
+                checksum,
+            };
+            update_module_async(request, false, ScriptApproval::IfClean).await?;
+        }
+        SyncAction::Reconfigure {
+            uuid,
+            changes,
+            reapply,
+        } => {
+            let mut prefs = load_preferences(uuid);
+            prefs.extend(changes.clone());
+            save_preferences(uuid, &prefs)
+                .map_err(|e| format!("Failed to save preferences: {e}"))?;
+
+            // The module stays enabled with the scripts it was approved with,
+            // so only its definition is written again.
+            if *reapply {
+                reapply_module(uuid, true).await?;
+            }
+        }
+        SyncAction::Move { uuid, to, .. } => {
+            let bar = super::load_installed_async()
+                .await?
+                .into_iter()
+                .find(|m| m.uuid.to_string() == *uuid)
+                .and_then(|m| m.position)
+                .and_then(|p| p.bar);
+            change_module_position_async(uuid.clone(), ModulePosition::new(*to).on_bar(bar))
+                .await?;
+        }
+        SyncAction::Enable { uuid } => {
+            toggle_module_async(uuid.clone(), true, ScriptApproval::IfClean)
+                .await
+                .map_err(|(_, e)| e.to_string())?;
+        }
+        SyncAction::Disable { uuid } => {
+            toggle_module_async(uuid.clone(), false, ScriptApproval::IfClean)
+                .await
+                .map_err(|(_, e)| e.to_string())?;
+        }
+    }
+
+    tracing::info!("Applied sync action: {}", action);
+    Ok(())
+}
+
+fn registry_module<'a>(
+    registry: Option<&'a RegistryIndex>,
+    uuid: &str,
+    pinned: Option<&ModuleVersion>,
+) -> Result<&'a RegistryModule, String> {
+    let module = registry
+        .ok_or("Registry is not available")?
+        .find_by_uuid(uuid)
+        .ok_or_else(|| format!("Module not found in registry: {uuid}"))?;
+
+    check_pinned_version(uuid, pinned, module.version.as_ref())?;
+    Ok(module)
+}
+
+fn check_pinned_version(
+    uuid: &str,
+    pinned: Option<&ModuleVersion>,
+    available: Option<&ModuleVersion>,
+) -> Result<(), String> {
+    match (pinned, available) {
+        (Some(pinned), Some(available)) if pinned != available => Err(format!(
+            "Pinned version {pinned} of {uuid} is not available (registry has {available})"
+        )),
+        (Some(pinned), None) => Err(format!(
+            "Pinned version {pinned} of {uuid} is not available (registry has no version)"
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(v: &str) -> ModuleVersion {
+        ModuleVersion::try_from(v).unwrap()
+    }
+
+    #[test]
+    fn unpinned_accepts_any_registry_version() {
+        assert!(check_pinned_version("a@b", None, Some(&version("1.0.0"))).is_ok());
+        assert!(check_pinned_version("a@b", None, None).is_ok());
+    }
+
+    #[test]
+    fn pinned_requires_matching_registry_version() {
+        assert!(
+            check_pinned_version("a@b", Some(&version("1.0.0")), Some(&version("1.0.0"))).is_ok()
+        );
+
+        let err = check_pinned_version("a@b", Some(&version("1.0.0")), Some(&version("1.1.0")))
+            .unwrap_err();
+        assert!(err.contains("registry has 1.1.0"));
+
+        assert!(check_pinned_version("a@b", Some(&version("1.0.0")), None).is_err());
+    }
+
+    #[test]
+    fn install_without_registry_fails() {
+        let result = registry_module(None, "a@b", None);
+        assert!(result.is_err());
+    }
+}
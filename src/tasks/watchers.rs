@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use iced::Subscription;
@@ -7,6 +7,9 @@ use tokio::sync::mpsc as async_mpsc;
 use tokio::time::timeout;
 
 use crate::app::Message;
+use crate::services::{installed_state_path, is_own_write, paths};
+
+const CHANGE_DEBOUNCE: Duration = Duration::from_millis(300);
 
 fn omarchy_theme_path() -> PathBuf {
     dirs::config_dir()
@@ -76,6 +79,126 @@ pub fn watch_omarchy_theme() -> Subscription<Message> {
     })
 }
 
+/// The files Barforge reads its state from, and the modules directory.
+struct WatchedPaths {
+    files: Vec<PathBuf>,
+    modules_dir: PathBuf,
+}
+
+impl WatchedPaths {
+    fn current() -> Self {
+        let waybar = paths::waybar_paths();
+        Self {
+            files: vec![
+                waybar.config,
+                waybar.managed_include,
+                waybar.style,
+                waybar.managed_style,
+                installed_state_path(),
+            ],
+            modules_dir: paths::modules_dir(),
+        }
+    }
+
+    /// The directories to watch: the parents of the files, non-recursively,
+    /// and the modules directory, recursively.
+    fn directories(&self) -> Vec<(PathBuf, RecursiveMode)> {
+        let mut dirs: Vec<(PathBuf, RecursiveMode)> = Vec::new();
+        for dir in self.files.iter().filter_map(|file| file.parent()) {
+            if !dirs.iter().any(|(d, _)| d == dir) {
+                dirs.push((dir.to_path_buf(), RecursiveMode::NonRecursive));
+            }
+        }
+        dirs.push((self.modules_dir.clone(), RecursiveMode::Recursive));
+        dirs
+    }
+
+    /// Whether a change to `path` can affect what the Installed screen shows.
+    /// Staging, preview and version directories are skipped.
+    fn is_relevant(&self, path: &Path) -> bool {
+        if self.files.iter().any(|file| file == path) {
+            return true;
+        }
+        path.strip_prefix(&self.modules_dir).is_ok_and(|rest| {
+            !rest
+                .components()
+                .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+        })
+    }
+}
+
+/// Emits [`Message::WaybarFilesChanged`] when the Waybar config or CSS,
+/// `installed.json` or a module directory changes outside Barforge.
+pub fn watch_waybar_files() -> Subscription<Message> {
+    Subscription::run(|| {
+        iced::futures::stream::unfold(WatcherState::Ready, |state| async move {
+            match state {
+                WatcherState::Ready => {
+                    let watched = WatchedPaths::current();
+                    let dirs = watched.directories();
+
+                    let (tx, rx) = async_mpsc::unbounded_channel();
+                    let watcher_result = RecommendedWatcher::new(
+                        move |res: Result<notify::Event, notify::Error>| {
+                            let Ok(event) = res else { return };
+                            if event.kind.is_access() || event.kind.is_other() {
+                                return;
+                            }
+                            if event
+                                .paths
+                                .iter()
+                                .any(|path| watched.is_relevant(path) && !is_own_write(path))
+                            {
+                                let _ = tx.send(());
+                            }
+                        },
+                        notify::Config::default(),
+                    );
+
+                    let Ok(mut watcher) = watcher_result else {
+                        return Some((Message::Tick, WatcherState::Unavailable));
+                    };
+                    let mut watching = false;
+                    for (dir, mode) in dirs {
+                        if dir.is_dir() && watcher.watch(&dir, mode).is_ok() {
+                            watching = true;
+                        }
+                    }
+                    if watching {
+                        Some((
+                            Message::Tick,
+                            WatcherState::Watching {
+                                _watcher: watcher,
+                                rx,
+                            },
+                        ))
+                    } else {
+                        Some((Message::Tick, WatcherState::Unavailable))
+                    }
+                }
+                WatcherState::Watching { _watcher, mut rx } => {
+                    match timeout(Duration::from_millis(500), rx.recv()).await {
+                        Ok(Some(())) => {
+                            tokio::time::sleep(CHANGE_DEBOUNCE).await;
+                            while rx.try_recv().is_ok() {}
+                            Some((
+                                Message::WaybarFilesChanged,
+                                WatcherState::Watching { _watcher, rx },
+                            ))
+                        }
+                        Ok(None) => Some((Message::Tick, WatcherState::Ready)),
+                        Err(_) => Some((Message::Tick, WatcherState::Watching { _watcher, rx })),
+                    }
+                }
+                WatcherState::Unavailable => {
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                    Some((Message::Tick, WatcherState::Ready))
+                }
+            }
+        })
+    })
+}
+
 enum WatcherState {
     Ready,
     Watching {
@@ -84,3 +207,53 @@ enum WatcherState {
     },
     Unavailable,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watched() -> WatchedPaths {
+        WatchedPaths {
+            files: vec![
+                PathBuf::from("/config/waybar/config.jsonc"),
+                PathBuf::from("/config/waybar/style.css"),
+                PathBuf::from("/data/barforge/installed.json"),
+            ],
+            modules_dir: PathBuf::from("/data/barforge/modules"),
+        }
+    }
+
+    #[test]
+    fn only_state_files_and_module_directories_are_relevant() {
+        let watched = watched();
+        assert!(watched.is_relevant(Path::new("/config/waybar/style.css")));
+        assert!(watched.is_relevant(Path::new("/data/barforge/installed.json")));
+        assert!(watched.is_relevant(Path::new("/data/barforge/modules/weather@test")));
+        assert!(watched.is_relevant(Path::new(
+            "/data/barforge/modules/weather@test/config.jsonc"
+        )));
+
+        assert!(!watched.is_relevant(Path::new("/config/waybar/scripts/clock.sh")));
+        assert!(!watched.is_relevant(Path::new("/data/barforge/installed.json.4242.tmp")));
+        assert!(!watched.is_relevant(Path::new(
+            "/data/barforge/modules/.staging/weather@test/style.css"
+        )));
+    }
+
+    #[test]
+    fn file_directories_are_watched_once() {
+        let dirs: Vec<PathBuf> = watched()
+            .directories()
+            .into_iter()
+            .map(|(dir, _)| dir)
+            .collect();
+        assert_eq!(
+            dirs,
+            vec![
+                PathBuf::from("/config/waybar"),
+                PathBuf::from("/data/barforge"),
+                PathBuf::from("/data/barforge/modules"),
+            ]
+        );
+    }
+}
@@ -1,42 +1,285 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::services::paths;
+use iced::Task;
 
-pub async fn handle_css_injection(uuid: &str, install_path: &Path) {
+use crate::app::Message;
+use crate::domain::BarSection;
+use crate::services::waybar_config::{BarInfo, LayoutLane, WaybarFiles};
+use crate::services::{
+    BackupStore, ConfigPreview, FileChange, StateStore, StyleIssue, WaybarReloader, paths,
+    record_own_write, update_module_style,
+};
+
+use super::module::record_module_orders;
+
+pub fn load_waybar_bars() -> Task<Message> {
+    Task::perform(load_waybar_bars_async(), Message::WaybarBarsLoaded)
+}
+
+async fn load_waybar_bars_async() -> Result<Vec<BarInfo>, String> {
     use crate::services::waybar_config;
 
-    let css_path = install_path.join("style.css");
-    if !css_path.exists() {
-        return;
+    let content = waybar_config::load_config().await?;
+    waybar_config::list_bars(&content)
+}
+
+pub fn load_bar_layout(bar: Option<String>) -> Task<Message> {
+    Task::perform(
+        async move { WaybarFiles::load()?.layout(bar.as_deref()) },
+        Message::LayoutLoaded,
+    )
+}
+
+pub fn move_layout_entry(
+    bar: Option<String>,
+    from: (BarSection, usize),
+    to: (BarSection, usize),
+) -> Task<Message> {
+    Task::perform(
+        move_layout_entry_async(bar, from, to),
+        Message::LayoutEntryMoved,
+    )
+}
+
+async fn move_layout_entry_async(
+    bar: Option<String>,
+    from: (BarSection, usize),
+    to: (BarSection, usize),
+) -> Result<Vec<LayoutLane>, String> {
+    let mut files = WaybarFiles::load()?;
+    let entry = files.move_entry(bar.as_deref(), from, to)?;
+    try_save_and_reload(&files).await?;
+    tracing::info!("Moved {} from {:?} to {:?}", entry, from, to);
+
+    let store = StateStore::default();
+    if store.path().exists() {
+        store
+            .update(|modules| {
+                record_module_orders(modules, &files);
+                Ok(())
+            })
+            .map_err(|e| format!("Failed to save state: {e}"))?;
     }
 
-    let Ok(module_css) = tokio::fs::read_to_string(&css_path).await else {
-        return;
+    files.layout(bar.as_deref())
+}
+
+pub async fn save_and_reload(files: &WaybarFiles) {
+    if let Err(e) = try_save_and_reload(files).await {
+        tracing::warn!("{e}");
+    }
+}
+
+pub async fn try_save_and_reload(files: &WaybarFiles) -> Result<(), String> {
+    let written = files
+        .save()
+        .map_err(|e| format!("Failed to save waybar config: {e}"))?;
+    if !written.is_empty() {
+        reload_or_revert(|| files.revert()).await?;
+    }
+    Ok(())
+}
+
+/// Reloads Waybar and, if it exits on the new files, puts the old ones back
+/// with `revert` and starts it again. Failing to signal Waybar is only logged.
+pub(super) async fn reload_or_revert(
+    revert: impl FnOnce() -> Result<(), String>,
+) -> Result<(), String> {
+    let reloader = WaybarReloader::default();
+    let error = match reloader.reload().await {
+        Ok(_) => return Ok(()),
+        Err(e) => e,
+    };
+    let Some(args) = error.exit_args() else {
+        tracing::warn!("Failed to reload waybar: {error}");
+        return Ok(());
     };
 
-    let waybar_style_path = paths::waybar_style_path();
-    let existing_css = tokio::fs::read_to_string(&waybar_style_path)
-        .await
-        .unwrap_or_default();
+    let mut message = error.to_string();
+    match revert() {
+        Ok(()) => message.push_str("\nThe previous Waybar config and style were restored"),
+        Err(e) => message.push_str(&format!(
+            "\nRestoring the previous Waybar files failed: {e}"
+        )),
+    }
+    if let Err(e) = reloader.start(args).await {
+        message.push_str(&format!("\nWaybar could not be started again: {e}"));
+    }
+    tracing::warn!("{message}");
+    Err(message)
+}
 
-    let new_css = waybar_config::inject_module_css(&existing_css, uuid, &module_css);
+#[derive(Debug, Clone)]
+pub(super) struct StyleEdit {
+    pub path: PathBuf,
+    pub original: String,
+    pub content: String,
+}
+
+/// Waybar config and style changes that are computed before anything is
+/// written, so they can be previewed first.
+#[derive(Debug, Clone, Default)]
+pub(super) struct WaybarEdit {
+    pub files: Option<WaybarFiles>,
+    pub styles: Vec<StyleEdit>,
+    pub style_issues: Vec<StyleIssue>,
+}
+
+impl WaybarEdit {
+    pub fn preview(&self) -> ConfigPreview {
+        let mut preview = ConfigPreview::default();
+        if let Some(files) = &self.files {
+            preview.changes = files.changes();
+            preview.issues = files.introduced_issues();
+        }
+        for style in &self.styles {
+            preview.changes.extend(FileChange::between(
+                style.path.clone(),
+                &style.original,
+                &style.content,
+            ));
+        }
+        preview.style_issues = self.style_issues.clone();
+        preview
+    }
+
+    fn changed_styles(&self) -> impl Iterator<Item = &StyleEdit> {
+        self.styles
+            .iter()
+            .filter(|style| style.content != style.original)
+    }
+
+    /// Writes the edit without reloading and reports whether any file changed.
+    pub async fn write(&self) -> Result<bool, String> {
+        for issue in &self.style_issues {
+            tracing::warn!("{issue}");
+        }
+        let config_changed = self.files.as_ref().is_some_and(WaybarFiles::has_changes);
+        let mut written = match &self.files {
+            Some(files) => !files
+                .save()
+                .map_err(|e| format!("Failed to save waybar config: {e}"))?
+                .is_empty(),
+            None => false,
+        };
+        if !config_changed && self.changed_styles().next().is_some() {
+            let paths = paths::waybar_paths();
+            let targets = [
+                paths.config,
+                paths.managed_include,
+                paths.style,
+                paths.managed_style,
+            ];
+            if let Err(e) = BackupStore::default().snapshot(&targets) {
+                tracing::warn!("Failed to back up waybar files: {e}");
+            }
+        }
+        for style in self.changed_styles() {
+            record_own_write(&style.path);
+            tokio::fs::write(&style.path, &style.content)
+                .await
+                .map_err(|e| format!("Failed to update waybar CSS: {e}"))?;
+            written = true;
+        }
+        Ok(written)
+    }
+
+    /// Writes the edit and reloads Waybar, reverting the edit if Waybar
+    /// exits on it.
+    pub async fn apply(&self) -> Result<(), String> {
+        match self.write().await {
+            Ok(true) => reload_or_revert(|| self.revert()).await,
+            Ok(false) => Ok(()),
+            Err(e) => {
+                tracing::warn!("{e}");
+                Ok(())
+            }
+        }
+    }
 
-    if let Err(e) = tokio::fs::write(&waybar_style_path, new_css).await {
-        tracing::warn!("Failed to inject CSS: {e}");
+    fn revert(&self) -> Result<(), String> {
+        if let Some(files) = &self.files {
+            files.revert()?;
+        }
+        for style in self.changed_styles() {
+            record_own_write(&style.path);
+            std::fs::write(&style.path, &style.original)
+                .map_err(|e| format!("Failed to restore {}: {e}", style.path.display()))?;
+        }
+        Ok(())
     }
 }
 
-pub async fn handle_css_removal(uuid: &str) {
-    use crate::services::waybar_config;
+/// Plans the style edits that put `module_css` for `uuid` into the managed
+/// stylesheet, or take it out when `None`, starting from the given contents
+/// of `style.css` and `barforge.css`.
+pub(super) fn plan_module_style(
+    uuid: &str,
+    module_css: Option<(&str, &str)>,
+    style: Option<String>,
+    managed: Option<String>,
+) -> WaybarEdit {
+    let style_path = paths::waybar_style_path();
+    let managed_path = paths::waybar_managed_style_path(&style_path);
+    let style = style.unwrap_or_default();
+    let managed = managed.unwrap_or_default();
+
+    let update = update_module_style(&style, &managed, uuid, module_css);
+    let styles = [
+        (managed_path, managed, update.managed),
+        (style_path, style, update.style),
+    ]
+    .into_iter()
+    .filter(|(_, original, content)| original != content)
+    .map(|(path, original, content)| StyleEdit {
+        path,
+        original,
+        content,
+    })
+    .collect();
+    WaybarEdit {
+        files: None,
+        styles,
+        style_issues: update.issues,
+    }
+}
 
-    let waybar_style_path = paths::waybar_style_path();
-    let Ok(existing_css) = tokio::fs::read_to_string(&waybar_style_path).await else {
-        return;
+async fn read_styles() -> (Option<String>, Option<String>) {
+    let style_path = paths::waybar_style_path();
+    let managed_path = paths::waybar_managed_style_path(&style_path);
+    (
+        tokio::fs::read_to_string(&style_path).await.ok(),
+        tokio::fs::read_to_string(&managed_path).await.ok(),
+    )
+}
+
+pub(super) async fn planned_css_injection(
+    uuid: &str,
+    waybar_module_name: &str,
+    install_path: &Path,
+) -> WaybarEdit {
+    let Ok(module_css) = tokio::fs::read_to_string(install_path.join("style.css")).await else {
+        return WaybarEdit::default();
     };
+    let (style, managed) = read_styles().await;
+    plan_module_style(
+        uuid,
+        Some((waybar_module_name, &module_css)),
+        style,
+        managed,
+    )
+}
 
-    let new_css = waybar_config::remove_module_css(&existing_css, uuid);
+pub(super) async fn planned_css_removal(uuid: &str) -> WaybarEdit {
+    let (style, managed) = read_styles().await;
+    if style.is_none() && managed.is_none() {
+        return WaybarEdit::default();
+    }
+    plan_module_style(uuid, None, style, managed)
+}
 
-    if let Err(e) = tokio::fs::write(&waybar_style_path, new_css).await {
+pub async fn handle_css_removal(uuid: &str) {
+    if let Err(e) = planned_css_removal(uuid).await.write().await {
         tracing::warn!("Failed to remove CSS: {e}");
     }
 }
@@ -7,8 +7,9 @@ use barforge_registry_types::{
 };
 
 use crate::app::state::{
-    App, AuthorProfileState, BrowseState, CategoryFilter, ConfirmationState, InstalledState,
-    LoadingState, ModuleDetailState, PreferencesState, Screen, SortField, SortOrder, ViewMode,
+    App, AuthorProfileState, BackupsState, BrowseState, CategoryFilter, ConfirmationState,
+    InstalledState, LayoutState, LoadingState, ModuleDetailState, PreferencesState, Screen,
+    SortField, SortOrder, ViewMode,
 };
 use crate::domain::InstalledModule;
 use crate::theme::{AppTheme, ThemeMode};
@@ -29,6 +30,7 @@ pub struct InstalledModuleBuilder {
     waybar_module_name: String,
     has_preferences: bool,
     registry_version: Option<ModuleVersion>,
+    previous_versions: Vec<ModuleVersion>,
 }
 
 impl InstalledModuleBuilder {
@@ -41,6 +43,7 @@ impl InstalledModuleBuilder {
             waybar_module_name: format!("custom/{name}"),
             has_preferences: false,
             registry_version: None,
+            previous_versions: Vec::new(),
         }
     }
 
@@ -64,6 +67,11 @@ impl InstalledModuleBuilder {
         self
     }
 
+    pub fn previous_versions(mut self, versions: &[&str]) -> Self {
+        self.previous_versions = versions.iter().map(|v| test_version(v)).collect();
+        self
+    }
+
     pub fn build(self) -> InstalledModule {
         InstalledModule {
             uuid: self.uuid,
@@ -75,6 +83,7 @@ impl InstalledModuleBuilder {
             installed_at: chrono::Utc::now(),
             registry_version: self.registry_version,
             position: None,
+            previous_versions: self.previous_versions,
         }
     }
 }
@@ -89,6 +98,7 @@ pub struct RegistryModuleBuilder {
     downloads: u64,
     version: Option<ModuleVersion>,
     tags: Vec<String>,
+    checksum: Option<String>,
 }
 
 impl RegistryModuleBuilder {
@@ -105,6 +115,7 @@ impl RegistryModuleBuilder {
             downloads: 100,
             version: Some(test_version("1.0.0")),
             tags: vec![],
+            checksum: None,
         }
     }
 
@@ -136,6 +147,11 @@ impl RegistryModuleBuilder {
         self
     }
 
+    pub fn checksum(mut self, c: &str) -> Self {
+        self.checksum = Some(c.to_string());
+        self
+    }
+
     pub fn build(self) -> RegistryModule {
         RegistryModule {
             uuid: self.uuid,
@@ -152,7 +168,7 @@ impl RegistryModuleBuilder {
             rating: None,
             verified_author: false,
             tags: self.tags,
-            checksum: None,
+            checksum: self.checksum,
         }
     }
 }
@@ -184,6 +200,8 @@ pub fn test_app() -> App {
             last_refreshed: None,
         },
         installed: InstalledState::default(),
+        layout: LayoutState::default(),
+        backups: BackupsState::default(),
         notifications: VecDeque::new(),
         loading: LoadingState::Idle,
         theme_mode: ThemeMode::Dark,
@@ -194,11 +212,18 @@ pub fn test_app() -> App {
         author_profile: AuthorProfileState::default(),
         confirmation: ConfirmationState::default(),
         preferences: PreferencesState::default(),
+        script_output: None,
+        change_preview: None,
+        script_review: None,
         spinner_frame: 0,
         last_spinner_update: Instant::now(),
         tray_enabled: false,
         tray_receiver: None,
         sandbox_status: None,
+        allow_unsigned_updates: false,
+        sandbox_module_commands: false,
+        seccomp_filter: true,
+        backup_retention: crate::services::DEFAULT_BACKUP_RETENTION,
     }
 }
 
@@ -146,6 +146,18 @@ pub fn notification_warning(theme: AppTheme) -> impl Fn(&Theme) -> container::St
     }
 }
 
+pub fn warning_banner(theme: AppTheme) -> impl Fn(&Theme) -> container::Style {
+    move |_| container::Style {
+        background: Some(Background::Color(theme.bg_floating)),
+        border: Border {
+            color: theme.warning,
+            width: 1.0,
+            radius: RADIUS_MD.into(),
+        },
+        ..Default::default()
+    }
+}
+
 pub fn modal_backdrop(theme: AppTheme) -> impl Fn(&Theme) -> container::Style {
     overlay(theme)
 }
@@ -109,6 +109,10 @@ pub const FILTER_DROPDOWN_WIDTH: f32 = 250.0;
 pub const PREFERENCES_MODAL_WIDTH: f32 = 500.0;
 pub const PREFERENCES_MODAL_MAX_HEIGHT: f32 = 600.0;
 pub const CONFIRMATION_DIALOG_WIDTH: f32 = 350.0;
+pub const SCRIPT_OUTPUT_DIALOG_WIDTH: f32 = 560.0;
+pub const SCRIPT_OUTPUT_MAX_HEIGHT: f32 = 320.0;
+pub const CHANGE_PREVIEW_DIALOG_WIDTH: f32 = 680.0;
+pub const CHANGE_PREVIEW_MAX_HEIGHT: f32 = 420.0;
 pub const NUMBER_INPUT_WIDTH: f32 = 120.0;
 pub const SKELETON_BAR_SM: f32 = 40.0;
 pub const SKELETON_BAR_MD: f32 = 60.0;
@@ -0,0 +1,137 @@
+use iced::widget::{Column, Space, button, column, container, row, scrollable, text};
+use iced::{Alignment, Element, Font, Length};
+
+use crate::app::message::Message;
+use crate::app::state::ChangePreviewState;
+use crate::services::ConfigPreview;
+
+use super::script_review_dialog::script_findings;
+use crate::theme::{
+    AppTheme, CHANGE_PREVIEW_DIALOG_WIDTH, CHANGE_PREVIEW_MAX_HEIGHT, FONT_MD, FONT_SM, FONT_XS,
+    SPACE_LG, SPACE_MD, SPACE_SM, SPACE_XS, button as button_style, container as cont_style,
+};
+
+pub fn change_preview_dialog<'a>(
+    state: &'a ChangePreviewState,
+    theme: &AppTheme,
+) -> Element<'a, Message> {
+    let preview = &state.preview;
+
+    let diff = diff_lines(preview, "No files change", theme);
+
+    let diff_panel = container(scrollable(diff).height(Length::Shrink))
+        .padding(SPACE_SM)
+        .max_height(CHANGE_PREVIEW_MAX_HEIGHT)
+        .width(Length::Fill)
+        .style(cont_style::input(*theme));
+
+    let mut issues = column![].spacing(SPACE_XS);
+    if !preview.is_valid() {
+        issues = issues.push(
+            text("Barforge will not save this change because it would break the Waybar config:")
+                .size(FONT_SM)
+                .color(theme.danger),
+        );
+        for issue in &preview.issues {
+            issues = issues.push(
+                text(format!("\u{2022} {issue}"))
+                    .size(FONT_XS)
+                    .color(theme.danger),
+            );
+        }
+    }
+
+    if !preview.style_issues.is_empty() {
+        issues = issues.push(
+            text("The module's CSS may affect more than the module itself:")
+                .size(FONT_SM)
+                .color(theme.warning),
+        );
+        for issue in &preview.style_issues {
+            issues = issues.push(
+                text(format!("\u{2022} {issue}"))
+                    .size(FONT_XS)
+                    .color(theme.warning),
+            );
+        }
+    }
+
+    let cancel_btn = button(text("Cancel").size(FONT_SM))
+        .on_press(Message::CancelChange)
+        .padding([SPACE_SM, SPACE_MD])
+        .style(button_style::secondary(*theme));
+
+    if !preview.scripts.is_clean() {
+        issues = issues.push(script_findings(
+            &preview.scripts,
+            state.scripts_approved,
+            theme,
+        ));
+    }
+
+    let apply_btn = button(text("Apply").size(FONT_SM))
+        .on_press_maybe(state.can_apply().then_some(Message::ConfirmChange))
+        .padding([SPACE_SM, SPACE_MD])
+        .style(button_style::primary(*theme));
+
+    let dialog_content = column![
+        text(state.change.title())
+            .size(FONT_MD)
+            .color(theme.text_normal),
+        text("Review the changes to your Waybar files before they are written")
+            .size(FONT_SM)
+            .color(theme.text_muted),
+        diff_panel,
+        issues,
+        Space::new().height(SPACE_SM),
+        row![Space::new().width(Length::Fill), cancel_btn, apply_btn]
+            .spacing(SPACE_SM)
+            .align_y(Alignment::Center),
+    ]
+    .spacing(SPACE_SM)
+    .width(Length::Fixed(CHANGE_PREVIEW_DIALOG_WIDTH));
+
+    let dialog = container(dialog_content)
+        .padding(SPACE_LG)
+        .style(cont_style::modal(*theme));
+
+    container(dialog)
+        .center(Length::Fill)
+        .style(cont_style::modal_backdrop(*theme))
+        .into()
+}
+
+/// Colored unified diff lines for each changed file in `preview`.
+pub(super) fn diff_lines<'a>(
+    preview: &'a ConfigPreview,
+    empty_label: &'a str,
+    theme: &AppTheme,
+) -> Column<'a, Message> {
+    let mut diff = column![].spacing(SPACE_XS / 2.0);
+    for change in &preview.changes {
+        diff = diff.push(
+            text(change.path.display().to_string())
+                .size(FONT_XS)
+                .color(theme.text_muted),
+        );
+        for line in change.hunks.lines() {
+            let color = match line.chars().next() {
+                Some('+') => theme.success,
+                Some('-') => theme.danger,
+                Some('@') => theme.info,
+                _ => theme.text_normal,
+            };
+            diff = diff.push(
+                text(line.to_string())
+                    .size(FONT_XS)
+                    .font(Font::MONOSPACE)
+                    .color(color),
+            );
+        }
+        diff = diff.push(Space::new().height(SPACE_SM));
+    }
+    if preview.changes.is_empty() {
+        diff = diff.push(text(empty_label).size(FONT_XS).color(theme.text_muted));
+    }
+    diff
+}
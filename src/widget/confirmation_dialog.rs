@@ -12,13 +12,30 @@ pub fn confirmation_dialog(
     action: &ConfirmationAction,
     theme: &AppTheme,
 ) -> Element<'static, Message> {
-    let (title, message) = match action {
+    let (title, message, confirm_label) = match action {
         ConfirmationAction::UninstallModule { name, .. } => (
             "Uninstall Module",
             format!(
                 "Are you sure you want to uninstall \"{}\"? This action cannot be undone.",
                 name
             ),
+            "Uninstall",
+        ),
+        ConfirmationAction::RemoveExternal(module) => (
+            "Remove Module",
+            format!(
+                "Remove \"{}\" from your Waybar config? Its definition is deleted once no bar uses it.",
+                module.name
+            ),
+            "Remove",
+        ),
+        ConfirmationAction::RestoreBackup(id) => (
+            "Restore Backup",
+            format!(
+                "Replace your Waybar config and stylesheet with backup {id}? \
+                 The current files are backed up first."
+            ),
+            "Restore",
         ),
     };
 
@@ -27,7 +44,7 @@ pub fn confirmation_dialog(
         .padding([SPACE_SM, SPACE_MD])
         .style(button_style::secondary(*theme));
 
-    let confirm_btn = button(text("Uninstall").size(FONT_SM))
+    let confirm_btn = button(text(confirm_label).size(FONT_SM))
         .on_press(Message::ConfirmAction)
         .padding([SPACE_SM, SPACE_MD])
         .style(button_style::danger(*theme));
@@ -0,0 +1,61 @@
+use iced::widget::{Space, button, column, container, row, text};
+use iced::{Alignment, Element, Length};
+
+use crate::app::message::Message;
+use crate::services::Drift;
+use crate::theme::{
+    AppTheme, FONT_SM, FONT_XS, SPACE_MD, SPACE_SM, SPACE_XS, button as btn_style,
+    container as cont_style,
+};
+
+/// Lists the places where `installed.json` and the Waybar files disagree,
+/// each with a button that fixes it.
+pub fn drift_banner<'a>(
+    drift: &'a [Drift],
+    fixing: Option<&'a Drift>,
+    theme: &AppTheme,
+) -> Element<'a, Message> {
+    let title = text(match drift.len() {
+        1 => "Your Waybar files don't match Barforge's records in 1 place".to_string(),
+        n => format!("Your Waybar files don't match Barforge's records in {n} places"),
+    })
+    .size(FONT_SM)
+    .color(theme.warning);
+
+    let mut rows = column![title].spacing(SPACE_XS);
+    for item in drift {
+        let label = if fixing == Some(item) {
+            "Fixing..."
+        } else {
+            "Fix"
+        };
+        let fix_btn = button(text(label).size(FONT_XS))
+            .on_press_maybe(fixing.is_none().then(|| Message::FixDrift(item.clone())))
+            .style(btn_style::secondary(*theme))
+            .padding([SPACE_XS, SPACE_MD]);
+
+        rows = rows.push(
+            row![
+                column![
+                    text(item.to_string())
+                        .size(FONT_XS)
+                        .color(theme.text_normal),
+                    text(format!("Fix: {}", item.fix_label()))
+                        .size(FONT_XS)
+                        .color(theme.text_faint),
+                ]
+                .spacing(SPACE_XS / 2.0),
+                Space::new().width(Length::Fill),
+                fix_btn,
+            ]
+            .spacing(SPACE_SM)
+            .align_y(Alignment::Center),
+        );
+    }
+
+    container(rows)
+        .padding(SPACE_MD)
+        .width(Length::Fill)
+        .style(cont_style::warning_banner(*theme))
+        .into()
+}
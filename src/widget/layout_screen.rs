@@ -0,0 +1,247 @@
+use iced::widget::{
+    Space, button, column, container, mouse_area, pick_list, row, scrollable, text,
+};
+use iced::{Alignment, Element, Length, mouse};
+
+use crate::app::message::Message;
+use crate::app::state::LayoutState;
+use crate::domain::{BarSection, InstalledModule, ModuleCategory};
+use crate::icons::Icon;
+use crate::services::waybar_config::BarInfo;
+use crate::theme::{
+    AppTheme, FONT_2XL, FONT_2XS, FONT_LG, FONT_SM, FONT_XS, PickListColors, RADIUS_SM, SPACE_LG,
+    SPACE_MD, SPACE_SM, SPACE_XL, SPACE_XS, button as btn_style, container as cont_style,
+    menu_style, pick_list_style,
+};
+
+use super::{badge_color, badge_text_color, empty_state_dynamic};
+
+pub fn layout_screen(
+    state: &LayoutState,
+    bars: &[BarInfo],
+    installed: &[InstalledModule],
+    theme: &AppTheme,
+) -> Element<'static, Message> {
+    let picker_colors = PickListColors::from_theme(theme);
+
+    let bar_picker: Element<Message> = if bars.len() > 1 {
+        let selected = bars
+            .iter()
+            .find(|bar| match &state.bar {
+                Some(id) => bar.matches(id),
+                None => bar.index == 0,
+            })
+            .cloned();
+        pick_list(bars.to_vec(), selected, Message::LayoutSelectBar)
+            .padding([SPACE_SM / 2.0, SPACE_SM])
+            .text_size(FONT_XS)
+            .style(pick_list_style(picker_colors, RADIUS_SM))
+            .menu_style(menu_style(picker_colors, RADIUS_SM, 0.2, 4.0))
+            .into()
+    } else {
+        Space::new().width(0).into()
+    };
+
+    let header = row![
+        column![
+            text("Bar Layout").size(FONT_2XL).color(theme.text_normal),
+            text("Drag entries to reorder them or move them between sections")
+                .size(FONT_XS)
+                .color(theme.text_faint),
+        ]
+        .spacing(SPACE_XS),
+        Space::new().width(Length::Fill),
+        bar_picker,
+    ]
+    .align_y(Alignment::Center);
+
+    let body: Element<Message> = if let Some(error) = &state.error {
+        empty_state_dynamic(
+            Icon::Error,
+            "Could not read the Waybar config",
+            error.clone(),
+            theme,
+        )
+    } else if state.loading && state.lanes.is_empty() {
+        container(text("Loading...").size(FONT_SM).color(theme.text_muted))
+            .padding(SPACE_LG)
+            .into()
+    } else {
+        let lanes: Vec<Element<Message>> = BarSection::all()
+            .iter()
+            .map(|&section| lane(state, section, installed, theme))
+            .collect();
+        row(lanes).spacing(SPACE_LG).into()
+    };
+
+    scrollable(
+        column![header, body]
+            .spacing(SPACE_XL)
+            .padding(SPACE_XL)
+            .width(Length::Fill),
+    )
+    .height(Length::Fill)
+    .into()
+}
+
+fn lane(
+    state: &LayoutState,
+    section: BarSection,
+    installed: &[InstalledModule],
+    theme: &AppTheme,
+) -> Element<'static, Message> {
+    let entries: &[String] = state
+        .lane(section)
+        .map(|lane| lane.entries.as_slice())
+        .unwrap_or_default();
+    let is_dragging = state.dragging.is_some();
+
+    let title = row![
+        text(section.to_string())
+            .size(FONT_LG)
+            .color(theme.text_normal),
+        Space::new().width(Length::Fill),
+        text(entries.len().to_string())
+            .size(FONT_XS)
+            .color(theme.text_faint),
+    ]
+    .align_y(Alignment::Center);
+
+    let mut items: Vec<Element<Message>> = vec![title.into()];
+    items.extend(entries.iter().enumerate().map(|(index, name)| {
+        let managed = installed.iter().any(|m| &m.waybar_module_name == name);
+        let is_drop_target = is_dragging
+            && state.dragging != Some((section, index))
+            && state.drag_over == Some((section, index));
+        layout_entry(
+            name,
+            section,
+            index,
+            entries.len(),
+            managed,
+            is_drop_target,
+            theme,
+        )
+    }));
+
+    let end = (section, entries.len());
+    let end_highlighted = is_dragging && state.drag_over == Some(end);
+    let drop_theme = *theme;
+    let drop_zone = container(
+        text(if is_dragging { "Drop here" } else { "" })
+            .size(FONT_XS)
+            .color(theme.text_faint),
+    )
+    .center_x(Length::Fill)
+    .padding(SPACE_MD)
+    .style(move |t: &iced::Theme| {
+        if end_highlighted {
+            cont_style::card_hover(drop_theme)(t)
+        } else {
+            container::Style::default()
+        }
+    });
+    items.push(
+        mouse_area(drop_zone)
+            .on_enter(Message::LayoutDragOver(end.0, end.1))
+            .on_exit(Message::LayoutDragLeave(end.0, end.1))
+            .into(),
+    );
+
+    container(column(items).spacing(SPACE_SM))
+        .style(cont_style::card(*theme))
+        .padding(SPACE_MD)
+        .width(Length::FillPortion(1))
+        .into()
+}
+
+fn layout_entry(
+    name: &str,
+    section: BarSection,
+    index: usize,
+    lane_len: usize,
+    managed: bool,
+    is_drop_target: bool,
+    theme: &AppTheme,
+) -> Element<'static, Message> {
+    let sections = BarSection::all();
+    let position = sections.iter().position(|s| *s == section).unwrap_or(0);
+    let previous = position.checked_sub(1).map(|i| sections[i]);
+    let next = sections.get(position + 1).copied();
+
+    let arrow = |label: &'static str, target: Option<(BarSection, usize)>| {
+        button(text(label).size(FONT_2XS))
+            .on_press_maybe(target.map(|to| Message::LayoutMoveEntry {
+                from: (section, index),
+                to,
+            }))
+            .style(btn_style::ghost(*theme))
+            .padding([SPACE_XS / 2.0, SPACE_XS])
+    };
+
+    let controls = row![
+        arrow("\u{25C0}", previous.map(|s| (s, usize::MAX))),
+        arrow("\u{25B2}", index.checked_sub(1).map(|i| (section, i))),
+        arrow(
+            "\u{25BC}",
+            (index + 1 < lane_len).then_some((section, index + 1))
+        ),
+        arrow("\u{25B6}", next.map(|s| (s, 0))),
+    ]
+    .spacing(SPACE_XS / 2.0);
+
+    let badge: Element<Message> = if managed {
+        container(
+            text("Barforge")
+                .size(FONT_2XS)
+                .color(badge_text_color(ModuleCategory::Custom)),
+        )
+        .padding([2.0, SPACE_SM])
+        .style(cont_style::badge(badge_color(ModuleCategory::Custom)))
+        .into()
+    } else {
+        container(text("Waybar").size(FONT_2XS).color(theme.text_muted))
+            .padding([2.0, SPACE_SM])
+            .style(cont_style::badge_outline(theme.text_faint))
+            .into()
+    };
+
+    let handle = mouse_area(
+        container(text("\u{2807}").size(FONT_SM).color(theme.text_faint))
+            .padding([SPACE_XS, SPACE_XS]),
+    )
+    .on_press(Message::LayoutDrag(section, index))
+    .interaction(mouse::Interaction::Grab);
+
+    let entry_theme = *theme;
+    let card = container(
+        column![
+            row![
+                handle,
+                text(name.to_string())
+                    .size(FONT_SM)
+                    .color(theme.text_normal),
+                Space::new().width(Length::Fill),
+                badge,
+            ]
+            .spacing(SPACE_SM)
+            .align_y(Alignment::Center),
+            controls,
+        ]
+        .spacing(SPACE_XS),
+    )
+    .padding(SPACE_SM)
+    .width(Length::Fill)
+    .style(move |t: &iced::Theme| {
+        if is_drop_target {
+            cont_style::card_hover(entry_theme)(t)
+        } else {
+            cont_style::list_item(entry_theme)(t)
+        }
+    });
+
+    mouse_area(card)
+        .on_enter(Message::LayoutDragOver(section, index))
+        .on_exit(Message::LayoutDragLeave(section, index))
+        .into()
+}
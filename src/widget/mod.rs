@@ -1,26 +1,36 @@
 mod author_card;
 mod category_style;
+mod change_preview_dialog;
 mod confirmation_dialog;
+mod drift_banner;
 mod empty_state;
+mod layout_screen;
 mod module_card;
 mod module_detail_screen;
 mod module_row;
 mod module_table;
 mod notification;
 mod preferences_modal;
+mod script_output_dialog;
+mod script_review_dialog;
 mod settings_screen;
 mod sidebar;
 mod skeleton_card;
 
 pub use author_card::{author_card, author_mini};
+pub use change_preview_dialog::change_preview_dialog;
 pub use confirmation_dialog::confirmation_dialog;
+pub use drift_banner::drift_banner;
 pub use empty_state::{empty_state, empty_state_dynamic, empty_state_with_action};
+pub use layout_screen::layout_screen;
 pub use module_card::module_card;
 pub use module_detail_screen::module_detail_screen;
-pub use module_row::module_row;
+pub use module_row::{external_row, module_row};
 pub use module_table::module_table;
 pub use notification::notification_toast;
 pub use preferences_modal::preferences_modal;
+pub use script_output_dialog::script_output_dialog;
+pub use script_review_dialog::script_review_dialog;
 pub use settings_screen::settings_screen;
 pub use sidebar::sidebar;
 pub use skeleton_card::skeleton_card;
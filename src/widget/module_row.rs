@@ -1,25 +1,45 @@
-use iced::widget::{Space, button, column, container, pick_list, row, text, toggler};
-use iced::{Alignment, Element, Length};
+use iced::widget::{Space, button, column, container, mouse_area, pick_list, row, text, toggler};
+use iced::{Alignment, Element, Length, mouse};
 
 use crate::app::message::Message;
 use crate::app::state::ConfirmationAction;
-use crate::domain::{BarSection, InstalledModule};
+use crate::domain::{BarSection, ExternalModule, InstalledModule, ModuleMove, ModuleVersion};
+use crate::services::waybar_config::BarInfo;
 use crate::theme::{
     AppTheme, FONT_2XS, FONT_SM, FONT_XS, PickListColors, RADIUS_SM, SPACE_LG, SPACE_MD, SPACE_SM,
     button as btn_style, container as cont_style, menu_style, pick_list_style,
 };
 
+#[derive(Debug, Clone, PartialEq)]
+struct RollbackTarget(ModuleVersion);
+
+impl std::fmt::Display for RollbackTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Roll back to {}", self.0)
+    }
+}
+
 pub fn module_row(
     module: &InstalledModule,
     is_toggling: bool,
     is_uninstalling: bool,
+    is_rolling_back: bool,
+    bars: &[BarInfo],
+    is_drop_target: bool,
     theme: &AppTheme,
 ) -> Element<'static, Message> {
     let uuid = module.uuid.to_string();
+    let uuid_up = module.uuid.clone();
+    let uuid_down = module.uuid.clone();
+    let uuid_drag = module.uuid.clone();
+    let uuid_hover = module.uuid.clone();
+    let uuid_leave = module.uuid.clone();
     let uuid_toggle = module.uuid.clone();
     let uuid_position = module.uuid.clone();
+    let uuid_bar = module.uuid.clone();
     let uuid_prefs = module.uuid.clone();
     let uuid_uninstall = uuid.clone();
+    let uuid_rollback = module.uuid.clone();
     let has_preferences = module.has_preferences;
     let name = module.waybar_module_name.clone();
     let name_for_confirm = name.clone();
@@ -29,6 +49,8 @@ pub fn module_row(
         .as_ref()
         .map(|p| p.section)
         .unwrap_or(BarSection::Center);
+    let current_bar = module.position.as_ref().and_then(|p| p.bar.clone());
+    let bar_for_section = current_bar.clone();
 
     let status_text = if enabled {
         text("Enabled").size(FONT_2XS).color(theme.success)
@@ -56,6 +78,7 @@ pub fn module_row(
         Message::SetModulePosition {
             uuid: uuid_position.clone(),
             section,
+            bar: bar_for_section.clone(),
         }
     })
     .padding([SPACE_SM / 2.0, SPACE_SM])
@@ -63,6 +86,62 @@ pub fn module_row(
     .style(pick_list_style(picker_colors, RADIUS_SM))
     .menu_style(menu_style(picker_colors, RADIUS_SM, 0.2, 4.0));
 
+    let bar_widget: Element<Message> = if bars.len() > 1 {
+        let selected_bar = bars
+            .iter()
+            .find(|bar| match &current_bar {
+                Some(id) => bar.matches(id),
+                None => bar.index == 0,
+            })
+            .cloned();
+
+        pick_list(bars.to_vec(), selected_bar, move |bar| {
+            Message::SetModulePosition {
+                uuid: uuid_bar.clone(),
+                section: current_section,
+                bar: Some(bar.id()),
+            }
+        })
+        .padding([SPACE_SM / 2.0, SPACE_SM])
+        .text_size(FONT_XS)
+        .style(pick_list_style(picker_colors, RADIUS_SM))
+        .menu_style(menu_style(picker_colors, RADIUS_SM, 0.2, 4.0))
+        .into()
+    } else {
+        Space::new().width(0).into()
+    };
+
+    let order_widget: Element<Message> = if enabled {
+        row![
+            mouse_area(
+                container(text("\u{2807}").size(FONT_SM).color(theme.text_faint))
+                    .padding([SPACE_SM / 2.0, SPACE_SM / 2.0]),
+            )
+            .on_press(Message::DragModule(uuid_drag))
+            .interaction(mouse::Interaction::Grab),
+            column![
+                button(text("\u{25B2}").size(FONT_2XS))
+                    .on_press(Message::MoveModule {
+                        uuid: uuid_up,
+                        to: ModuleMove::Up,
+                    })
+                    .style(btn_style::ghost(*theme))
+                    .padding([0.0, SPACE_SM / 2.0]),
+                button(text("\u{25BC}").size(FONT_2XS))
+                    .on_press(Message::MoveModule {
+                        uuid: uuid_down,
+                        to: ModuleMove::Down,
+                    })
+                    .style(btn_style::ghost(*theme))
+                    .padding([0.0, SPACE_SM / 2.0]),
+            ],
+        ]
+        .align_y(Alignment::Center)
+        .into()
+    } else {
+        Space::new().width(0).into()
+    };
+
     let prefs_widget: Element<Message> = if has_preferences {
         button(text("\u{2699}").size(FONT_SM))
             .on_press(Message::OpenPreferences(uuid_prefs))
@@ -73,6 +152,38 @@ pub fn module_row(
         Space::new().width(0).into()
     };
 
+    let rollback_targets: Vec<RollbackTarget> = module
+        .previous_versions
+        .iter()
+        .cloned()
+        .map(RollbackTarget)
+        .collect();
+
+    let rollback_widget: Element<Message> = if is_rolling_back {
+        container(
+            text("Rolling back...")
+                .size(FONT_XS)
+                .color(theme.text_muted),
+        )
+        .padding([SPACE_SM, SPACE_MD])
+        .into()
+    } else if rollback_targets.is_empty() {
+        Space::new().width(0).into()
+    } else {
+        pick_list(rollback_targets, None::<RollbackTarget>, move |target| {
+            Message::RollbackModule {
+                uuid: uuid_rollback.clone(),
+                version: target.0,
+            }
+        })
+        .placeholder("Roll back")
+        .padding([SPACE_SM / 2.0, SPACE_SM])
+        .text_size(FONT_XS)
+        .style(pick_list_style(picker_colors, RADIUS_SM))
+        .menu_style(menu_style(picker_colors, RADIUS_SM, 0.2, 4.0))
+        .into()
+    };
+
     let uninstall_widget: Element<Message> = if is_uninstalling {
         container(text("Removing...").size(FONT_XS).color(theme.text_muted))
             .padding([SPACE_SM, SPACE_MD])
@@ -101,11 +212,23 @@ pub fn module_row(
     ]
     .spacing(SPACE_SM / 2.0);
 
-    container(
+    let row_theme = *theme;
+    let row_style = move |t: &iced::Theme| {
+        if is_drop_target {
+            cont_style::card_hover(row_theme)(t)
+        } else {
+            cont_style::list_item(row_theme)(t)
+        }
+    };
+
+    let row_container = container(
         row![
+            order_widget,
             info_column,
             Space::new().width(Length::Fill),
+            bar_widget,
             position_picker,
+            rollback_widget,
             prefs_widget,
             toggle_widget,
             uninstall_widget,
@@ -114,6 +237,102 @@ pub fn module_row(
         .padding(SPACE_MD)
         .align_y(Alignment::Center),
     )
+    .style(row_style)
+    .width(Length::Fill);
+
+    mouse_area(row_container)
+        .on_enter(Message::DragOver(uuid_hover))
+        .on_exit(Message::DragLeave(uuid_leave))
+        .into()
+}
+
+pub fn external_row(module: &ExternalModule, theme: &AppTheme) -> Element<'static, Message> {
+    let module_toggle = module.clone();
+    let module_position = module.clone();
+    let module_adopt = module.clone();
+    let module_remove = module.clone();
+    let enabled = module.enabled;
+
+    let kind = if module.is_custom_module() {
+        "Custom"
+    } else {
+        "Built-in"
+    };
+    let location = match &module.position.bar {
+        Some(bar) => format!("{kind} \u{00B7} {bar}"),
+        None => kind.to_string(),
+    };
+
+    let status_text = if enabled {
+        text("Enabled").size(FONT_2XS).color(theme.success)
+    } else {
+        text("Disabled").size(FONT_2XS).color(theme.text_faint)
+    };
+
+    let picker_colors = PickListColors::from_theme(theme);
+    let position_picker = pick_list(
+        BarSection::all(),
+        Some(module.position.section),
+        move |section| Message::MoveExternal {
+            module: module_position.clone(),
+            section,
+        },
+    )
+    .padding([SPACE_SM / 2.0, SPACE_SM])
+    .text_size(FONT_XS)
+    .style(pick_list_style(picker_colors, RADIUS_SM))
+    .menu_style(menu_style(picker_colors, RADIUS_SM, 0.2, 4.0));
+
+    let adopt_widget: Element<Message> = if module.can_adopt() {
+        button(text("Adopt").size(FONT_XS))
+            .on_press(Message::AdoptExternal(module_adopt))
+            .style(btn_style::secondary(*theme))
+            .padding([SPACE_SM, SPACE_MD])
+            .into()
+    } else {
+        Space::new().width(0).into()
+    };
+
+    let toggle_widget = toggler(enabled)
+        .on_toggle(move |new_enabled| Message::ToggleExternal {
+            module: module_toggle.clone(),
+            enabled: new_enabled,
+        })
+        .size(20.0);
+
+    let remove_widget = button(text("Remove").size(FONT_XS))
+        .on_press(Message::RequestConfirmation(
+            ConfirmationAction::RemoveExternal(module_remove),
+        ))
+        .style(btn_style::danger(*theme))
+        .padding([SPACE_SM, SPACE_MD]);
+
+    let info_column = column![
+        text(module.name.clone())
+            .size(FONT_SM)
+            .color(theme.text_normal),
+        row![
+            text(location).size(FONT_XS).color(theme.text_faint),
+            Space::new().width(SPACE_MD),
+            status_text,
+        ]
+        .spacing(SPACE_SM),
+    ]
+    .spacing(SPACE_SM / 2.0);
+
+    container(
+        row![
+            info_column,
+            Space::new().width(Length::Fill),
+            position_picker,
+            adopt_widget,
+            toggle_widget,
+            remove_widget,
+        ]
+        .spacing(SPACE_LG)
+        .padding(SPACE_MD)
+        .align_y(Alignment::Center),
+    )
     .style(cont_style::list_item(*theme))
     .width(Length::Fill)
     .into()
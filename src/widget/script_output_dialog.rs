@@ -0,0 +1,69 @@
+use iced::widget::{Space, button, column, container, row, scrollable, text};
+use iced::{Alignment, Element, Font, Length};
+
+use crate::app::message::Message;
+use crate::app::state::ScriptOutputState;
+use crate::theme::{
+    AppTheme, FONT_MD, FONT_SM, FONT_XS, SCRIPT_OUTPUT_DIALOG_WIDTH, SCRIPT_OUTPUT_MAX_HEIGHT,
+    SPACE_LG, SPACE_MD, SPACE_SM, SPACE_XS, button as button_style, container as cont_style,
+};
+
+pub fn script_output_dialog<'a>(
+    output: &'a ScriptOutputState,
+    theme: &AppTheme,
+) -> Element<'a, Message> {
+    let result = &output.result;
+
+    let (status, status_color) = match (result.success, result.exit_code) {
+        (true, _) => ("Script finished successfully".to_string(), theme.success),
+        (false, Some(code)) => (format!("Script exited with code {code}"), theme.danger),
+        (false, None) => ("Script was terminated".to_string(), theme.danger),
+    };
+
+    let mut log = column![].spacing(SPACE_XS);
+    for (label, content) in [("stdout", &result.stdout), ("stderr", &result.stderr)] {
+        if content.trim().is_empty() {
+            continue;
+        }
+        log = log.push(text(label).size(FONT_XS).color(theme.text_muted));
+        log = log.push(
+            text(content.trim_end())
+                .size(FONT_XS)
+                .font(Font::MONOSPACE)
+                .color(theme.text_normal),
+        );
+    }
+    if result.stdout.trim().is_empty() && result.stderr.trim().is_empty() {
+        log = log.push(text("No output").size(FONT_XS).color(theme.text_muted));
+    }
+
+    let log_panel = container(scrollable(log).height(Length::Shrink))
+        .padding(SPACE_SM)
+        .max_height(SCRIPT_OUTPUT_MAX_HEIGHT)
+        .width(Length::Fill)
+        .style(cont_style::input(*theme));
+
+    let close_btn = button(text("Close").size(FONT_SM))
+        .on_press(Message::CloseScriptOutput)
+        .padding([SPACE_SM, SPACE_MD])
+        .style(button_style::secondary(*theme));
+
+    let dialog_content = column![
+        text(&output.title).size(FONT_MD).color(theme.text_normal),
+        text(status).size(FONT_SM).color(status_color),
+        log_panel,
+        Space::new().height(SPACE_SM),
+        row![Space::new().width(Length::Fill), close_btn].align_y(Alignment::Center),
+    ]
+    .spacing(SPACE_SM)
+    .width(Length::Fixed(SCRIPT_OUTPUT_DIALOG_WIDTH));
+
+    let dialog = container(dialog_content)
+        .padding(SPACE_LG)
+        .style(cont_style::modal(*theme));
+
+    container(dialog)
+        .center(Length::Fill)
+        .style(cont_style::modal_backdrop(*theme))
+        .into()
+}
@@ -0,0 +1,106 @@
+use iced::widget::{Column, Space, button, checkbox, column, container, row, scrollable, text};
+use iced::{Alignment, Element, Font, Length};
+
+use crate::app::message::Message;
+use crate::app::state::ScriptReviewState;
+use crate::services::ScriptReview;
+use crate::theme::{
+    AppTheme, FONT_MD, FONT_SM, FONT_XS, SCRIPT_OUTPUT_DIALOG_WIDTH, SCRIPT_OUTPUT_MAX_HEIGHT,
+    SPACE_LG, SPACE_MD, SPACE_SM, SPACE_XS, button as button_style, checkbox as chk_style,
+    container as cont_style,
+};
+
+pub fn script_review_dialog<'a>(
+    state: &'a ScriptReviewState,
+    theme: &AppTheme,
+) -> Element<'a, Message> {
+    let cancel_btn = button(text("Cancel").size(FONT_SM))
+        .on_press(Message::CancelScriptReview)
+        .padding([SPACE_SM, SPACE_MD])
+        .style(button_style::secondary(*theme));
+
+    let install_btn = button(text("Install").size(FONT_SM))
+        .on_press_maybe(state.approved.then_some(Message::ConfirmScriptReview))
+        .padding([SPACE_SM, SPACE_MD])
+        .style(button_style::danger(*theme));
+
+    let dialog_content = column![
+        text(format!("Review scripts in {}", state.uuid))
+            .size(FONT_MD)
+            .color(theme.text_normal),
+        text("The install script will not run until you approve what Barforge found")
+            .size(FONT_SM)
+            .color(theme.text_muted),
+        script_findings(&state.review, state.approved, theme),
+        Space::new().height(SPACE_SM),
+        row![Space::new().width(Length::Fill), cancel_btn, install_btn]
+            .spacing(SPACE_SM)
+            .align_y(Alignment::Center),
+    ]
+    .spacing(SPACE_SM)
+    .width(Length::Fixed(SCRIPT_OUTPUT_DIALOG_WIDTH));
+
+    let dialog = container(dialog_content)
+        .padding(SPACE_LG)
+        .style(cont_style::modal(*theme));
+
+    container(dialog)
+        .center(Length::Fill)
+        .style(cont_style::modal_backdrop(*theme))
+        .into()
+}
+
+/// The risky lines in each script and the box that approves them.
+pub(super) fn script_findings<'a>(
+    review: &'a ScriptReview,
+    approved: bool,
+    theme: &AppTheme,
+) -> Column<'a, Message> {
+    let mut findings = column![].spacing(SPACE_XS / 2.0);
+    for script in &review.scripts {
+        findings = findings.push(
+            text(script.path.display().to_string())
+                .size(FONT_XS)
+                .color(theme.text_muted),
+        );
+        for finding in &script.findings {
+            findings = findings.push(
+                row![
+                    text(format!("{:>4}", finding.line))
+                        .size(FONT_XS)
+                        .font(Font::MONOSPACE)
+                        .color(theme.text_faint),
+                    text(finding.pattern.to_string())
+                        .size(FONT_XS)
+                        .font(Font::MONOSPACE)
+                        .color(theme.warning),
+                ]
+                .spacing(SPACE_SM),
+            );
+        }
+        findings = findings.push(Space::new().height(SPACE_SM));
+    }
+
+    let panel = container(scrollable(findings).height(Length::Shrink))
+        .padding(SPACE_SM)
+        .max_height(SCRIPT_OUTPUT_MAX_HEIGHT)
+        .width(Length::Fill)
+        .style(cont_style::input(*theme));
+
+    let approve = checkbox(approved)
+        .label("I have reviewed these lines and trust this module")
+        .on_toggle(Message::ApproveScripts)
+        .style(chk_style::themed(*theme));
+
+    column![
+        text(format!(
+            "{} risky patterns found in the module's scripts:",
+            review.finding_count()
+        ))
+        .size(FONT_SM)
+        .color(theme.warning),
+        panel,
+        approve,
+    ]
+    .spacing(SPACE_SM)
+}
@@ -1,17 +1,31 @@
-use iced::widget::{Space, button, checkbox, column, container, row, scrollable, text};
+use iced::widget::{Space, button, checkbox, column, container, pick_list, row, scrollable, text};
 use iced::{Alignment, Element, Length};
 
 use crate::app::message::Message;
+use crate::app::state::{BackupsState, ConfirmationAction};
 use crate::icons::Icon;
 use crate::theme::{
-    AppTheme, FONT_2XL, FONT_LG, FONT_MD, FONT_SM, FONT_XS, ICON_SM, SETTINGS_CONTENT_MAX_WIDTH,
-    SPACE_LG, SPACE_MD, SPACE_SM, SPACE_XL, SPACE_XS, button as btn_style, checkbox as chk_style,
-    container as cont_style,
+    AppTheme, CHANGE_PREVIEW_MAX_HEIGHT, FONT_2XL, FONT_LG, FONT_MD, FONT_SM, FONT_XS, ICON_SM,
+    PickListColors, RADIUS_SM, SETTINGS_CONTENT_MAX_WIDTH, SPACE_LG, SPACE_MD, SPACE_SM, SPACE_XL,
+    SPACE_XS, button as btn_style, checkbox as chk_style, container as cont_style, menu_style,
+    pick_list_style,
 };
 
+use super::change_preview_dialog::diff_lines;
+
 const GITHUB_URL: &str = "https://github.com/jtaw5649/barforge-app";
 
-pub fn settings_screen(theme: &AppTheme, tray_enabled: bool) -> Element<'_, Message> {
+const BACKUP_RETENTION_CHOICES: [usize; 6] = [0, 5, 10, 20, 50, 100];
+
+pub fn settings_screen<'a>(
+    theme: &'a AppTheme,
+    tray_enabled: bool,
+    allow_unsigned_updates: bool,
+    sandbox_module_commands: bool,
+    seccomp_filter: bool,
+    backup_retention: usize,
+    backups: &'a BackupsState,
+) -> Element<'a, Message> {
     let header = text("Settings").size(FONT_2XL).color(theme.text_normal);
 
     let data_label = text("Data").size(FONT_LG).color(theme.text_normal);
@@ -123,10 +137,73 @@ pub fn settings_screen(theme: &AppTheme, tray_enabled: bool) -> Element<'_, Mess
     .padding(SPACE_LG)
     .width(Length::Fill);
 
-    let content = column![header, appearance_section, data_section, about_section]
-        .spacing(SPACE_XL)
-        .padding(SPACE_XL)
-        .max_width(SETTINGS_CONTENT_MAX_WIDTH);
+    let security_label = text("Security").size(FONT_LG).color(theme.text_normal);
+
+    let unsigned_checkbox = checkbox(allow_unsigned_updates)
+        .label("Allow unsigned updates")
+        .on_toggle(Message::ToggleUnsignedUpdates)
+        .style(chk_style::themed(*theme));
+
+    let unsigned_desc = text(
+        "Download updates straight from GitHub when no signed package is published. \
+         These skip signature, checksum and revocation checks.",
+    )
+    .size(FONT_XS)
+    .color(theme.text_faint);
+
+    let sandbox_checkbox = checkbox(sandbox_module_commands)
+        .label("Sandbox module commands")
+        .on_toggle(Message::ToggleSandboxCommands)
+        .style(chk_style::themed(*theme));
+
+    let sandbox_desc = text(
+        "Run the commands Waybar starts for a module with only the permissions its \
+         Package.toml declares. Applies to modules enabled or updated from now on.",
+    )
+    .size(FONT_XS)
+    .color(theme.text_faint);
+
+    let seccomp_checkbox = checkbox(seccomp_filter)
+        .label("Filter system calls in sandboxes")
+        .on_toggle(Message::ToggleSeccompFilter)
+        .style(chk_style::themed(*theme));
+
+    let seccomp_desc = text(
+        "Stop sandboxed scripts and commands from tracing processes, using io_uring, \
+         opening raw sockets or opening network sockets they were not granted.",
+    )
+    .size(FONT_XS)
+    .color(theme.text_faint);
+
+    let security_section = container(
+        column![
+            security_label,
+            Space::new().height(SPACE_MD),
+            column![unsigned_checkbox, unsigned_desc].spacing(SPACE_XS),
+            Space::new().height(SPACE_SM),
+            column![sandbox_checkbox, sandbox_desc].spacing(SPACE_XS),
+            Space::new().height(SPACE_SM),
+            column![seccomp_checkbox, seccomp_desc].spacing(SPACE_XS),
+        ]
+        .spacing(SPACE_XS),
+    )
+    .style(cont_style::card(*theme))
+    .padding(SPACE_LG)
+    .width(Length::Fill);
+
+    let backups_section = backups_section(theme, backup_retention, backups);
+
+    let content = column![
+        header,
+        appearance_section,
+        security_section,
+        backups_section,
+        data_section,
+        about_section
+    ]
+    .spacing(SPACE_XL)
+    .padding(SPACE_XL)
+    .max_width(SETTINGS_CONTENT_MAX_WIDTH);
 
     scrollable(
         container(content)
@@ -137,3 +214,135 @@ pub fn settings_screen(theme: &AppTheme, tray_enabled: bool) -> Element<'_, Mess
     .height(Length::Fill)
     .into()
 }
+
+fn backups_section<'a>(
+    theme: &'a AppTheme,
+    backup_retention: usize,
+    state: &'a BackupsState,
+) -> Element<'a, Message> {
+    let picker_colors = PickListColors::from_theme(theme);
+
+    let backups_label = text("Backups").size(FONT_LG).color(theme.text_normal);
+
+    let retention_picker = pick_list(
+        BACKUP_RETENTION_CHOICES,
+        Some(backup_retention),
+        Message::SetBackupRetention,
+    )
+    .padding([SPACE_XS, SPACE_SM])
+    .text_size(FONT_SM)
+    .style(pick_list_style(picker_colors, RADIUS_SM))
+    .menu_style(menu_style(picker_colors, RADIUS_SM, 0.2, 4.0));
+
+    let prune_btn = button(text("Prune Now").size(FONT_SM))
+        .on_press_maybe((!state.backups.is_empty()).then_some(Message::PruneBackups))
+        .style(btn_style::secondary(*theme))
+        .padding([SPACE_SM, SPACE_MD]);
+
+    let retention_row = row![
+        text("Backups to keep")
+            .size(FONT_MD)
+            .color(theme.text_muted),
+        Space::new().width(Length::Fill),
+        retention_picker,
+        prune_btn,
+    ]
+    .spacing(SPACE_SM)
+    .align_y(Alignment::Center);
+
+    let retention_desc = text(
+        "Barforge copies your Waybar config and stylesheet here before changing them. \
+         Set to 0 to stop taking backups.",
+    )
+    .size(FONT_XS)
+    .color(theme.text_faint);
+
+    let mut list = column![].spacing(SPACE_SM);
+    if state.loading && state.backups.is_empty() {
+        list = list.push(
+            text("Loading backups...")
+                .size(FONT_SM)
+                .color(theme.text_muted),
+        );
+    } else if let Some(error) = &state.error {
+        list = list.push(text(error.as_str()).size(FONT_SM).color(theme.danger));
+    } else if state.backups.is_empty() {
+        list = list.push(text("No backups yet").size(FONT_SM).color(theme.text_muted));
+    }
+
+    for backup in &state.backups {
+        let selected = state.selected.as_ref() == Some(&backup.id);
+        let created = backup
+            .created_at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        let file_count = backup.files.iter().filter(|f| f.stored.is_some()).count();
+
+        let diff_btn = button(text(if selected { "Hide" } else { "Diff" }).size(FONT_SM))
+            .on_press(Message::SelectBackup(backup.id.clone()))
+            .style(btn_style::secondary(*theme))
+            .padding([SPACE_XS, SPACE_MD]);
+
+        let restore_btn = button(text("Restore").size(FONT_SM))
+            .on_press_maybe(state.restoring.is_none().then(|| {
+                Message::RequestConfirmation(ConfirmationAction::RestoreBackup(backup.id.clone()))
+            }))
+            .style(btn_style::secondary(*theme))
+            .padding([SPACE_XS, SPACE_MD]);
+
+        list = list.push(
+            row![
+                column![
+                    text(created).size(FONT_SM).color(theme.text_normal),
+                    text(format!("{} · {file_count} files", backup.id))
+                        .size(FONT_XS)
+                        .color(theme.text_faint),
+                ]
+                .spacing(SPACE_XS / 2.0),
+                Space::new().width(Length::Fill),
+                diff_btn,
+                restore_btn,
+            ]
+            .spacing(SPACE_SM)
+            .align_y(Alignment::Center),
+        );
+
+        if selected {
+            let diff: Element<'a, Message> = match &state.diff {
+                None => text("Loading diff...")
+                    .size(FONT_XS)
+                    .color(theme.text_muted)
+                    .into(),
+                Some(Err(e)) => text(e.as_str()).size(FONT_XS).color(theme.danger).into(),
+                Some(Ok(preview)) => {
+                    scrollable(diff_lines(preview, "Matches the current files", theme))
+                        .height(Length::Shrink)
+                        .into()
+                }
+            };
+            list = list.push(
+                container(diff)
+                    .padding(SPACE_SM)
+                    .max_height(CHANGE_PREVIEW_MAX_HEIGHT)
+                    .width(Length::Fill)
+                    .style(cont_style::input(*theme)),
+            );
+        }
+    }
+
+    container(
+        column![
+            backups_label,
+            Space::new().height(SPACE_MD),
+            column![retention_row, retention_desc].spacing(SPACE_XS),
+            Space::new().height(SPACE_SM),
+            list,
+        ]
+        .spacing(SPACE_XS),
+    )
+    .style(cont_style::card(*theme))
+    .padding(SPACE_LG)
+    .width(Length::Fill)
+    .into()
+}
@@ -152,6 +152,31 @@ pub fn sidebar(
             .padding([SPACE_MD, SPACE_SM])
     };
 
+    let layout_btn = {
+        let is_active = matches!(current, Screen::Layout);
+        let color = if is_active {
+            theme.text_normal
+        } else {
+            theme.text_muted
+        };
+        button(
+            row![
+                Icon::Grid.colored(ICON_MD, color),
+                text("Layout").size(FONT_SM).color(color)
+            ]
+            .spacing(SPACE_SM)
+            .align_y(iced::Alignment::Center),
+        )
+        .on_press(Message::Navigate(Screen::Layout))
+        .style(if is_active {
+            btn_style::sidebar_active(theme_copy)
+        } else {
+            btn_style::sidebar(theme_copy)
+        })
+        .width(Length::Fill)
+        .padding([SPACE_MD, SPACE_SM])
+    };
+
     let nav = column![
         marketplace_label,
         Space::new().height(SPACE_XS),
@@ -160,7 +185,8 @@ pub fn sidebar(
         library_label,
         Space::new().height(SPACE_XS),
         installed_btn,
-        updates_btn
+        updates_btn,
+        layout_btn
     ]
     .padding([0.0, SPACE_SM]);
 
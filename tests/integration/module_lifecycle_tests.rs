@@ -1,4 +1,13 @@
+use std::path::PathBuf;
+
 use barforge::app::state::{LoadingState, Screen};
+use barforge::domain::BarSection;
+use barforge::security::{RiskyPattern, ScriptFinding};
+use barforge::services::waybar_config::{ConfigIssue, LayoutLane};
+use barforge::services::{
+    Backup, ConfigPreview, Drift, FileChange, ReviewedScript, ScriptReview, StyleIssue,
+};
+use barforge::tasks::PendingChange;
 use barforge::testing::{
     InstalledModuleBuilder, RegistryModuleBuilder, test_app, test_app_with_installed,
     test_app_with_registry,
@@ -133,6 +142,309 @@ fn test_screen_label() {
     assert_eq!(Screen::Browse.label(), "Browse");
     assert_eq!(Screen::Installed.label(), "Installed");
     assert_eq!(Screen::Settings.label(), "Settings");
+    assert_eq!(Screen::Layout.label(), "Layout");
+}
+
+fn layout_lane(section: BarSection, entries: &[&str]) -> LayoutLane {
+    LayoutLane {
+        section,
+        entries: entries.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_layout_move_entry_updates_lanes_before_saving() {
+    use barforge::app::handlers::handle_layout_move_entry;
+
+    let mut app = test_app();
+    app.layout.lanes = vec![
+        layout_lane(BarSection::Left, &["sway/workspaces"]),
+        layout_lane(BarSection::Center, &["clock"]),
+        layout_lane(BarSection::Right, &["battery", "network"]),
+    ];
+
+    let _task = handle_layout_move_entry(&mut app, (BarSection::Right, 1), (BarSection::Left, 0));
+
+    let left = app.layout.lane(BarSection::Left).unwrap();
+    assert_eq!(left.entries, vec!["network", "sway/workspaces"]);
+    let right = app.layout.lane(BarSection::Right).unwrap();
+    assert_eq!(right.entries, vec!["battery"]);
+
+    assert!(
+        !app.layout
+            .move_entry((BarSection::Center, 4), (BarSection::Left, 0))
+    );
+}
+
+#[test]
+fn test_layout_load_error_is_shown() {
+    use barforge::app::handlers::handle_layout_loaded;
+
+    let mut app = test_app();
+    app.layout.loading = true;
+
+    let _task = handle_layout_loaded(&mut app, Err("Waybar config not found".to_string()));
+
+    assert!(!app.layout.loading);
+    assert_eq!(app.layout.error.as_deref(), Some("Waybar config not found"));
+}
+
+fn pending_toggle() -> PendingChange {
+    PendingChange::Toggle {
+        uuid: "weather@test".to_string(),
+        enabled: true,
+    }
+}
+
+#[test]
+fn test_change_preview_is_shown_until_cancelled() {
+    use barforge::app::handlers::{handle_cancel_change, handle_change_previewed};
+
+    let mut app = test_app();
+    app.installed.toggling.insert("weather@test".to_string());
+    let preview = ConfigPreview {
+        changes: FileChange::between(
+            PathBuf::from("config.jsonc"),
+            "{}\n",
+            "{\"modules-right\": [\"custom/weather\"]}\n",
+        )
+        .into_iter()
+        .collect(),
+        issues: Vec::new(),
+        ..ConfigPreview::default()
+    };
+
+    let _task = handle_change_previewed(&mut app, pending_toggle(), Ok(preview));
+    assert!(app.change_preview.is_some());
+
+    handle_cancel_change(&mut app);
+    assert!(app.change_preview.is_none());
+    assert!(!app.installed.toggling.contains("weather@test"));
+}
+
+#[test]
+fn test_invalid_change_preview_is_not_applied() {
+    use barforge::app::handlers::{handle_change_previewed, handle_confirm_change};
+
+    let mut app = test_app();
+    app.installed.toggling.insert("weather@test".to_string());
+    let preview = ConfigPreview {
+        changes: Vec::new(),
+        issues: vec![ConfigIssue::MissingDefinition {
+            bar: "Bar 1".to_string(),
+            name: "custom/weather".to_string(),
+        }],
+        ..ConfigPreview::default()
+    };
+
+    let _task = handle_change_previewed(&mut app, pending_toggle(), Ok(preview));
+    let _task = handle_confirm_change(&mut app);
+
+    assert!(app.change_preview.is_none());
+    assert!(!app.installed.toggling.contains("weather@test"));
+}
+
+#[test]
+fn test_style_warnings_are_previewed_but_do_not_block() {
+    use barforge::app::handlers::{handle_change_previewed, handle_confirm_change};
+
+    let mut app = test_app();
+    app.installed.toggling.insert("weather@test".to_string());
+    let preview = ConfigPreview {
+        style_issues: vec![StyleIssue::OutOfScope {
+            uuid: "weather@test".to_string(),
+            selector: "window#waybar".to_string(),
+            line: 1,
+            scope: "custom-weather".to_string(),
+        }],
+        ..ConfigPreview::default()
+    };
+
+    let _task = handle_change_previewed(&mut app, pending_toggle(), Ok(preview));
+    assert!(app.change_preview.is_some());
+
+    let _task = handle_confirm_change(&mut app);
+    assert!(app.change_preview.is_none());
+    assert!(app.installed.toggling.contains("weather@test"));
+}
+
+fn risky_review() -> ScriptReview {
+    ScriptReview {
+        scripts: vec![ReviewedScript {
+            path: PathBuf::from("weather.sh"),
+            findings: vec![ScriptFinding {
+                line: 3,
+                span: 21..25,
+                pattern: RiskyPattern::NetworkCommand("curl".to_string()),
+            }],
+        }],
+    }
+}
+
+#[test]
+fn test_risky_scripts_block_change_until_approved() {
+    use barforge::app::handlers::{
+        handle_approve_scripts, handle_change_previewed, handle_confirm_change,
+    };
+
+    let preview = ConfigPreview {
+        scripts: risky_review(),
+        ..ConfigPreview::default()
+    };
+
+    let mut app = test_app();
+    app.installed.toggling.insert("weather@test".to_string());
+    let _task = handle_change_previewed(&mut app, pending_toggle(), Ok(preview.clone()));
+    assert!(!app.change_preview.as_ref().unwrap().can_apply());
+    let _task = handle_confirm_change(&mut app);
+    assert!(!app.installed.toggling.contains("weather@test"));
+
+    app.installed.toggling.insert("weather@test".to_string());
+    let _task = handle_change_previewed(&mut app, pending_toggle(), Ok(preview));
+    handle_approve_scripts(&mut app, true);
+    assert!(app.change_preview.as_ref().unwrap().can_apply());
+    let _task = handle_confirm_change(&mut app);
+    assert!(app.change_preview.is_none());
+    assert!(app.installed.toggling.contains("weather@test"));
+}
+
+#[test]
+fn test_install_script_review_needs_approval() {
+    use barforge::app::handlers::{
+        handle_approve_scripts, handle_cancel_script_review, handle_confirm_script_review,
+        handle_scripts_need_review,
+    };
+
+    let mut app = test_app();
+    app.module_detail.installing = true;
+    let _task = handle_scripts_need_review(&mut app, "weather@test".to_string(), risky_review());
+    assert_eq!(app.script_review.as_ref().unwrap().review, risky_review());
+
+    let _task = handle_confirm_script_review(&mut app);
+    assert!(app.script_review.is_some());
+
+    handle_approve_scripts(&mut app, true);
+    let _task = handle_confirm_script_review(&mut app);
+    assert!(app.script_review.is_none());
+
+    app.module_detail.installing = true;
+    let _task = handle_scripts_need_review(&mut app, "weather@test".to_string(), risky_review());
+    handle_cancel_script_review(&mut app);
+    assert!(app.script_review.is_none());
+    assert!(!app.module_detail.installing);
+}
+
+fn drift() -> Drift {
+    Drift::NotPlaced {
+        uuid: "weather@test".to_string(),
+        module_name: "custom/weather".to_string(),
+    }
+}
+
+#[test]
+fn test_drift_checked_fills_banner() {
+    use barforge::app::handlers::handle_drift_checked;
+
+    let mut app = test_app();
+    let _task = handle_drift_checked(&mut app, Ok(vec![drift()]));
+    assert_eq!(app.installed.drift, vec![drift()]);
+
+    let _task = handle_drift_checked(&mut app, Err("no config".to_string()));
+    assert_eq!(app.installed.drift, vec![drift()]);
+}
+
+#[test]
+fn test_drift_fix_runs_one_at_a_time() {
+    use barforge::app::handlers::{handle_drift_fixed, handle_fix_drift};
+
+    let mut app = test_app();
+    let _task = handle_fix_drift(&mut app, drift());
+    assert_eq!(app.installed.fixing_drift, Some(drift()));
+
+    let other = Drift::MissingStyle {
+        uuid: "clock@test".to_string(),
+    };
+    let _task = handle_fix_drift(&mut app, other);
+    assert_eq!(app.installed.fixing_drift, Some(drift()));
+
+    let _task = handle_drift_fixed(&mut app, drift(), Err("no config".to_string()));
+    assert!(app.installed.fixing_drift.is_none());
+    assert_eq!(app.notifications.len(), 1);
+}
+
+#[test]
+fn test_failed_change_preview_notifies() {
+    use barforge::app::handlers::handle_change_previewed;
+
+    let mut app = test_app();
+    app.installed.toggling.insert("weather@test".to_string());
+
+    let _task = handle_change_previewed(
+        &mut app,
+        pending_toggle(),
+        Err("Module not found".to_string()),
+    );
+
+    assert!(app.change_preview.is_none());
+    assert!(!app.installed.toggling.contains("weather@test"));
+    assert_eq!(app.notifications.len(), 1);
+}
+
+fn backup(id: &str) -> Backup {
+    Backup {
+        id: id.to_string(),
+        created_at: chrono::Utc::now(),
+        files: Vec::new(),
+    }
+}
+
+#[test]
+fn test_selecting_backup_toggles_its_diff() {
+    use barforge::app::handlers::{
+        handle_backup_diff_loaded, handle_backups_loaded, handle_select_backup,
+    };
+
+    let mut app = test_app();
+    let _task = handle_backups_loaded(&mut app, Ok(vec![backup("b2"), backup("b1")]));
+    assert_eq!(app.backups.backups.len(), 2);
+
+    let _task = handle_select_backup(&mut app, "b1".to_string());
+    let _task = handle_backup_diff_loaded(&mut app, "b2".to_string(), Ok(ConfigPreview::default()));
+    assert!(app.backups.diff.is_none());
+
+    let _task = handle_backup_diff_loaded(&mut app, "b1".to_string(), Ok(ConfigPreview::default()));
+    assert!(app.backups.diff.is_some());
+
+    let _task = handle_select_backup(&mut app, "b1".to_string());
+    assert!(app.backups.selected.is_none());
+    assert!(app.backups.diff.is_none());
+}
+
+#[test]
+fn test_pruned_backup_clears_selection() {
+    use barforge::app::handlers::handle_backups_loaded;
+
+    let mut app = test_app();
+    app.backups.selected = Some("b1".to_string());
+    app.backups.diff = Some(Ok(ConfigPreview::default()));
+
+    let _task = handle_backups_loaded(&mut app, Ok(vec![backup("b2")]));
+
+    assert!(app.backups.selected.is_none());
+    assert!(app.backups.diff.is_none());
+}
+
+#[test]
+fn test_failed_backup_restore_notifies() {
+    use barforge::app::handlers::handle_backup_restored;
+
+    let mut app = test_app();
+    app.backups.restoring = Some("b1".to_string());
+
+    let _task = handle_backup_restored(&mut app, "b1".to_string(), Err("disk full".to_string()));
+
+    assert!(app.backups.restoring.is_none());
+    assert_eq!(app.notifications.len(), 1);
 }
 
 #[test]